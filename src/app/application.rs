@@ -1,6 +1,9 @@
 // src/app/mod.rs
 
-use crate::rendering::browser::elements::element::EventType;
+use crate::rendering::browser::elements::element::{EventType, Gesture, GesturePhase, KeyInput, WheelDelta};
+use crate::rendering::browser::elements::styles::Cursor;
+use crate::rendering::browser::shortcuts::command::Command;
+use crate::rendering::browser::styling::theme_manager::ThemeManager;
 use crate::rendering::renderer::Renderer;
 use crate::window::WindowingSystem;
 use glutin::surface::GlSurface;
@@ -8,9 +11,13 @@ use glutin::config::GlConfig;
 use skia_safe::gpu::gl::FramebufferInfo;
 use skia_safe::Point;
 use std::num::NonZeroU32;
+use std::time::{Duration, Instant};
 use winit::application::ApplicationHandler;
-use winit::event::{ElementState, KeyEvent, Modifiers, MouseButton, WindowEvent};
+use winit::event::{ElementState, KeyEvent, Modifiers, MouseScrollDelta, Touch, TouchPhase, WindowEvent};
 use winit::event_loop::{ActiveEventLoop, ControlFlow};
+use winit::window::CursorIcon;
+
+const FRAME_INTERVAL: Duration = Duration::from_millis(16);
 
 pub struct Application {
     pub windowing: WindowingSystem,
@@ -18,17 +25,22 @@ pub struct Application {
     pub renderer: Renderer,
     pub modifiers: Modifiers,
     pub mouse_position: Option<Point>,
+    pub cursor: Cursor, // Last one set on the window
 }
 
 impl Application {
     pub fn new(mut windowing: WindowingSystem, fb_info: FramebufferInfo) -> Self {
-        let renderer = Renderer::new(
+        let mut renderer = Renderer::new(
             &windowing.window, 
             &mut windowing.gr_context, 
             fb_info, 
             windowing.gl_config.num_samples() as usize, 
             windowing.gl_config.stencil_size() as usize
         );
+        // Start in the OS light/dark preference when the platform reports one
+        if let Some(system_theme) = windowing.window.theme() {
+            renderer.set_theme(ThemeManager::for_system_theme(system_theme));
+        }
 
         Self {
             windowing,
@@ -36,8 +48,71 @@ impl Application {
             renderer,
             modifiers: Modifiers::default(),
             mouse_position: None,
+            cursor: Cursor::default(),
+        }
+    }
+
+    // Shows the cursor of what's under the pointer, or of the page loading; only calls into the window when it changes
+    fn update_cursor(&mut self) {
+        let cursor = self.renderer.get_cursor();
+        if cursor != self.cursor {
+            self.cursor = cursor;
+            self.windowing.window.set_cursor(Self::get_cursor_icon(cursor));
+        }
+    }
+
+    fn get_cursor_icon(cursor: Cursor) -> CursorIcon {
+        match cursor {
+            Cursor::Default => CursorIcon::Default,
+            Cursor::Pointer => CursorIcon::Pointer,
+            Cursor::Text => CursorIcon::Text,
+            Cursor::Wait => CursorIcon::Wait,
+            Cursor::Progress => CursorIcon::Progress,
+            Cursor::NotAllowed => CursorIcon::NotAllowed,
+            Cursor::Crosshair => CursorIcon::Crosshair,
+            Cursor::Move => CursorIcon::Move,
+            Cursor::Grab => CursorIcon::Grab,
+            Cursor::Grabbing => CursorIcon::Grabbing,
+            Cursor::Copy => CursorIcon::Copy,
+            Cursor::NoDrop => CursorIcon::NoDrop,
+            Cursor::ColResize => CursorIcon::ColResize,
+            Cursor::RowResize => CursorIcon::RowResize,
+        }
+    }
+
+    // Runs the commands of shortcuts that are the application's rather than the browser chrome's
+    fn run_commands(&mut self, event_loop: &ActiveEventLoop) {
+        for command in self.renderer.take_unhandled_commands() {
+            match command {
+                Command::Quit => event_loop.exit(),
+                Command::Reload => {
+                    self.renderer.reload_page();
+                    self.windowing.window.request_redraw();
+                }
+                // Nothing to act on yet, e.g. focusing the address bar before there is one
+                Command::FocusAddressBar | Command::NewTab | Command::CloseTab | Command::NextTab | Command::PreviousTab => (),
+            }
+        }
+    }
+
+    // Touchpad gestures have no separate cancellation; a cancelled one just ends
+    fn get_gesture_phase(phase: TouchPhase) -> GesturePhase {
+        match phase {
+            TouchPhase::Started => GesturePhase::Started,
+            TouchPhase::Moved => GesturePhase::Moved,
+            TouchPhase::Ended | TouchPhase::Cancelled => GesturePhase::Ended,
         }
     }
+
+    fn handle_gesture(&mut self, gesture: Gesture) {
+        self.renderer.handle_event(self.get_event_position(), EventType::Gesture(gesture));
+        self.windowing.window.request_redraw();
+    }
+
+    // Key, text and focus events have no position of their own; they go with the last known one
+    fn get_event_position(&self) -> Point {
+        self.mouse_position.unwrap_or(Point::new(f32::NAN, f32::NAN))
+    }
 }
 
 impl ApplicationHandler for Application {
@@ -67,28 +142,77 @@ impl ApplicationHandler for Application {
                 self.renderer.resize_surface(&self.windowing.window, &mut self.windowing.gr_context, self.fb_info, self.windowing.gl_config.num_samples() as usize, self.windowing.gl_config.stencil_size() as usize);
                 self.windowing.window.request_redraw();
             }
+            WindowEvent::ThemeChanged(system_theme) => {
+                self.renderer.set_theme(ThemeManager::for_system_theme(system_theme));
+                self.windowing.window.request_redraw();
+            }
             WindowEvent::ModifiersChanged(new_modifiers) => {
                 self.modifiers = new_modifiers;
             }
+            WindowEvent::Focused(is_focused) => {
+                let event_type = if is_focused { EventType::FocusGained } else { EventType::FocusLost };
+                self.renderer.handle_event(self.get_event_position(), event_type);
+                self.windowing.window.request_redraw();
+            }
             WindowEvent::MouseInput { state, button, .. } => {
-                if let (ElementState::Pressed, MouseButton::Left) = (state, button) {
-                    if let Some(mouse_position) = self.mouse_position {
-                        self.renderer.handle_event(mouse_position, EventType::MouseClick);
-                        self.windowing.window.request_redraw();
-                    }
+                let modifiers = self.modifiers.state();
+                let event_type = match state {
+                    ElementState::Pressed => EventType::MouseDown { button, modifiers },
+                    ElementState::Released => EventType::MouseUp { button, modifiers },
+                };
+                if let Some(mouse_position) = self.mouse_position {
+                    self.renderer.handle_event(mouse_position, event_type);
+                    self.windowing.window.request_redraw();
+                }
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                if let Some(mouse_position) = self.mouse_position {
+                    let delta = match delta {
+                        MouseScrollDelta::LineDelta(x, y) => WheelDelta::Lines { x, y },
+                        MouseScrollDelta::PixelDelta(position) => WheelDelta::Pixels { x: position.x as f32, y: position.y as f32 },
+                    };
+                    self.renderer.handle_event(mouse_position, EventType::MouseWheel { delta, modifiers: self.modifiers.state() });
+                    self.windowing.window.request_redraw();
                 }
             }
+            WindowEvent::Touch(Touch { id, phase, location, .. }) => {
+                let touch_position = Point::new(location.x as f32, location.y as f32);
+                self.renderer.handle_event(touch_position, EventType::Touch { id, phase });
+                self.windowing.window.request_redraw();
+            }
+            WindowEvent::PinchGesture { delta, phase, .. } => {
+                self.handle_gesture(Gesture::Pinch { scale: 1.0 + delta as f32, phase: Self::get_gesture_phase(phase) });
+            }
+            WindowEvent::PanGesture { delta, phase, .. } => {
+                self.handle_gesture(Gesture::Pan { delta_x: delta.x, delta_y: delta.y, phase: Self::get_gesture_phase(phase) });
+            }
             WindowEvent::KeyboardInput {
-                event: KeyEvent { logical_key, .. },
+                event: KeyEvent { logical_key, physical_key, text, state, repeat, .. },
                 ..
             } => {
-                if self.modifiers.state().super_key() && logical_key == "q" {
-                    event_loop.exit();
+                let key_input = KeyInput { logical_key, physical_key, is_repeat: repeat, modifiers: self.modifiers.state() };
+                match state {
+                    ElementState::Pressed => {
+                        self.renderer.handle_event(self.get_event_position(), EventType::KeyDown(key_input));
+                        // Keys like Enter, Escape or Backspace come with control characters, which aren't text
+                        if let Some(text) = text.filter(|text| !text.chars().any(char::is_control)) {
+                            self.renderer.handle_event(self.get_event_position(), EventType::TextInput(text.to_string()));
+                        }
+                    }
+                    ElementState::Released => self.renderer.handle_event(self.get_event_position(), EventType::KeyUp(key_input)),
                 }
                 self.windowing.window.request_redraw();
             }
+            WindowEvent::CursorLeft { .. } => {
+                self.mouse_position = None;
+                self.renderer.handle_cursor_left();
+                self.windowing.window.request_redraw();
+            }
             WindowEvent::CursorMoved { position, .. } => {
-                self.mouse_position = Some(Point::new(position.x as f32, position.y as f32));
+                let mouse_position = Point::new(position.x as f32, position.y as f32);
+                self.mouse_position = Some(mouse_position);
+                self.renderer.handle_event(mouse_position, EventType::MouseMove { modifiers: self.modifiers.state() });
+                self.windowing.window.request_redraw();
             }
             WindowEvent::RedrawRequested => {
                 // Render and flush the Skia context
@@ -103,8 +227,32 @@ impl ApplicationHandler for Application {
             }
             _ => (),
         }
+    }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        // Shortcuts and element callbacks may have asked for commands, whichever event they came with
+        self.run_commands(event_loop);
+
+        // Fire due timers (e.g. tooltip delays) and step running animations
+        let now = Instant::now();
+        let is_timer_due = self.renderer.on_timer(now);
+        let is_animating = self.renderer.advance_animations(now);
+        if is_timer_due || is_animating {
+            self.windowing.window.request_redraw();
+        }
 
-        event_loop.set_control_flow(ControlFlow::Wait);
+        // Once per batch of events, as pointer events and pages starting or finishing to load both change it
+        self.update_cursor();
+        if let Some(window_title) = self.renderer.take_window_title() {
+            self.windowing.window.set_title(&window_title);
+        }
+
+        // While animating, wake up for the next frame; otherwise sleep until the next timer or event
+        let next_frame = is_animating.then(|| now + FRAME_INTERVAL);
+        match [self.renderer.next_timer_deadline(), next_frame].into_iter().flatten().min() {
+            Some(deadline) => event_loop.set_control_flow(ControlFlow::WaitUntil(deadline)),
+            None => event_loop.set_control_flow(ControlFlow::Wait),
+        }
     }
 }
 
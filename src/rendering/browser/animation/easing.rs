@@ -0,0 +1,67 @@
+// Maps the linear progress of an animation onto the eased one, both from 0 to 1
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Easing {
+    Linear,
+    Ease,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+    CubicBezier(f32, f32, f32, f32), // Control points (x1, y1) and (x2, y2), as in CSS
+}
+
+impl Default for Easing {
+    fn default() -> Self {
+        Self::Ease
+    }
+}
+
+impl Easing {
+    pub fn apply(&self, progress: f32) -> f32 {
+        let progress = progress.clamp(0.0, 1.0);
+        match *self {
+            Easing::Linear => progress,
+            Easing::Ease => Easing::cubic_bezier(0.25, 0.1, 0.25, 1.0, progress),
+            Easing::EaseIn => Easing::cubic_bezier(0.42, 0.0, 1.0, 1.0, progress),
+            Easing::EaseOut => Easing::cubic_bezier(0.0, 0.0, 0.58, 1.0, progress),
+            Easing::EaseInOut => Easing::cubic_bezier(0.42, 0.0, 0.58, 1.0, progress),
+            Easing::CubicBezier(x1, y1, x2, y2) => Easing::cubic_bezier(x1, y1, x2, y2, progress),
+        }
+    }
+
+    // Solves the curve's x for the progress with Newton's method, falling back to bisection
+    fn cubic_bezier(x1: f32, y1: f32, x2: f32, y2: f32, progress: f32) -> f32 {
+        let sample = |a: f32, b: f32, t: f32| {
+            let inverse = 1.0 - t;
+            3.0 * inverse * inverse * t * a + 3.0 * inverse * t * t * b + t * t * t
+        };
+        let slope = |a: f32, b: f32, t: f32| {
+            let inverse = 1.0 - t;
+            3.0 * inverse * inverse * a + 6.0 * inverse * t * (b - a) + 3.0 * t * t * (1.0 - b)
+        };
+
+        let mut t = progress;
+        for _ in 0..8 {
+            let error = sample(x1, x2, t) - progress;
+            if error.abs() < 1e-5 {
+                return sample(y1, y2, t);
+            }
+            let derivative = slope(x1, x2, t);
+            if derivative.abs() < 1e-6 {
+                break;
+            }
+            t -= error / derivative;
+        }
+
+        let (mut low, mut high) = (0.0, 1.0);
+        t = progress;
+        for _ in 0..32 {
+            let x = sample(x1, x2, t);
+            if (x - progress).abs() < 1e-5 {
+                break;
+            }
+            if x < progress { low = t; } else { high = t; }
+            t = (low + high) / 2.0;
+        }
+        sample(y1, y2, t)
+    }
+}
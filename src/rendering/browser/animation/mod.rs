@@ -0,0 +1,4 @@
+pub mod easing;
+pub mod momentum;
+pub mod style_transition;
+pub mod tween;
\ No newline at end of file
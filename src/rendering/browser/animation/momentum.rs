@@ -0,0 +1,63 @@
+use std::time::Instant;
+
+const FRICTION: f32 = 4.0;      // Rate at which the velocity decays exponentially, per second
+const MIN_VELOCITY: f32 = 20.0; // Pixels per second below which the motion stops
+
+// Motion that carries on after a fling, slowing down until it stops
+#[derive(Clone, Copy, Debug)]
+pub struct Momentum {
+    velocity_x: f32,
+    velocity_y: f32,
+    last_step: Instant,
+}
+
+impl Momentum {
+    pub fn new(velocity_x: f32, velocity_y: f32, now: Instant) -> Self {
+        Self { velocity_x, velocity_y, last_step: now }
+    }
+
+    // Distance travelled since the last step, or None once it has stopped
+    pub fn step(&mut self, now: Instant) -> Option<(f32, f32)> {
+        if self.velocity_x.hypot(self.velocity_y) < MIN_VELOCITY {
+            return None;
+        }
+        let elapsed = now.saturating_duration_since(self.last_step).as_secs_f32();
+        self.last_step = now;
+
+        // The integral of the decaying velocity over the step, so the distance doesn't depend on the frame rate
+        let decay = (-FRICTION * elapsed).exp();
+        let travelled = (1.0 - decay) / FRICTION;
+        let delta = (self.velocity_x * travelled, self.velocity_y * travelled);
+        self.velocity_x *= decay;
+        self.velocity_y *= decay;
+        Some(delta)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn step_decays_until_it_stops() {
+        let start = Instant::now();
+        let mut momentum = Momentum::new(1000.0, 0.0, start);
+
+        let mut deltas = vec![];
+        for frame in 1..=100 {
+            match momentum.step(start + Duration::from_millis(100 * frame)) {
+                Some((delta_x, delta_y)) => {
+                    assert_eq!(delta_y, 0.0);
+                    deltas.push(delta_x);
+                }
+                None => break,
+            }
+        }
+
+        assert!(deltas.len() > 1 && deltas.len() < 100, "{:?}", deltas);
+        assert!(deltas.windows(2).all(|pair| pair[1] < pair[0] && pair[1] > 0.0), "{:?}", deltas);
+        assert_eq!(momentum.step(start + Duration::from_secs(60)), None);
+    }
+}
@@ -0,0 +1,146 @@
+use std::time::Instant;
+
+use skia_safe::Color;
+
+use crate::rendering::browser::elements::styles::{Border, BorderRadius, BoxShadow, ColorValue, Margin, Padding, Size, Spacing, Styles, Transform, Transition};
+
+use super::tween::Tween;
+
+pub struct StyleTransition {
+    from: Styles,
+    to: Styles,
+    tween: Tween,
+}
+
+/*
+ * Animates an element's computed styles from the ones before a change to the ones after it.
+ * Each frame samples a copy of the target with its numeric and colour properties interpolated;
+ * a property only set on one side can't be interpolated and takes the target value at once.
+ * Colours are interpolated as resolved literals, and the target keeps its tokens once finished.
+ */
+impl StyleTransition {
+    pub fn new(from: Styles, to: Styles, transition: Transition, now: Instant) -> Self {
+        Self { from, to, tween: Tween::new(now, transition.duration, transition.easing) }
+    }
+
+    pub fn is_finished_at(&self, now: Instant) -> bool {
+        self.tween.is_finished_at(now)
+    }
+
+    pub fn sample(&self, now: Instant) -> Styles {
+        if self.is_finished_at(now) {
+            return self.to.clone();
+        }
+        StyleTransition::interpolate(&self.from, &self.to, self.tween.progress_at(now))
+    }
+
+    fn interpolate(from: &Styles, to: &Styles, progress: f32) -> Styles {
+        let both = |from: Option<f32>, to: Option<f32>| match (from, to) {
+            (Some(from), Some(to)) => Some(lerp(from, to, progress)),
+            _ => to,
+        };
+        let color = |from: Option<ColorValue>, to: Option<ColorValue>| match (from, to) {
+            (Some(from), Some(to)) => Some(lerp_color(from, to, progress)),
+            _ => to,
+        };
+
+        Styles {
+            size: match (from.size, to.size) {
+                (Some(from), Some(to)) => Some(Size { width: both(from.width, to.width), height: both(from.height, to.height), ..to }),
+                _ => to.size,
+            },
+            margin: match (from.margin, to.margin) {
+                (Some(from), Some(to)) => Some(Margin {
+                    top: lerp(from.top, to.top, progress),
+                    right: lerp(from.right, to.right, progress),
+                    bottom: lerp(from.bottom, to.bottom, progress),
+                    left: lerp(from.left, to.left, progress),
+                }),
+                _ => to.margin,
+            },
+            padding: match (from.padding, to.padding) {
+                (Some(from), Some(to)) => Some(Padding {
+                    top: lerp(from.top, to.top, progress),
+                    right: lerp(from.right, to.right, progress),
+                    bottom: lerp(from.bottom, to.bottom, progress),
+                    left: lerp(from.left, to.left, progress),
+                }),
+                _ => to.padding,
+            },
+            spacing: match (from.spacing, to.spacing) {
+                (Some(from), Some(to)) => Some(Spacing {
+                    spacing_x: lerp(from.spacing_x, to.spacing_x, progress),
+                    spacing_y: lerp(from.spacing_y, to.spacing_y, progress),
+                }),
+                _ => to.spacing,
+            },
+            color: color(from.color, to.color),
+            border: match (from.border, to.border) {
+                (Some(from), Some(to)) => Some(Border {
+                    width: lerp(from.width, to.width, progress),
+                    color: lerp_color(from.color, to.color, progress),
+                    radius: BorderRadius {
+                        top_left: lerp(from.radius.top_left, to.radius.top_left, progress),
+                        top_right: lerp(from.radius.top_right, to.radius.top_right, progress),
+                        bottom_right: lerp(from.radius.bottom_right, to.radius.bottom_right, progress),
+                        bottom_left: lerp(from.radius.bottom_left, to.radius.bottom_left, progress),
+                    },
+                    ..to
+                }),
+                _ => to.border,
+            },
+            shadows: match (&from.shadows, &to.shadows) {
+                (Some(from), Some(to)) if from.len() == to.len() => Some(from.iter().zip(to.iter())
+                    .map(|(from, to)| BoxShadow {
+                        offset_x: lerp(from.offset_x, to.offset_x, progress),
+                        offset_y: lerp(from.offset_y, to.offset_y, progress),
+                        blur_radius: lerp(from.blur_radius, to.blur_radius, progress),
+                        spread: lerp(from.spread, to.spread, progress),
+                        color: lerp_color(from.color, to.color, progress),
+                        ..*to
+                    })
+                    .collect()),
+                _ => to.shadows.clone(),
+            },
+            opacity: both(from.opacity, to.opacity),
+            // An unset transform is the identity, so elements can animate into and out of one
+            transform: match (from.transform, to.transform) {
+                (None, None) => None,
+                (from, to) => {
+                    let identity = |other: Transform| Transform { origin_x: other.origin_x, origin_y: other.origin_y, ..Default::default() };
+                    let (from, to) = match (from, to) {
+                        (Some(from), Some(to)) => (from, to),
+                        (Some(from), None) => (from, identity(from)),
+                        (None, to) => {
+                            let to = to.unwrap_or_default();
+                            (identity(to), to)
+                        }
+                    };
+                    Some(Transform {
+                        translate_x: lerp(from.translate_x, to.translate_x, progress),
+                        translate_y: lerp(from.translate_y, to.translate_y, progress),
+                        scale_x: lerp(from.scale_x, to.scale_x, progress),
+                        scale_y: lerp(from.scale_y, to.scale_y, progress),
+                        rotate: lerp(from.rotate, to.rotate, progress),
+                        origin_x: lerp(from.origin_x, to.origin_x, progress),
+                        origin_y: lerp(from.origin_y, to.origin_y, progress),
+                    })
+                }
+            },
+            text_color: color(from.text_color, to.text_color),
+            font_size: both(from.font_size, to.font_size),
+            letter_spacing: both(from.letter_spacing, to.letter_spacing),
+            ..to.clone()
+        }
+    }
+}
+
+fn lerp(from: f32, to: f32, progress: f32) -> f32 {
+    from + (to - from) * progress
+}
+
+fn lerp_color(from: ColorValue, to: ColorValue, progress: f32) -> ColorValue {
+    let (from, to) = (from.resolve(), to.resolve());
+    let channel = |from: u8, to: u8| lerp(from as f32, to as f32, progress).round() as u8;
+    Color::from_argb(channel(from.a(), to.a()), channel(from.r(), to.r()), channel(from.g(), to.g()), channel(from.b(), to.b())).into()
+}
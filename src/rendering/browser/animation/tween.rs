@@ -0,0 +1,30 @@
+use std::time::{Duration, Instant};
+
+use super::easing::Easing;
+
+// Eased progress of one run of an animation, from its start to its end
+#[derive(Clone, Copy, Debug)]
+pub struct Tween {
+    start: Instant,
+    duration: Duration,
+    easing: Easing,
+}
+
+impl Tween {
+    pub fn new(start: Instant, duration: Duration, easing: Easing) -> Self {
+        Self { start, duration, easing }
+    }
+
+    // Eased progress from 0 to 1; 1 once the duration has passed
+    pub fn progress_at(&self, now: Instant) -> f32 {
+        if self.duration.is_zero() {
+            return 1.0;
+        }
+        let elapsed = now.saturating_duration_since(self.start);
+        self.easing.apply(elapsed.as_secs_f32() / self.duration.as_secs_f32())
+    }
+
+    pub fn is_finished_at(&self, now: Instant) -> bool {
+        now.saturating_duration_since(self.start) >= self.duration
+    }
+}
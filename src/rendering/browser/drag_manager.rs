@@ -0,0 +1,159 @@
+use std::time::{Duration, Instant};
+
+use skia_safe::Point;
+use winit::{event::MouseButton, keyboard::ModifiersState};
+
+use super::{elements::{element::{DragData, Element, Event, EventType}, styles::Cursor}, event_dispatcher::{EventDispatcher, HitPath}};
+
+const MULTI_CLICK_INTERVAL: Duration = Duration::from_millis(500); // Between two presses of a double click
+const MULTI_CLICK_DISTANCE: f32 = 4.0;
+const DRAG_THRESHOLD: f32 = 4.0; // Distance the pointer moves while pressed before it's dragging
+
+struct Press {
+    button: MouseButton,
+    position: Point,
+    time: Instant,
+    path: HitPath,
+    click_count: u32,
+}
+
+struct DragSession {
+    data: Option<DragData>,
+    drop_path: Option<HitPath>, // Where the dragged data is; None outside the tree or without data
+    is_drop_accepted: bool,
+}
+
+pub struct DragManager {
+    last_press: Option<Press>,
+    is_pressed: bool,
+    drag: Option<DragSession>,
+}
+
+/*
+ * Turns presses, moves and releases into clicks and drags, sent like the events they come from
+ * (see UIManager):
+ *   A. A release over the element the press was on, or one of its ancestors, clicks the deepest
+ *      element both were on. Presses of the same button close in time and place count up, so
+ *      the click's count is 2 for a double click and 3 for a triple one
+ *   B. A press that moves far enough starts a drag instead: the pressed element gets DragStart,
+ *      DragMove for every move after, wherever the pointer is, and DragEnd on the release
+ *   C. An element on the pressed path can give the drag data to carry. The elements under the
+ *      pointer then get DragEnter and DragLeave as it moves over them, and DragOver, which a drop
+ *      target handles to accept the data; releasing over one that accepted drops it there
+ * Escape and the window losing focus cancel a drag.
+ */
+impl DragManager {
+    pub fn new() -> Self {
+        Self { last_press: None, is_pressed: false, drag: None }
+    }
+
+    // `path` is the pressed element's; only the first button pressed counts until all are released
+    pub fn on_press(&mut self, button: MouseButton, cursor_position: Point, path: HitPath, now: Instant) {
+        if self.is_pressed {
+            return;
+        }
+        let click_count = match &self.last_press {
+            Some(last_press) if last_press.button == button
+                && now.duration_since(last_press.time) <= MULTI_CLICK_INTERVAL
+                && (cursor_position - last_press.position).length() <= MULTI_CLICK_DISTANCE => last_press.click_count + 1,
+            _ => 1,
+        };
+        self.last_press = Some(Press { button, position: cursor_position, time: now, path, click_count });
+        self.is_pressed = true;
+    }
+
+    pub fn on_move(&mut self, root: &mut dyn Element, cursor_position: Point, modifiers: ModifiersState) {
+        let Some(press) = self.last_press.as_ref().filter(|_| self.is_pressed) else { return; };
+
+        if self.drag.is_none() {
+            if (cursor_position - press.position).length() < DRAG_THRESHOLD {
+                return;
+            }
+            let mut drag_start = Event::new(EventType::DragStart { button: press.button, modifiers });
+            EventDispatcher::dispatch(root, &press.path, cursor_position, &mut drag_start);
+            let data = DragManager::find_drag_data(root, &press.path);
+            self.drag = Some(DragSession { data, drop_path: None, is_drop_accepted: false });
+        }
+
+        EventDispatcher::dispatch(root, &press.path, cursor_position, &mut Event::new(EventType::DragMove { modifiers }));
+        self.update_drop_target(root, cursor_position);
+    }
+
+    // `hit_path` is the element's under the cursor
+    pub fn on_release(&mut self, root: &mut dyn Element, button: MouseButton, cursor_position: Point, hit_path: Option<&[usize]>, modifiers: ModifiersState) {
+        let Some(press) = self.last_press.as_ref().filter(|press| self.is_pressed && press.button == button) else { return; };
+        self.is_pressed = false;
+
+        match self.drag.take() {
+            Some(drag) => {
+                EventDispatcher::update_path(root, drag.drop_path.as_deref(), None, cursor_position, EventType::DragLeave, EventType::DragEnter);
+                let is_dropped = match (drag.data, drag.drop_path) {
+                    (Some(data), Some(drop_path)) if drag.is_drop_accepted => {
+                        EventDispatcher::dispatch(root, &drop_path, cursor_position, &mut Event::new(EventType::Drop { data }));
+                        true
+                    }
+                    _ => false,
+                };
+                EventDispatcher::dispatch(root, &press.path, cursor_position, &mut Event::new(EventType::DragEnd { is_dropped }));
+            }
+            None => {
+                let Some(hit_path) = hit_path else { return; };
+                let click_path: HitPath = press.path.iter().zip(hit_path)
+                    .take_while(|(pressed, released)| pressed == released)
+                    .map(|(pressed, _)| *pressed)
+                    .collect();
+                let click = EventType::Click { button, click_count: press.click_count, modifiers };
+                EventDispatcher::dispatch(root, &click_path, cursor_position, &mut Event::new(click));
+            }
+        }
+    }
+
+    // Returns whether there was a drag to cancel
+    pub fn cancel(&mut self, root: &mut dyn Element, cursor_position: Point) -> bool {
+        let (Some(drag), Some(press)) = (self.drag.take(), &self.last_press) else { return false; };
+        self.is_pressed = false; // The release to come is neither a click nor a drop
+
+        EventDispatcher::update_path(root, drag.drop_path.as_deref(), None, cursor_position, EventType::DragLeave, EventType::DragEnter);
+        EventDispatcher::dispatch(root, &press.path, cursor_position, &mut Event::new(EventType::DragEnd { is_dropped: false }));
+        true
+    }
+
+    // The release may happen in another window, so it may never come
+    pub fn on_focus_lost(&mut self, root: &mut dyn Element, cursor_position: Point) {
+        self.cancel(root, cursor_position);
+        self.is_pressed = false;
+    }
+
+    // While data is dragged, whether the element under the pointer would take it
+    pub fn get_cursor(&self) -> Option<Cursor> {
+        let drag = self.drag.as_ref().filter(|drag| drag.data.is_some())?;
+        Some(if drag.is_drop_accepted { Cursor::Copy } else { Cursor::NoDrop })
+    }
+
+    fn update_drop_target(&mut self, root: &mut dyn Element, cursor_position: Point) {
+        let Some(drag) = &mut self.drag else { return; };
+        let Some(data) = &drag.data else { return; };
+
+        let drop_path = EventDispatcher::hit_test(root, cursor_position);
+        EventDispatcher::update_path(root, drag.drop_path.as_deref(), drop_path.as_deref(), cursor_position, EventType::DragLeave, EventType::DragEnter);
+
+        let mut drag_over = Event::new(EventType::DragOver { data: data.clone() });
+        if let Some(path) = &drop_path {
+            EventDispatcher::dispatch(root, path, cursor_position, &mut drag_over);
+        }
+        drag.is_drop_accepted = drag_over.is_handled();
+        drag.drop_path = drop_path;
+    }
+
+    // From the deepest element on the pressed path up, the first that has data to drag
+    fn find_drag_data(root: &dyn Element, path: &[usize]) -> Option<DragData> {
+        let mut element = root;
+        let mut elements = vec![root];
+        for index in path {
+            let Some(child) = element.get_event_child(*index) else { break; };
+            element = child;
+            elements.push(element);
+        }
+        elements.iter().rev().find_map(|element| element.get_drag_data())
+    }
+}
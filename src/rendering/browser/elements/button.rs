@@ -1,9 +1,16 @@
-use skia_safe::{Canvas, Contains, Paint, Point, Rect};
+use std::time::Instant;
 
-use crate::rendering::browser::{internal::element_id_generator::IDGenerator, layout::types::ChildSpaceAllocationPlan};
+use skia_safe::{Canvas, Contains, Point, Rect};
+use winit::{event::MouseButton, keyboard::{Key, NamedKey}};
+
+use crate::rendering::browser::{internal::element_id_generator::IDGenerator, layout::types::ChildSpaceAllocationPlan, painting::box_painter::BoxPainter};
 use crate::rendering::browser::layout::types::{Position, VerticalHorizontal};
+use crate::rendering::browser::styling::{interaction_styles::{InteractionStyles, StateStyles}, style_resolver::{StyleContext, StyleResolver}, stylesheet::SelectorSubject};
+use crate::rendering::browser::reactive::binding::Binding;
+use crate::rendering::browser::state::app_state::AppState;
+use crate::rendering::browser::styling::theme::ColorToken;
 
-use super::{common::ElementType, element::{Element, ElementSize, EventType}, styles::{Directions, Styles}};
+use super::{common::ElementType, element::{DragData, Element, ElementSize, Event, EventType, KeyInput}, menu::{Menu, MenuResponse}, styles::{ColorValue, Cursor, Directions, Styles}, tooltip::Tooltip};
 
 
 pub struct Button {
@@ -12,7 +19,22 @@ pub struct Button {
     size: ElementSize,
     allocated_size: Option<ElementSize>,
     styles: Styles,
+    computed_styles: Styles,
+    style_id: Option<String>,
+    class_names: Vec<String>,
+    interaction: InteractionStyles,
     pub on_click: Box<dyn FnMut()>,
+    menu: Option<Menu>,
+    context_menu_builder: Option<Box<dyn Fn() -> Menu>>,
+    tooltip: Option<Tooltip>,
+    tab_index: Option<i32>,
+    pub drag_data: Option<DragData>,
+    drop_handler: Option<(String, Box<dyn FnMut(&DragData)>)>, // Format of the data it accepts, and what to do with it
+    is_drop_accepted: bool, // Data it accepts is being dragged over it
+    state_binding: Option<Box<dyn FnMut(&mut Button, &AppState)>>,
+    is_visible: bool, // A hidden button keeps its place but isn't painted, hit or focused
+    color_binding: Option<Binding<Option<ColorValue>>>,
+    visibility_binding: Option<Binding<bool>>,
 }
 
 impl Button {
@@ -25,7 +47,22 @@ impl Button {
             size: Button::get_size_from_styles(styles.clone()),
             allocated_size: None,
             styles,
-            on_click 
+            computed_styles: Styles::initial(),
+            style_id: None,
+            class_names: vec![],
+            interaction: InteractionStyles::new(),
+            on_click,
+            menu: None,
+            context_menu_builder: None,
+            tooltip: None,
+            tab_index: None,
+            drag_data: None,
+            drop_handler: None,
+            is_drop_accepted: false,
+            state_binding: None,
+            is_visible: true,
+            color_binding: None,
+            visibility_binding: None,
         }
     }
 
@@ -38,11 +75,57 @@ impl Button {
     }
 
     pub fn set_styles(mut self, styles: Styles) -> Self {
-        self.styles = styles;
         self.size = Button::get_size_from_styles(styles.clone());
+        self.styles = styles;
+        self
+    }
+
+    // Id and class names that stylesheet selectors match against
+    #[allow(dead_code)]
+    pub fn set_style_id(mut self, style_id: &str) -> Self {
+        self.style_id = Some(style_id.to_string());
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn add_class(mut self, class_name: &str) -> Self {
+        self.class_names.push(class_name.to_string());
+        self
+    }
+
+    // Overrides merged over the styles while the element is hovered, pressed, focused or disabled
+    #[allow(dead_code)]
+    pub fn set_state_styles(mut self, state_styles: StateStyles) -> Self {
+        self.interaction.state_styles = state_styles;
         self
     }
+
+    #[allow(dead_code)]
+    pub fn set_disabled(mut self, is_disabled: bool) -> Self {
+        self.interaction.set_disabled(is_disabled);
+        self
+    }
+
+    // Enables or disables the button in place, e.g. following the application state
+    pub fn update_disabled(&mut self, is_disabled: bool) {
+        if self.interaction.state.is_disabled != is_disabled {
+            self.interaction.set_disabled(is_disabled);
+            self.restyle_for_interaction();
+        }
+    }
+
+    fn restyle_for_interaction(&mut self) {
+        let previous_styles = self.computed_styles.clone();
+        self.resolve_styles(&self.interaction.get_context());
+        self.interaction.on_restyled(&previous_styles, &mut self.computed_styles);
+    }
     
+    // Replaces the styles of a button in place, e.g. when a list rebinds a recycled one
+    pub fn restyle(&mut self, styles: Styles) {
+        self.size = Button::get_size_from_styles(styles.clone());
+        self.styles = styles;
+    }
+
     #[allow(dead_code)]
     pub fn set_size(mut self, size: ElementSize) -> Self {
         self.size = size;
@@ -61,6 +144,78 @@ impl Button {
         self
     }
 
+    // Opens the menu below the button when it is clicked, instead of calling `on_click`
+    #[allow(dead_code)]
+    pub fn set_menu(mut self, menu: Menu) -> Self {
+        self.menu = Some(menu);
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn set_context_menu(mut self, context_menu_builder: Box<dyn Fn() -> Menu>) -> Self {
+        self.context_menu_builder = Some(context_menu_builder);
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn set_tooltip(mut self, tooltip: Tooltip) -> Self {
+        self.tooltip = Some(tooltip);
+        self
+    }
+
+    // Place in Tab order: positive indices come first, negative ones are skipped
+    #[allow(dead_code)]
+    pub fn set_tab_index(mut self, tab_index: i32) -> Self {
+        self.tab_index = Some(tab_index);
+        self
+    }
+
+    // Data dragged out of the button, e.g. the link of a history entry
+    #[allow(dead_code)]
+    pub fn set_drag_data(mut self, drag_data: DragData) -> Self {
+        self.drag_data = Some(drag_data);
+        self
+    }
+
+    // Makes the button a drop target for dragged data of the format
+    #[allow(dead_code)]
+    pub fn set_on_drop(mut self, format: &str, on_drop: Box<dyn FnMut(&DragData)>) -> Self {
+        self.drop_handler = Some((format.to_string(), on_drop));
+        self
+    }
+
+    // Updates the button from the application state whenever it changes, e.g. disabling Back with nothing to go back to
+    #[allow(dead_code)]
+    pub fn set_state_binding(mut self, state_binding: Box<dyn FnMut(&mut Button, &AppState)>) -> Self {
+        self.state_binding = Some(state_binding);
+        self
+    }
+
+    // Keeps the background colour computed from the signals it reads
+    #[allow(dead_code)]
+    pub fn set_color_binding(mut self, color: Box<dyn Fn() -> ColorValue>) -> Self {
+        let color_binding = Binding::new(move || Some(color()));
+        if let Some(color) = color_binding.take_changed(&self.styles.color) {
+            self.styles.color = color;
+        }
+        self.color_binding = Some(color_binding);
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn set_visibility_binding(mut self, is_visible: Box<dyn Fn() -> bool>) -> Self {
+        let visibility_binding = Binding::new(is_visible);
+        if let Some(is_visible) = visibility_binding.take_changed(&self.is_visible) {
+            self.is_visible = is_visible;
+        }
+        self.visibility_binding = Some(visibility_binding);
+        self
+    }
+
+    fn accepts_drop(&self, data: &DragData) -> bool {
+        self.drop_handler.as_ref().is_some_and(|(format, _)| *format == data.format) && !self.interaction.state.is_disabled
+    }
+
     #[allow(dead_code)]
     pub fn set_margin(mut self, margin: super::styles::Margin) -> Self {
         self.styles.margin = Some(margin);
@@ -74,16 +229,16 @@ impl Button {
     }
 
     #[allow(dead_code)]
-    pub fn set_color(mut self, color: skia_safe::Color) -> Self {
+    pub fn set_color(mut self, color: super::styles::ColorValue) -> Self {
         self.styles.color = Some(color);
         self
     }
 
     pub fn rect(&self) -> Rect {
-        let width = if let Some(size) = &self.styles.size { 
+        let width = if let Some(size) = &self.computed_styles.size { 
             if let Some(width) = size.width { width } else { 0.0 }
         } else { 0.0 };
-        let height = if let Some(size) = &self.styles.size { 
+        let height = if let Some(size) = &self.computed_styles.size { 
             if let Some(height) = size.height { height } else { 0.0 }
         } else { 0.0 };
         Rect::from_point_and_size(self.position, (width, height))
@@ -92,19 +247,57 @@ impl Button {
 
 impl Element for Button {
     fn render(&self, canvas: &Canvas) {
-        let mut paint = Paint::default();
-        paint.set_anti_alias(true);
-        paint.set_color(if let Some(color) = &self.styles.color { *color } else { skia_safe::Color::WHITE });
-
-        let rect = self.rect();
-        canvas.draw_rect(rect, &paint);
+        if !self.is_visible {
+            return;
+        }
+        BoxPainter::begin_group(canvas, self.rect(), &self.computed_styles);
+        BoxPainter::paint_box(canvas, self.rect(), &self.computed_styles, ColorToken::SurfaceRaised.into());
+        if self.interaction.state.is_focus_visible || self.is_drop_accepted {
+            BoxPainter::paint_focus_ring(canvas, self.rect(), &self.computed_styles.border.unwrap_or_default().radius);
+        }
+        BoxPainter::end_group(canvas);
     }
 
-    fn update(&mut self) {}
+    // Pulls the values of bindings whose signals changed; a colour only needs a restyle
+    fn update(&mut self) {
+        if let Some(color) = self.color_binding.as_ref().and_then(|binding| binding.take_changed(&self.styles.color)) {
+            self.styles.color = color;
+            self.restyle_for_interaction();
+        }
+        if let Some(is_visible) = self.visibility_binding.as_ref().and_then(|binding| binding.take_changed(&self.is_visible)) {
+            self.is_visible = is_visible;
+        }
+    }
     
-    fn handle_event(&mut self, cursor_position: Point, event_type: &EventType) {
-        match event_type {
-            EventType::MouseClick if self.rect().contains(cursor_position) => (self.on_click)(),
+    fn handle_event(&mut self, _cursor_position: Point, event: &mut Event) {
+        if self.interaction.on_event(&event.event_type) {
+            self.restyle_for_interaction();
+        }
+
+        match &event.event_type {
+            EventType::Click { button: MouseButton::Left, .. } if self.menu.is_none() && !self.interaction.state.is_disabled => {
+                (self.on_click)();
+                event.set_handled();
+            }
+            // Keyboard activation; buttons with a menu open it in handle_overlay_event instead
+            EventType::KeyDown(KeyInput { logical_key: Key::Named(NamedKey::Enter | NamedKey::Space), is_repeat: false, .. })
+                if self.menu.is_none() && !self.interaction.state.is_disabled => {
+                (self.on_click)();
+                event.set_handled();
+            }
+            EventType::DragOver { data } => {
+                self.is_drop_accepted = self.accepts_drop(data);
+                if self.is_drop_accepted {
+                    event.set_handled();
+                }
+            }
+            EventType::DragLeave => self.is_drop_accepted = false,
+            EventType::Drop { data } => {
+                if let Some((_, on_drop)) = &mut self.drop_handler {
+                    on_drop(data);
+                }
+                event.set_handled();
+            }
             _ => (),
         }
     }
@@ -149,11 +342,36 @@ impl Element for Button {
     fn get_styles(&self) -> Styles {
         self.styles.clone()
     }
+
+    fn resolve_styles(&mut self, context: &StyleContext) {
+        let specified = self.interaction.apply(&self.styles, context);
+        self.computed_styles = StyleResolver::resolve_element(&SelectorSubject::of(self), &specified, context);
+    }
+
+    fn get_computed_styles(&self) -> Styles {
+        self.computed_styles.clone()
+    }
+
+    fn get_style_id(&self) -> Option<String> {
+        self.style_id.clone()
+    }
+
+    fn get_class_names(&self) -> Vec<String> {
+        self.class_names.clone()
+    }
+
+    fn take_needs_relayout(&mut self) -> bool {
+        self.interaction.take_needs_relayout()
+    }
+
+    fn advance_animations(&mut self, now: Instant) -> bool {
+        self.interaction.advance_transition(now, &mut self.computed_styles)
+    }
     
     fn is_variable_size(&self) -> Directions {
         let mut directions = Directions { horizontal: true, vertical: true };
 
-        if let Some(size) = &self.styles.size {
+        if let Some(size) = &self.computed_styles.size {
             if size.width.is_some() { directions.horizontal = false; }
             if size.height.is_some() { directions.vertical = false; }
         }
@@ -162,7 +380,7 @@ impl Element for Button {
     }
 
     fn compute_allocation_plan(&mut self) {
-        if let Some(size) = &self.styles.size {
+        if let Some(size) = &self.computed_styles.size {
             if let Some(width) = size.width {
                 self.size.width = width;
             }
@@ -181,4 +399,83 @@ impl Element for Button {
         self.set_position(Point::new(allocated_position.x, allocated_position.y));
         self.set_size(allocated_size);
     }
+
+    fn render_overlay(&self, canvas: &Canvas, _viewport: &ElementSize) {
+        if let Some(menu) = &self.menu {
+            menu.render(canvas);
+        }
+    }
+
+    fn handle_overlay_event(&mut self, cursor_position: Point, event_type: &EventType, viewport: &ElementSize) -> bool {
+        let rect = self.rect();
+        let local_position = self.computed_styles.map_to_local(rect, cursor_position);
+        let screen_rect = self.computed_styles.map_rect_from_local(rect, rect); // Menus open in screen space, untransformed
+        let is_disabled = self.interaction.state.is_disabled;
+        match &mut self.menu {
+            Some(menu) if menu.is_open() => menu.handle_event(cursor_position, event_type, viewport) != MenuResponse::Ignored,
+            Some(menu) => match event_type { // Opened here rather than in handle_event, where the viewport is unknown
+                EventType::MouseDown { button: MouseButton::Left, .. } if !is_disabled && rect.contains(local_position) => {
+                    menu.open_at(Point::new(screen_rect.left, screen_rect.bottom), viewport);
+                    true
+                }
+                EventType::KeyDown(KeyInput { logical_key: Key::Named(NamedKey::Enter | NamedKey::Space | NamedKey::ArrowDown), .. })
+                    if !is_disabled && self.interaction.state.is_focused => {
+                    menu.open_at(Point::new(screen_rect.left, screen_rect.bottom), viewport);
+                    true
+                }
+                _ => false,
+            },
+            None => false,
+        }
+    }
+
+    fn build_context_menu(&self, cursor_position: Point) -> Option<Menu> {
+        let cursor_position = self.computed_styles.map_to_local(self.rect(), cursor_position);
+        if !self.is_visible || !self.rect().contains(cursor_position) {
+            return None;
+        }
+        self.context_menu_builder.as_ref().map(|builder| builder())
+    }
+
+    fn find_tooltip(&self, cursor_position: Point) -> Option<(Tooltip, Rect)> {
+        let cursor_position = self.computed_styles.map_to_local(self.rect(), cursor_position);
+        if !self.is_visible || !self.rect().contains(cursor_position) {
+            return None;
+        }
+        self.tooltip.clone().map(|tooltip| (tooltip, self.computed_styles.map_rect_from_local(self.rect(), self.rect())))
+    }
+
+    fn find_cursor(&self, cursor_position: Point) -> Option<Cursor> {
+        let cursor_position = self.computed_styles.map_to_local(self.rect(), cursor_position);
+        (self.is_visible && self.rect().contains(cursor_position)).then(|| self.computed_styles.cursor.unwrap_or_default())
+    }
+
+    fn map_to_local(&self, cursor_position: Point) -> Point {
+        self.computed_styles.map_to_local(self.rect(), cursor_position)
+    }
+
+    fn contains_point(&self, cursor_position: Point) -> bool {
+        let size = self.get_size();
+        self.is_visible && Rect::from_point_and_size(self.position, (size.width, size.height)).contains(cursor_position)
+    }
+
+    fn is_focusable(&self) -> bool {
+        self.is_visible && !self.interaction.state.is_disabled
+    }
+
+    fn get_tab_index(&self) -> Option<i32> {
+        self.tab_index
+    }
+
+    fn get_drag_data(&self) -> Option<DragData> {
+        self.drag_data.clone()
+    }
+
+    fn on_state_changed(&mut self, state: &AppState) {
+        // Taken out while it runs, as it gets the element itself
+        if let Some(mut state_binding) = self.state_binding.take() {
+            state_binding(self, state);
+            self.state_binding = Some(state_binding);
+        }
+    }
 }
\ No newline at end of file
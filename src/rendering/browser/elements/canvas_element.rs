@@ -0,0 +1,236 @@
+use std::time::Instant;
+
+use skia_safe::{Canvas, Color, Contains, Point, Rect};
+
+use crate::rendering::browser::internal::element_id_generator::IDGenerator;
+use crate::rendering::browser::layout::types::Position;
+use crate::rendering::browser::painting::box_painter::BoxPainter;
+use crate::rendering::browser::styling::{interaction_styles::{InteractionStyles, StateStyles}, style_resolver::{StyleContext, StyleResolver}, stylesheet::SelectorSubject};
+
+use super::{common::ElementType, element::{Element, ElementSize, Event, EventType}, styles::{Cursor, Directions, Styles}};
+
+/*
+ * Element whose drawing is delegated to a user closure. The closure gets the canvas already
+ * translated to the element's origin and clipped to its (rounded) bounds, so it draws in local coordinates.
+ */
+pub struct CanvasElement {
+    _id: String,
+    position: Point,
+    size: ElementSize,
+    styles: Styles,
+    computed_styles: Styles,
+    style_id: Option<String>,
+    class_names: Vec<String>,
+    interaction: InteractionStyles,
+    render_callback: Box<dyn Fn(&Canvas, &ElementSize)>,
+    on_mouse_event: Option<Box<dyn FnMut(Point, &EventType)>>,
+}
+
+impl CanvasElement {
+    pub fn new(render_callback: Box<dyn Fn(&Canvas, &ElementSize)>) -> Self {
+        Self {
+            _id: IDGenerator::get(),
+            position: Point::new(0.0, 0.0),
+            size: ElementSize::default(),
+            styles: Styles::default(),
+            computed_styles: Styles::initial(),
+            style_id: None,
+            class_names: vec![],
+            interaction: InteractionStyles::new(),
+            render_callback,
+            on_mouse_event: None,
+        }
+    }
+
+    pub fn set_styles(mut self, styles: Styles) -> Self {
+        self.styles = styles;
+        self
+    }
+
+    // Id and class names that stylesheet selectors match against
+    #[allow(dead_code)]
+    pub fn set_style_id(mut self, style_id: &str) -> Self {
+        self.style_id = Some(style_id.to_string());
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn add_class(mut self, class_name: &str) -> Self {
+        self.class_names.push(class_name.to_string());
+        self
+    }
+
+    // Overrides merged over the styles while the element is hovered, pressed, focused or disabled
+    #[allow(dead_code)]
+    pub fn set_state_styles(mut self, state_styles: StateStyles) -> Self {
+        self.interaction.state_styles = state_styles;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn set_disabled(mut self, is_disabled: bool) -> Self {
+        self.interaction.set_disabled(is_disabled);
+        self
+    }
+
+    fn restyle_for_interaction(&mut self) {
+        let previous_styles = self.computed_styles.clone();
+        self.resolve_styles(&self.interaction.get_context());
+        self.interaction.on_restyled(&previous_styles, &mut self.computed_styles);
+    }
+
+    // Receives mouse events over the element, with the cursor position relative to its top-left corner
+    #[allow(dead_code)]
+    pub fn set_on_mouse_event(mut self, on_mouse_event: Box<dyn FnMut(Point, &EventType)>) -> Self {
+        self.on_mouse_event = Some(on_mouse_event);
+        self
+    }
+
+    pub fn rect(&self) -> Rect {
+        Rect::from_point_and_size(self.position, (self.size.width, self.size.height))
+    }
+}
+
+impl Element for CanvasElement {
+    fn render(&self, canvas: &Canvas) {
+        let border = self.computed_styles.border.unwrap_or_default();
+        BoxPainter::begin_group(canvas, self.rect(), &self.computed_styles);
+        BoxPainter::paint_box_background(canvas, self.rect(), &self.computed_styles, Color::TRANSPARENT.into());
+
+        canvas.save();
+        BoxPainter::clip_to_padding_box(canvas, self.rect(), &border);
+        canvas.translate((self.position.x, self.position.y));
+
+        (self.render_callback)(canvas, &self.size);
+
+        canvas.restore();
+
+        BoxPainter::paint_border(canvas, self.rect(), &border);
+        BoxPainter::end_group(canvas);
+    }
+
+    fn update(&mut self) {}
+
+    fn handle_event(&mut self, cursor_position: Point, event: &mut Event) {
+        if self.interaction.on_event(&event.event_type) {
+            self.restyle_for_interaction();
+        }
+
+        if self.interaction.state.is_disabled || !event.event_type.is_pointer_event() {
+            return;
+        }
+        // Moves and the release after a press arrive here even outside the element, like in a drag
+        let local_position = self.get_local_position(cursor_position);
+        if let Some(on_mouse_event) = &mut self.on_mouse_event {
+            on_mouse_event(local_position, &event.event_type);
+            event.set_handled();
+        }
+    }
+
+    fn set_position(&mut self, position: Point) {
+        self.position = position;
+    }
+
+    fn set_size(&mut self, size: ElementSize) {
+        self.size = size;
+    }
+
+    fn layout(&mut self, available_space: Option<ElementSize>) {
+        if let Some(available_space) = available_space {
+            self.size = ElementSize {
+                width: self.size.width.min(available_space.width),
+                height: self.size.height.min(available_space.height),
+            }
+        }
+    }
+
+    fn get_id(&self) -> String {
+        self._id.clone()
+    }
+
+    fn get_element_type(&self) -> ElementType {
+        ElementType::Canvas
+    }
+
+    fn get_children_mut(&mut self) -> Option<&mut Vec<Box<dyn Element>>> {
+        None
+    }
+
+    fn get_position(&self) -> Point {
+        self.position
+    }
+
+    fn get_size(&self) -> ElementSize {
+        self.size.clone()
+    }
+
+    fn get_styles(&self) -> Styles {
+        self.styles.clone()
+    }
+
+    fn resolve_styles(&mut self, context: &StyleContext) {
+        let specified = self.interaction.apply(&self.styles, context);
+        self.computed_styles = StyleResolver::resolve_element(&SelectorSubject::of(self), &specified, context);
+    }
+
+    fn get_computed_styles(&self) -> Styles {
+        self.computed_styles.clone()
+    }
+
+    fn get_style_id(&self) -> Option<String> {
+        self.style_id.clone()
+    }
+
+    fn get_class_names(&self) -> Vec<String> {
+        self.class_names.clone()
+    }
+
+    fn take_needs_relayout(&mut self) -> bool {
+        self.interaction.take_needs_relayout()
+    }
+
+    fn advance_animations(&mut self, now: Instant) -> bool {
+        self.interaction.advance_transition(now, &mut self.computed_styles)
+    }
+
+    fn is_variable_size(&self) -> Directions {
+        let mut directions = Directions { horizontal: true, vertical: true };
+
+        if let Some(size) = &self.computed_styles.size {
+            if size.width.is_some() { directions.horizontal = false; }
+            if size.height.is_some() { directions.vertical = false; }
+        }
+
+        directions
+    }
+
+    fn compute_allocation_plan(&mut self) {
+        if let Some(size) = &self.computed_styles.size {
+            if let Some(width) = size.width {
+                self.size.width = width;
+            }
+            if let Some(height) = size.height {
+                self.size.height = height;
+            }
+        } else {
+            self.size = ElementSize {
+                width: 100.0,
+                height: 40.0,
+            };
+        }
+    }
+
+    fn enact_allocation_plan(&mut self, allocated_position: Position, allocated_size: ElementSize) {
+        self.set_position(Point::new(allocated_position.x, allocated_position.y));
+        self.set_size(allocated_size);
+    }
+
+    fn find_cursor(&self, cursor_position: Point) -> Option<Cursor> {
+        let cursor_position = self.computed_styles.map_to_local(self.rect(), cursor_position);
+        self.rect().contains(cursor_position).then(|| self.computed_styles.cursor.unwrap_or_default())
+    }
+
+    fn map_to_local(&self, cursor_position: Point) -> Point {
+        self.computed_styles.map_to_local(self.rect(), cursor_position)
+    }
+}
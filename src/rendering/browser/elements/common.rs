@@ -3,4 +3,26 @@
 pub enum ElementType {
     Row,
     Button,
+    Select,
+    Tab,
+    TabStrip,
+    VirtualList,
+    Canvas,
+    Label,
+}
+
+impl ElementType {
+    // Name used by type selectors in stylesheets
+    pub fn get_type_name(&self) -> &'static str {
+        match self {
+            ElementType::Row => "row",
+            ElementType::Button => "button",
+            ElementType::Select => "select",
+            ElementType::Tab => "tab",
+            ElementType::TabStrip => "tab-strip",
+            ElementType::VirtualList => "virtual-list",
+            ElementType::Canvas => "canvas",
+            ElementType::Label => "label",
+        }
+    }
 }
\ No newline at end of file
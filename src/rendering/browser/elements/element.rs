@@ -1,16 +1,21 @@
-use skia_safe::{Canvas, Point};
-use std::ops::Sub;
+use skia_safe::{Canvas, Point, Rect};
+use std::{ops::Sub, time::Instant};
+use winit::{event::{MouseButton, TouchPhase}, keyboard::{Key, ModifiersState, PhysicalKey}};
 
 use crate::rendering::browser::layout::types::{ChildSpaceAllocationPlan, Position};
+use crate::rendering::browser::shortcuts::command::Command;
+use crate::rendering::browser::state::app_state::AppState;
+use crate::rendering::browser::styling::style_resolver::StyleContext;
 
-use super::{common::ElementType, styles::{Directions, Styles}};
+use super::{common::ElementType, menu::Menu, styles::{Cursor, Directions, Styles}, tooltip::Tooltip};
 
 
 pub trait Element {
     fn render(&self, canvas: &Canvas);
     #[allow(dead_code)]
     fn update(&mut self);
-    fn handle_event(&mut self, cursor_position: Point, event_type: &EventType);
+    // Called for each phase of an event whose path goes through the element, see EventDispatcher
+    fn handle_event(&mut self, cursor_position: Point, event: &mut Event);
 
     fn set_position(&mut self, position: Point);
     fn set_size(&mut self, size: ElementSize);
@@ -31,12 +36,252 @@ pub trait Element {
     // Refactored system
     fn compute_allocation_plan(&mut self);
     fn enact_allocation_plan(&mut self, allocated_position: Position, allocated_size: ElementSize);
+
+    // Styles as specified are resolved against the parent's computed ones; layout and rendering read the result
+    fn resolve_styles(&mut self, context: &StyleContext);
+    fn get_computed_styles(&self) -> Styles;
+
+    // Id and class names matched by stylesheet selectors, alongside the element type
+    fn get_style_id(&self) -> Option<String> {
+        None
+    }
+    fn get_class_names(&self) -> Vec<String> {
+        vec![]
+    }
+
+    // Whether an interaction state change since the last call altered a layout-affecting style
+    fn take_needs_relayout(&mut self) -> bool {
+        false
+    }
+
+    // Steps running style transitions to the given time; returns whether any ran, so a frame is needed
+    fn advance_animations(&mut self, _now: Instant) -> bool {
+        false
+    }
+
+    // Overlay layer: popups drawn after the whole tree, receiving events before it
+    fn render_overlay(&self, _canvas: &Canvas, _viewport: &ElementSize) {}
+    fn handle_overlay_event(&mut self, _cursor_position: Point, _event_type: &EventType, _viewport: &ElementSize) -> bool {
+        false
+    }
+
+    // Context menu of the deepest element under the cursor that provides one
+    fn build_context_menu(&self, _cursor_position: Point) -> Option<Menu> {
+        None
+    }
+
+    // Tooltip of the deepest element under the cursor that has one, with the rect to anchor it to
+    fn find_tooltip(&self, _cursor_position: Point) -> Option<(Tooltip, Rect)> {
+        None
+    }
+
+    // Computed cursor of the deepest element under the cursor position
+    fn find_cursor(&self, _cursor_position: Point) -> Option<Cursor> {
+        None
+    }
+
+    // The cursor position handle_event receives, relative to the element's top left corner
+    fn get_local_position(&self, cursor_position: Point) -> Point {
+        cursor_position - self.get_position()
+    }
+
+    // Hit-testing. Positions are in the element's own coordinates, which map_to_local derives
+    // from its parent's by undoing the element's transform
+    fn map_to_local(&self, cursor_position: Point) -> Point {
+        cursor_position
+    }
+    fn contains_point(&self, cursor_position: Point) -> bool {
+        let (position, size) = (self.get_position(), self.get_size());
+        Rect::from_point_and_size(position, (size.width, size.height)).contains(cursor_position)
+    }
+    // Whether children can be hit at the position; not where the element clips them away
+    fn can_hit_children_at(&self, _cursor_position: Point) -> bool {
+        true
+    }
+
+    // Keyboard focus: focusable elements are focused by clicking them and by Tab traversal
+    fn is_focusable(&self) -> bool {
+        false
+    }
+    // Explicit place in the Tab order, as in HTML: positive indices come first, in increasing
+    // order, then elements without one in tree order. Negative ones are skipped by Tab
+    fn get_tab_index(&self) -> Option<i32> {
+        None
+    }
+
+    // Data dragged out of the element when a drag starts on it, for drop targets to take; see DragManager
+    fn get_drag_data(&self) -> Option<DragData> {
+        None
+    }
+
+    // Called on every element after messages changed the application state, for those bound to it; see Store
+    fn on_state_changed(&mut self, _state: &AppState) {}
+
+    // Indices of the children that events can reach, in paint order: those painted later are hit first.
+    // An index keeps leading to the same child, so that paths to it stay valid while siblings come and go
+    fn get_event_child_indices(&self) -> Vec<usize> {
+        vec![]
+    }
+    fn get_event_child(&self, _index: usize) -> Option<&dyn Element> {
+        None
+    }
+    fn get_event_child_mut(&mut self, _index: usize) -> Option<&mut dyn Element> {
+        None
+    }
 }
 
+pub const SCROLL_LINE_HEIGHT: f32 = 40.0; // Pixels scrolled per wheel line
+
+/*
+ * Input events, as dispatched down the element tree together with the cursor position. The
+ * position is in the receiving element's own coordinates: those of its layout rect, with the
+ * transforms of the element and its ancestors undone. Key, text and focus events are sent
+ * with the last known cursor position.
+ */
+#[derive(Clone, Debug)]
 pub enum EventType {
-    MouseClick,
-    MouseMove,
-    KeyPress(char),
+    MouseDown { button: MouseButton, modifiers: ModifiersState },
+    MouseUp { button: MouseButton, modifiers: ModifiersState },
+    MouseMove { modifiers: ModifiersState },
+    MouseEnter, // The cursor entered the element or one of its descendants; not propagated
+    MouseLeave, // The cursor left the element and its descendants; not propagated
+    MouseWheel { delta: WheelDelta, modifiers: ModifiersState },
+    Click { button: MouseButton, click_count: u32, modifiers: ModifiersState }, // Pressed and released on the element; 2 and 3 clicks for double and triple
+    DragStart { button: MouseButton, modifiers: ModifiersState }, // Moved far enough while pressed on the element, see DragManager
+    DragMove { modifiers: ModifiersState },                       // Sent to the dragged element wherever the pointer is
+    DragEnd { is_dropped: bool },
+    DragEnter,                  // Dragged data entered the element or one of its descendants; not propagated
+    DragLeave,                  // Dragged data left the element and its descendants, or was dropped; not propagated
+    DragOver { data: DragData }, // Dragged data is over the element; a drop target accepts it by handling the event
+    Drop { data: DragData },     // Released over the element after it accepted the data
+    Touch { id: u64, phase: TouchPhase }, // A finger on a touchscreen; UIManager turns touches into pointer events and gestures
+    Gesture(Gesture),                     // Sent to the element the gesture started on and its ancestors
+    KeyDown(KeyInput),
+    KeyUp(KeyInput),
+    TextInput(String), // Text typed, with the keyboard layout and dead keys applied; sent after its KeyDown
+    Focus { is_visible: bool }, // The element got keyboard focus, showing a focus ring if it came from the keyboard; not propagated
+    Blur,                       // The element lost keyboard focus; not propagated
+    FocusGained,       // The window gained keyboard focus; sent to every element
+    FocusLost,
+    Command(Command), // Triggered by a shortcut; sent like a key event, then to every element until one handles it
+}
+
+impl EventType {
+    pub fn is_press_of(&self, button: MouseButton) -> bool {
+        matches!(self, EventType::MouseDown { button: pressed, .. } if *pressed == button)
+    }
+
+    pub fn is_pointer_event(&self) -> bool {
+        matches!(self, EventType::MouseDown { .. } | EventType::MouseUp { .. } | EventType::MouseMove { .. } | EventType::MouseWheel { .. })
+    }
+
+    // Modifier keys held when the event happened, for the events that record them
+    #[allow(dead_code)]
+    pub fn get_modifiers(&self) -> Option<ModifiersState> {
+        match self {
+            EventType::MouseDown { modifiers, .. }
+            | EventType::MouseUp { modifiers, .. }
+            | EventType::MouseMove { modifiers }
+            | EventType::MouseWheel { modifiers, .. } => Some(*modifiers),
+            EventType::KeyDown(key_input) | EventType::KeyUp(key_input) => Some(key_input.modifiers),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum EventPhase {
+    Capturing, // At the target's ancestors, from the root down
+    AtTarget,
+    Bubbling,  // At the target's ancestors again, from the parent up
+}
+
+/*
+ * An event on its way along the path from the root to its target. A handler can stop it from
+ * going further, or mark it handled, so that handlers after it still see it but skip their
+ * default action, e.g. an outer list not scrolling for a wheel event an inner one used.
+ */
+pub struct Event {
+    pub event_type: EventType,
+    pub phase: EventPhase,
+    is_propagation_stopped: bool,
+    is_handled: bool,
+}
+
+impl Event {
+    pub fn new(event_type: EventType) -> Self {
+        Self { event_type, phase: EventPhase::AtTarget, is_propagation_stopped: false, is_handled: false }
+    }
+
+    #[allow(dead_code)]
+    pub fn stop_propagation(&mut self) {
+        self.is_propagation_stopped = true;
+    }
+
+    pub fn is_propagation_stopped(&self) -> bool {
+        self.is_propagation_stopped
+    }
+
+    pub fn set_handled(&mut self) {
+        self.is_handled = true;
+    }
+
+    pub fn is_handled(&self) -> bool {
+        self.is_handled
+    }
+}
+
+// Line deltas come from notched mouse wheels, pixel deltas from touchpads and smooth-scrolling mice
+#[derive(Clone, Copy, Debug)]
+pub enum WheelDelta {
+    Lines { x: f32, y: f32 },
+    Pixels { x: f32, y: f32 },
+}
+
+impl WheelDelta {
+    pub fn get_pixels(&self) -> (f32, f32) {
+        match *self {
+            WheelDelta::Lines { x, y } => (x * SCROLL_LINE_HEIGHT, y * SCROLL_LINE_HEIGHT),
+            WheelDelta::Pixels { x, y } => (x, y),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GesturePhase {
+    Started,
+    Moved,
+    Ended,
+}
+
+// Recognized from touches (see GestureRecognizer) or reported by the touchpad
+#[derive(Clone, Copy, Debug)]
+pub enum Gesture {
+    Pan { delta_x: f32, delta_y: f32, phase: GesturePhase }, // Content should follow the fingers by the delta
+    Pinch { scale: f32, phase: GesturePhase },               // Scale since the last event, centred on the position
+    LongPress,
+    Fling { velocity_x: f32, velocity_y: f32 },              // Pixels per second, as a pan ended
+}
+
+// What a drag carries, e.g. a link as `text/uri-list`; drop targets accept the formats they know
+#[derive(Clone, PartialEq, Debug)]
+pub struct DragData {
+    pub format: String,
+    pub value: String,
+}
+
+impl DragData {
+    pub fn new(format: &str, value: &str) -> Self {
+        Self { format: format.to_string(), value: value.to_string() }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct KeyInput {
+    pub logical_key: Key,          // The key as the keyboard layout maps it, e.g. 'z' on an AZERTY 'W' key
+    pub physical_key: PhysicalKey, // Where the key is on the keyboard, whatever the layout
+    pub is_repeat: bool,           // Sent again because the key is held down
+    pub modifiers: ModifiersState,
 }
 
 #[derive(Clone, Debug)]
@@ -0,0 +1,254 @@
+use std::time::Instant;
+
+use skia_safe::{Canvas, Color, Contains, Point, Rect};
+
+use crate::rendering::browser::internal::element_id_generator::IDGenerator;
+use crate::rendering::browser::layout::types::Position;
+use crate::rendering::browser::painting::{box_painter::BoxPainter, text_painter::TextPainter};
+use crate::rendering::browser::reactive::binding::Binding;
+use crate::rendering::browser::styling::{interaction_styles::InteractionStyles, style_resolver::{StyleContext, StyleResolver}, stylesheet::SelectorSubject};
+use crate::rendering::browser::styling::theme::ColorToken;
+
+use super::{common::ElementType, element::{Element, ElementSize, Event}, styles::{Cursor, Directions, Styles}, tooltip::Tooltip};
+
+const DEFAULT_HEIGHT: f32 = 24.0;
+
+/*
+ * A single line of text, cut off with an ellipsis when it doesn't fit. Its text and visibility
+ * can be bound to signals; a hidden label keeps its place but isn't painted or hit.
+ */
+pub struct Label {
+    _id: String,
+    position: Point,
+    size: ElementSize,
+    styles: Styles,
+    computed_styles: Styles,
+    style_id: Option<String>,
+    class_names: Vec<String>,
+    interaction: InteractionStyles,
+    text: String,
+    is_visible: bool,
+    tooltip: Option<Tooltip>,
+    text_binding: Option<Binding<String>>,
+    visibility_binding: Option<Binding<bool>>,
+    needs_relayout: bool, // The text changed, and with it the width the label fits
+}
+
+impl Label {
+    pub fn new(text: &str) -> Self {
+        Self {
+            _id: IDGenerator::get(),
+            position: Point::new(0.0, 0.0),
+            size: ElementSize::default(),
+            styles: Styles::default(),
+            computed_styles: Styles::initial(),
+            style_id: None,
+            class_names: vec![],
+            interaction: InteractionStyles::new(),
+            text: text.to_string(),
+            is_visible: true,
+            tooltip: None,
+            text_binding: None,
+            visibility_binding: None,
+            needs_relayout: false,
+        }
+    }
+
+    pub fn set_styles(mut self, styles: Styles) -> Self {
+        self.styles = styles;
+        self
+    }
+
+    // Id and class names that stylesheet selectors match against
+    #[allow(dead_code)]
+    pub fn set_style_id(mut self, style_id: &str) -> Self {
+        self.style_id = Some(style_id.to_string());
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn add_class(mut self, class_name: &str) -> Self {
+        self.class_names.push(class_name.to_string());
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn set_tooltip(mut self, tooltip: Tooltip) -> Self {
+        self.tooltip = Some(tooltip);
+        self
+    }
+
+    // Keeps the text computed from the signals it reads
+    #[allow(dead_code)]
+    pub fn set_text_binding(mut self, text: Box<dyn Fn() -> String>) -> Self {
+        let text_binding = Binding::new(text);
+        if let Some(text) = text_binding.take_changed(&self.text) {
+            self.text = text;
+        }
+        self.text_binding = Some(text_binding);
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn set_visibility_binding(mut self, is_visible: Box<dyn Fn() -> bool>) -> Self {
+        let visibility_binding = Binding::new(is_visible);
+        if let Some(is_visible) = visibility_binding.take_changed(&self.is_visible) {
+            self.is_visible = is_visible;
+        }
+        self.visibility_binding = Some(visibility_binding);
+        self
+    }
+
+    pub fn rect(&self) -> Rect {
+        Rect::from_point_and_size(self.position, (self.size.width, self.size.height))
+    }
+}
+
+impl Element for Label {
+    fn render(&self, canvas: &Canvas) {
+        if !self.is_visible {
+            return;
+        }
+        let border = self.computed_styles.border.unwrap_or_default();
+        BoxPainter::begin_group(canvas, self.rect(), &self.computed_styles);
+        BoxPainter::paint_box(canvas, self.rect(), &self.computed_styles, Color::TRANSPARENT.into());
+
+        let padding = self.computed_styles.padding.unwrap_or_default();
+        let text_rect = Rect::from_ltrb(
+            self.position.x + border.get_left_side().width + padding.left,
+            self.position.y,
+            self.position.x + self.size.width - border.get_right_side().width - padding.right,
+            self.position.y + self.size.height,
+        );
+        let text_color = self.computed_styles.text_color.unwrap_or(ColorToken::Text.into()).resolve();
+        TextPainter::paint_line(canvas, &self.text, text_rect, &self.computed_styles, text_color);
+        BoxPainter::end_group(canvas);
+    }
+
+    // Pulls the values of bindings whose signals changed; a new text may need a new width
+    fn update(&mut self) {
+        if let Some(text) = self.text_binding.as_ref().and_then(|binding| binding.take_changed(&self.text)) {
+            self.text = text;
+            self.needs_relayout = true;
+        }
+        if let Some(is_visible) = self.visibility_binding.as_ref().and_then(|binding| binding.take_changed(&self.is_visible)) {
+            self.is_visible = is_visible;
+        }
+    }
+
+    fn handle_event(&mut self, _cursor_position: Point, event: &mut Event) {
+        if self.interaction.on_event(&event.event_type) {
+            let previous_styles = self.computed_styles.clone();
+            self.resolve_styles(&self.interaction.get_context());
+            self.interaction.on_restyled(&previous_styles, &mut self.computed_styles);
+        }
+    }
+
+    fn set_position(&mut self, position: Point) {
+        self.position = position;
+    }
+
+    fn set_size(&mut self, size: ElementSize) {
+        self.size = size;
+    }
+
+    fn layout(&mut self, _available_space: Option<ElementSize>) {}
+
+    fn get_id(&self) -> String {
+        self._id.clone()
+    }
+
+    fn get_element_type(&self) -> ElementType {
+        ElementType::Label
+    }
+
+    fn get_children_mut(&mut self) -> Option<&mut Vec<Box<dyn Element>>> {
+        None
+    }
+
+    fn get_position(&self) -> Point {
+        self.position
+    }
+
+    fn get_size(&self) -> ElementSize {
+        self.size.clone()
+    }
+
+    fn get_styles(&self) -> Styles {
+        self.styles.clone()
+    }
+
+    fn resolve_styles(&mut self, context: &StyleContext) {
+        let specified = self.interaction.apply(&self.styles, context);
+        self.computed_styles = StyleResolver::resolve_element(&SelectorSubject::of(self), &specified, context);
+    }
+
+    fn get_computed_styles(&self) -> Styles {
+        self.computed_styles.clone()
+    }
+
+    fn get_style_id(&self) -> Option<String> {
+        self.style_id.clone()
+    }
+
+    fn get_class_names(&self) -> Vec<String> {
+        self.class_names.clone()
+    }
+
+    fn take_needs_relayout(&mut self) -> bool {
+        let needs_relayout = std::mem::take(&mut self.needs_relayout);
+        self.interaction.take_needs_relayout() || needs_relayout
+    }
+
+    fn advance_animations(&mut self, now: Instant) -> bool {
+        self.interaction.advance_transition(now, &mut self.computed_styles)
+    }
+
+    fn is_variable_size(&self) -> Directions {
+        let mut directions = Directions { horizontal: true, vertical: true };
+
+        if let Some(size) = &self.computed_styles.size {
+            if size.width.is_some() { directions.horizontal = false; }
+            if size.height.is_some() { directions.vertical = false; }
+        }
+
+        directions
+    }
+
+    // Unless the styles fix it, the width fits the text
+    fn compute_allocation_plan(&mut self) {
+        let size = self.computed_styles.size.unwrap_or_default();
+        let padding = self.computed_styles.padding.unwrap_or_default();
+        let border_width = self.computed_styles.border.unwrap_or_default().get_horizontal_width();
+        self.size = ElementSize {
+            width: size.width.unwrap_or_else(|| TextPainter::measure_width(&self.text, &self.computed_styles) + padding.left + padding.right + border_width),
+            height: size.height.unwrap_or(DEFAULT_HEIGHT),
+        };
+    }
+
+    fn enact_allocation_plan(&mut self, allocated_position: Position, allocated_size: ElementSize) {
+        self.set_position(Point::new(allocated_position.x, allocated_position.y));
+        self.set_size(allocated_size);
+    }
+
+    fn find_tooltip(&self, cursor_position: Point) -> Option<(Tooltip, Rect)> {
+        let cursor_position = self.computed_styles.map_to_local(self.rect(), cursor_position);
+        if !self.is_visible || !self.rect().contains(cursor_position) {
+            return None;
+        }
+        self.tooltip.clone().map(|tooltip| (tooltip, self.computed_styles.map_rect_from_local(self.rect(), self.rect())))
+    }
+
+    fn find_cursor(&self, cursor_position: Point) -> Option<Cursor> {
+        let cursor_position = self.computed_styles.map_to_local(self.rect(), cursor_position);
+        (self.is_visible && self.rect().contains(cursor_position)).then(|| self.computed_styles.cursor.unwrap_or_default())
+    }
+
+    fn map_to_local(&self, cursor_position: Point) -> Point {
+        self.computed_styles.map_to_local(self.rect(), cursor_position)
+    }
+
+    fn contains_point(&self, cursor_position: Point) -> bool {
+        self.is_visible && self.rect().contains(cursor_position)
+    }
+}
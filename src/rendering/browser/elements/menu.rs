@@ -0,0 +1,415 @@
+use skia_safe::{Canvas, Contains, Paint, Point, Rect};
+use winit::{event::MouseButton, keyboard::{Key, NamedKey}};
+
+use crate::rendering::browser::painting::{box_painter::BoxPainter, text_painter::TextPainter};
+use crate::rendering::browser::styling::{theme::ColorToken, theme_manager::ThemeManager};
+
+use super::{element::{ElementSize, EventType, KeyInput}, styles::Styles};
+
+const ITEM_HEIGHT: f32 = 26.0;
+const SEPARATOR_HEIGHT: f32 = 9.0;
+const MENU_MIN_WIDTH: f32 = 180.0;
+const HORIZONTAL_INSET: f32 = 12.0;
+const ACCELERATOR_GAP: f32 = 32.0;
+const SUBMENU_ARROW_WIDTH: f32 = 12.0;
+const TEXT_SIZE: f32 = 13.0;
+
+pub enum MenuItemKind {
+    Action(Box<dyn FnMut()>),
+    Submenu(Menu),
+    Separator,
+}
+
+pub struct MenuItem {
+    pub label: String,
+    pub accelerator: Option<String>,
+    pub enabled: bool,
+    pub kind: MenuItemKind,
+}
+
+impl MenuItem {
+    pub fn action(label: &str, on_select: Box<dyn FnMut()>) -> Self {
+        Self { label: label.to_string(), accelerator: None, enabled: true, kind: MenuItemKind::Action(on_select) }
+    }
+
+    pub fn submenu(label: &str, menu: Menu) -> Self {
+        Self { label: label.to_string(), accelerator: None, enabled: true, kind: MenuItemKind::Submenu(menu) }
+    }
+
+    pub fn separator() -> Self {
+        Self { label: String::new(), accelerator: None, enabled: false, kind: MenuItemKind::Separator }
+    }
+
+    pub fn set_accelerator(mut self, accelerator: &str) -> Self {
+        self.accelerator = Some(accelerator.to_string());
+        self
+    }
+
+    pub fn set_enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    fn height(&self) -> f32 {
+        match self.kind {
+            MenuItemKind::Separator => SEPARATOR_HEIGHT,
+            _ => ITEM_HEIGHT,
+        }
+    }
+
+    fn is_selectable(&self) -> bool {
+        self.enabled && !matches!(self.kind, MenuItemKind::Separator)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MenuResponse {
+    Ignored,
+    Handled,
+    Activated,
+    Dismissed,
+}
+
+/*
+ * Popup menu drawn in the overlay layer. Menus keep themselves inside the viewport:
+ * they shift back inside at the edges, and submenus open on the left of their parent
+ * when there is no room on the right.
+ */
+pub struct Menu {
+    items: Vec<MenuItem>,
+    position: Point,
+    is_open: bool,
+    highlighted_index: Option<usize>,
+    open_submenu_index: Option<usize>,
+}
+
+impl Menu {
+    pub fn new(items: Vec<MenuItem>) -> Self {
+        Self {
+            items,
+            position: Point::new(0.0, 0.0),
+            is_open: false,
+            highlighted_index: None,
+            open_submenu_index: None,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.is_open
+    }
+
+    pub fn open_at(&mut self, position: Point, viewport: &ElementSize) {
+        let size = self.size();
+        self.position = Point::new(
+            position.x.min(viewport.width - size.width).max(0.0),
+            position.y.min(viewport.height - size.height).max(0.0),
+        );
+        self.is_open = true;
+        self.highlighted_index = None;
+        self.open_submenu_index = None;
+    }
+
+    fn open_beside(&mut self, parent_rect: &Rect, item_top: f32, viewport: &ElementSize) {
+        let size = self.size();
+        let x = if parent_rect.right + size.width <= viewport.width {
+            parent_rect.right
+        } else {
+            parent_rect.left - size.width
+        };
+        self.open_at(Point::new(x, item_top), viewport);
+    }
+
+    pub fn close(&mut self) {
+        self.close_submenu();
+        self.is_open = false;
+        self.highlighted_index = None;
+    }
+
+    fn close_submenu(&mut self) {
+        if let Some(submenu) = self.open_submenu_mut() {
+            submenu.close();
+        }
+        self.open_submenu_index = None;
+    }
+
+    fn open_submenu_mut(&mut self) -> Option<&mut Menu> {
+        let index = self.open_submenu_index?;
+        match &mut self.items[index].kind {
+            MenuItemKind::Submenu(submenu) => Some(submenu),
+            _ => None,
+        }
+    }
+
+    fn open_submenu(&self) -> Option<&Menu> {
+        let index = self.open_submenu_index?;
+        match &self.items[index].kind {
+            MenuItemKind::Submenu(submenu) => Some(submenu),
+            _ => None,
+        }
+    }
+
+    // Menus float above the page, so they use the default typography rather than an element's
+    fn text_styles() -> Styles {
+        Styles { font_size: Some(TEXT_SIZE), ..Styles::initial() }
+    }
+
+    // Geometry
+    pub fn size(&self) -> ElementSize {
+        let text_styles = Menu::text_styles();
+        let content_width = self.items.iter().map(|item| {
+            let label_width = TextPainter::measure_width(&item.label, &text_styles);
+            let accelerator_width = item.accelerator.as_ref()
+                .map_or(0.0, |accelerator| ACCELERATOR_GAP + TextPainter::measure_width(accelerator, &text_styles));
+            let arrow_width = if matches!(item.kind, MenuItemKind::Submenu(_)) { SUBMENU_ARROW_WIDTH } else { 0.0 };
+            label_width + accelerator_width + arrow_width
+        }).fold(0.0, f32::max);
+
+        ElementSize {
+            width: (content_width + 2.0 * HORIZONTAL_INSET).max(MENU_MIN_WIDTH),
+            height: self.items.iter().map(|item| item.height()).sum(),
+        }
+    }
+
+    fn rect(&self) -> Rect {
+        let size = self.size();
+        Rect::from_point_and_size(self.position, (size.width, size.height))
+    }
+
+    fn item_rects(&self) -> Vec<Rect> {
+        let rect = self.rect();
+        let mut top = rect.top;
+        self.items.iter().map(|item| {
+            let item_rect = Rect::from_xywh(rect.left, top, rect.width(), item.height());
+            top += item.height();
+            item_rect
+        }).collect()
+    }
+
+    fn item_index_at(&self, cursor_position: Point) -> Option<usize> {
+        self.item_rects().iter().position(|item_rect| item_rect.contains(cursor_position))
+    }
+
+    fn contains(&self, cursor_position: Point) -> bool {
+        self.is_open && (self.rect().contains(cursor_position)
+            || self.open_submenu().is_some_and(|submenu| submenu.contains(cursor_position)))
+    }
+
+    // Interaction
+    fn highlight(&mut self, index: Option<usize>, viewport: &ElementSize) {
+        if self.highlighted_index == index {
+            return;
+        }
+        self.highlighted_index = index;
+        self.close_submenu();
+
+        if let Some(index) = index {
+            if !self.items[index].enabled {
+                return;
+            }
+            let parent_rect = self.rect();
+            let item_top = self.item_rects()[index].top;
+            if let MenuItemKind::Submenu(submenu) = &mut self.items[index].kind {
+                submenu.open_beside(&parent_rect, item_top, viewport);
+                self.open_submenu_index = Some(index);
+            }
+        }
+    }
+
+    fn activate(&mut self, index: usize, viewport: &ElementSize) -> MenuResponse {
+        if !self.items[index].is_selectable() {
+            return MenuResponse::Handled;
+        }
+
+        if matches!(self.items[index].kind, MenuItemKind::Submenu(_)) {
+            self.highlighted_index = None;
+            self.highlight(Some(index), viewport);
+            if let Some(submenu) = self.open_submenu_mut() {
+                submenu.move_highlight(1);
+            }
+            return MenuResponse::Handled;
+        }
+
+        if let MenuItemKind::Action(on_select) = &mut self.items[index].kind {
+            on_select();
+        }
+        self.close();
+        MenuResponse::Activated
+    }
+
+    fn move_highlight(&mut self, step: isize) {
+        let item_count = self.items.len() as isize;
+        if item_count == 0 {
+            return;
+        }
+        let mut index = self.highlighted_index.map_or(if step > 0 { -1 } else { item_count }, |index| index as isize);
+        for _ in 0..item_count {
+            index = (index + step).rem_euclid(item_count);
+            if self.items[index as usize].is_selectable() {
+                self.close_submenu();
+                self.highlighted_index = Some(index as usize);
+                return;
+            }
+        }
+    }
+
+    pub fn handle_event(&mut self, cursor_position: Point, event_type: &EventType, viewport: &ElementSize) -> MenuResponse {
+        if !self.is_open {
+            return MenuResponse::Ignored;
+        }
+
+        match event_type {
+            EventType::MouseDown { button: MouseButton::Left | MouseButton::Right, .. } => self.handle_click(cursor_position, event_type, viewport),
+            EventType::MouseMove { .. } => self.handle_hover(cursor_position, viewport),
+            EventType::KeyDown(KeyInput { logical_key: Key::Named(key), .. }) => self.handle_named_key(key, viewport),
+            EventType::KeyDown(_) | EventType::KeyUp(_) | EventType::TextInput(_) => MenuResponse::Handled,
+            _ => MenuResponse::Ignored,
+        }
+    }
+
+    fn handle_click(&mut self, cursor_position: Point, event_type: &EventType, viewport: &ElementSize) -> MenuResponse {
+        if let Some(submenu) = self.open_submenu_mut() {
+            if submenu.contains(cursor_position) {
+                let response = submenu.handle_click(cursor_position, event_type, viewport);
+                if response == MenuResponse::Activated {
+                    self.close();
+                }
+                return response;
+            }
+        }
+
+        match self.item_index_at(cursor_position) {
+            Some(index) => self.activate(index, viewport),
+            None => {
+                self.close();
+                match event_type { // A right click elsewhere may open another context menu
+                    EventType::MouseDown { button: MouseButton::Right, .. } => MenuResponse::Ignored,
+                    _ => MenuResponse::Dismissed,
+                }
+            }
+        }
+    }
+
+    fn handle_hover(&mut self, cursor_position: Point, viewport: &ElementSize) -> MenuResponse {
+        if let Some(submenu) = self.open_submenu_mut() {
+            if submenu.contains(cursor_position) {
+                return submenu.handle_hover(cursor_position, viewport);
+            }
+        }
+
+        match self.item_index_at(cursor_position) {
+            Some(index) => {
+                self.highlight(Some(index), viewport);
+                MenuResponse::Handled
+            }
+            None => MenuResponse::Ignored,
+        }
+    }
+
+    fn handle_named_key(&mut self, key: &NamedKey, viewport: &ElementSize) -> MenuResponse {
+        // Keys go to the deepest submenu that has a highlighted item
+        if let Some(submenu) = self.open_submenu_mut() {
+            if submenu.highlighted_index.is_some() {
+                return match submenu.handle_named_key(key, viewport) {
+                    MenuResponse::Dismissed => {
+                        submenu.close();
+                        MenuResponse::Handled
+                    }
+                    MenuResponse::Activated => {
+                        self.close();
+                        MenuResponse::Activated
+                    }
+                    response => response,
+                };
+            }
+        }
+
+        match key {
+            NamedKey::ArrowDown => self.move_highlight(1),
+            NamedKey::ArrowUp => self.move_highlight(-1),
+            NamedKey::ArrowRight => {
+                if let Some(index) = self.highlighted_index {
+                    if matches!(self.items[index].kind, MenuItemKind::Submenu(_)) {
+                        return self.activate(index, viewport);
+                    }
+                }
+            }
+            NamedKey::Enter | NamedKey::Space => {
+                if let Some(index) = self.highlighted_index {
+                    return self.activate(index, viewport);
+                }
+            }
+            NamedKey::ArrowLeft | NamedKey::Escape => {
+                self.close();
+                return MenuResponse::Dismissed;
+            }
+            _ => (),
+        }
+        MenuResponse::Handled
+    }
+
+    // Rendering
+    pub fn render(&self, canvas: &Canvas) {
+        if !self.is_open {
+            return;
+        }
+        let rect = self.rect();
+        let theme = ThemeManager::current();
+        let surface_styles = Styles::popup_surface();
+        BoxPainter::paint_box_background(canvas, rect, &surface_styles, ColorToken::SurfaceRaised.into());
+
+        canvas.save();
+        BoxPainter::clip_to_padding_box(canvas, rect, &surface_styles.border.unwrap_or_default());
+
+        let mut paint = Paint::default();
+        paint.set_anti_alias(true);
+
+        let text_styles = Menu::text_styles();
+
+        for (index, (item, item_rect)) in self.items.iter().zip(self.item_rects()).enumerate() {
+            if let MenuItemKind::Separator = item.kind {
+                paint.set_color(theme.color(ColorToken::Border));
+                canvas.draw_line((item_rect.left + 1.0, item_rect.center_y()), (item_rect.right - 1.0, item_rect.center_y()), &paint);
+                continue;
+            }
+
+            let is_highlighted = self.highlighted_index == Some(index) && item.enabled;
+            if is_highlighted {
+                paint.set_color(theme.color(ColorToken::Accent));
+                canvas.draw_rect(item_rect, &paint);
+            }
+
+            let text_color = match (is_highlighted, item.enabled) {
+                (true, _) => theme.color(ColorToken::OnAccent),
+                (false, true) => theme.color(ColorToken::Text),
+                (false, false) => theme.color(ColorToken::TextMuted),
+            };
+            let text_rect = item_rect.with_inset((HORIZONTAL_INSET, 0.0));
+            TextPainter::paint_line(canvas, &item.label, text_rect, &text_styles, text_color);
+
+            if let Some(accelerator) = &item.accelerator {
+                let accelerator_width = TextPainter::measure_width(accelerator, &text_styles);
+                let accelerator_color = if is_highlighted { text_color } else { theme.color(ColorToken::TextMuted) };
+                let accelerator_rect = Rect::new(text_rect.right - accelerator_width, text_rect.top, text_rect.right, text_rect.bottom);
+                TextPainter::paint_line(canvas, accelerator, accelerator_rect, &text_styles, accelerator_color);
+            }
+
+            if let MenuItemKind::Submenu(_) = item.kind {
+                let tip = Point::new(item_rect.right - HORIZONTAL_INSET, item_rect.center_y());
+                let mut path = skia_safe::Path::new();
+                path.move_to((tip.x - 4.0, tip.y - 4.0));
+                path.line_to(tip);
+                path.line_to((tip.x - 4.0, tip.y + 4.0));
+                path.close();
+                paint.set_color(text_color);
+                canvas.draw_path(&path, &paint);
+            }
+        }
+
+        canvas.restore();
+        BoxPainter::paint_border(canvas, rect, &surface_styles.border.unwrap_or_default());
+
+        if let Some(submenu) = self.open_submenu() {
+            submenu.render(canvas);
+        }
+    }
+}
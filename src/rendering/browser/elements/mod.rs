@@ -1,6 +1,14 @@
 pub mod element;
 pub mod button;
 pub mod row;
+pub mod select;
+pub mod menu;
+pub mod tooltip;
+pub mod tab;
+pub mod tab_strip;
+pub mod virtual_list;
+pub mod canvas_element;
+pub mod label;
 
 pub mod styles;
 
@@ -1,9 +1,13 @@
-use skia_safe::{Canvas, Color, Paint, Point, Rect, PaintStyle};
+use std::time::Instant;
 
-use crate::rendering::browser::{internal::element_id_generator::IDGenerator, layout::{row_layout_manager::RowLayoutManager, space_distribution_manager::SpaceDistributionManager, types::{ChildSpaceAllocationPlan, DeficitResolutionReport, Position, RowSpaceAllocationPlan}}};
+use skia_safe::{Canvas, Color, Contains, Point, Rect};
+
+use crate::rendering::browser::{internal::element_id_generator::IDGenerator, painting::box_painter::BoxPainter, layout::{row_layout_manager::RowLayoutManager, space_distribution_manager::SpaceDistributionManager, types::{ChildSpaceAllocationPlan, DeficitResolutionReport, Position, RowSpaceAllocationPlan}}};
 use crate::rendering::browser::layout::types::VerticalHorizontal;
+use crate::rendering::browser::reactive::binding::Binding;
+use crate::rendering::browser::styling::{interaction_styles::{InteractionStyles, StateStyles}, style_resolver::{StyleContext, StyleResolver}, stylesheet::SelectorSubject};
 
-use super::{common::ElementType, element::{Element, ElementSize, EventType}, styles::{Directions, Margin, RowItemsAlignment, Spacing, Styles}};
+use super::{common::ElementType, element::{Element, ElementSize, Event, EventType}, menu::Menu, tooltip::Tooltip, styles::{Cursor, Direction, Directions, Margin, Overflow, RowItemsAlignment, Spacing, Styles}};
 
 pub struct Row {
     _id: String,
@@ -16,6 +20,15 @@ pub struct Row {
     pub row_allocation_plan: RowSpaceAllocationPlan,
     pub deficit_resolution_report: Option<DeficitResolutionReport>,
     pub styles: Styles,
+    pub computed_styles: Styles,
+    pub style_id: Option<String>,
+    pub class_names: Vec<String>,
+    interaction: InteractionStyles,
+    pub context_menu_builder: Option<Box<dyn Fn() -> Menu>>,
+    pub tooltip: Option<Tooltip>,
+    children_binding: Option<Box<dyn FnMut() -> Option<Vec<Box<dyn Element>>>>>, // New children once the items they're built from change
+    children_style_context: StyleContext, // Kept for children created after the last restyle
+    needs_relayout: bool, // The bound children were rebuilt
 }
 
 impl Row {
@@ -33,6 +46,15 @@ impl Row {
             row_allocation_plan: RowSpaceAllocationPlan::new(id),
             deficit_resolution_report: None,
             styles: Styles::default(),
+            computed_styles: Styles::initial(),
+            style_id: None,
+            class_names: vec![],
+            interaction: InteractionStyles::new(),
+            context_menu_builder: None,
+            tooltip: None,
+            children_binding: None,
+            children_style_context: StyleContext::default(),
+            needs_relayout: false,
         }
     }
 
@@ -41,6 +63,36 @@ impl Row {
         self
     }
 
+    // Id and class names that stylesheet selectors match against
+    pub fn set_style_id(mut self, style_id: &str) -> Self {
+        self.style_id = Some(style_id.to_string());
+        self
+    }
+
+    pub fn add_class(mut self, class_name: &str) -> Self {
+        self.class_names.push(class_name.to_string());
+        self
+    }
+
+    // Overrides merged over the styles while the element is hovered, pressed, focused or disabled
+    #[allow(dead_code)]
+    pub fn set_state_styles(mut self, state_styles: StateStyles) -> Self {
+        self.interaction.state_styles = state_styles;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn set_disabled(mut self, is_disabled: bool) -> Self {
+        self.interaction.set_disabled(is_disabled);
+        self
+    }
+
+    fn restyle_for_interaction(&mut self) {
+        let previous_styles = self.computed_styles.clone();
+        self.resolve_styles(&self.interaction.get_context());
+        self.interaction.on_restyled(&previous_styles, &mut self.computed_styles);
+    }
+
     #[allow(dead_code)]
     pub fn set_position(mut self, position: Point) -> Self {
         self.position = position;
@@ -69,50 +121,89 @@ impl Row {
         self.children.extend(children);
         self
     }
+
+    // Replaces the children with one built per item, and builds them again whenever the items,
+    // computed from the signals they read, change
+    pub fn set_children_binding<T: PartialEq + 'static>(mut self, items: Box<dyn Fn() -> Vec<T>>, build_child: Box<dyn Fn(&T) -> Box<dyn Element>>) -> Self {
+        let items_binding = Binding::new(items);
+        let mut current_items = vec![];
+        let mut update_children = move || {
+            let items = items_binding.take_changed(&current_items)?;
+            let children = items.iter().map(|item| build_child(item)).collect();
+            current_items = items;
+            Some(children)
+        };
+        self.children = update_children().unwrap_or_default();
+        self.children_binding = Some(Box::new(update_children));
+        self
+    }
+
+    // Swaps in new children, styled against the same context as the ones they replace
+    pub fn replace_children(&mut self, children: Vec<Box<dyn Element>>) {
+        self.children = children;
+        for child in &mut self.children {
+            child.resolve_styles(&self.children_style_context);
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn set_context_menu(mut self, context_menu_builder: Box<dyn Fn() -> Menu>) -> Self {
+        self.context_menu_builder = Some(context_menu_builder);
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn set_tooltip(mut self, tooltip: Tooltip) -> Self {
+        self.tooltip = Some(tooltip);
+        self
+    }
+
+    fn rect(&self) -> Rect {
+        Rect::from_point_and_size(self.position, (self.size.width, self.size.height))
+    }
     
     fn render_background_and_border(&self, canvas: &Canvas) {
-        let row_rect = Rect::from_point_and_size(
-            Point::new(self.position.x,
-                       self.position.y),
-            (self.size.width,
-             self.size.height)
-        );
-        let mut paint = Paint::default();
-        paint.set_anti_alias(true);
-        paint.set_style(PaintStyle::Fill);
-        paint.set_color(self.styles.color.unwrap_or(Color::TRANSPARENT));
-        canvas.draw_rect(row_rect, &paint);
-
-        paint.set_anti_alias(true);
-        paint.set_style(PaintStyle::Stroke);
-        paint.set_stroke_width(self.styles.border.as_ref().map_or(0.0, |b| b.width));
-        paint.set_color(self.styles.border.as_ref().map_or(Color::TRANSPARENT, |b| b.color));
-        canvas.draw_rect(row_rect, &paint);
+        BoxPainter::paint_box(canvas, self.rect(), &self.computed_styles, Color::TRANSPARENT.into());
     }
 
     pub fn get_spacing_x(&self) -> f32 {
-        self.styles.spacing.clone().unwrap_or_default().spacing_x
+        self.computed_styles.spacing.clone().unwrap_or_default().spacing_x
     }
 }
 
 impl Element for Row {
     fn render(&self, canvas: &Canvas) {
+        BoxPainter::begin_group(canvas, self.rect(), &self.computed_styles);
         self.render_background_and_border(canvas);
-    
+
+        if let Some(Overflow::Clip) = self.computed_styles.overflow {
+            canvas.save();
+            BoxPainter::clip_to_padding_box(canvas, self.rect(), &self.computed_styles.border.unwrap_or_default());
+        }
+
         for child in &self.children {
             child.render(canvas);
         }
+
+        if let Some(Overflow::Clip) = self.computed_styles.overflow {
+            canvas.restore();
+        }
+        BoxPainter::end_group(canvas);
     }
 
     fn update(&mut self) {
+        if let Some(children) = self.children_binding.as_mut().and_then(|update_children| update_children()) {
+            self.replace_children(children);
+            self.needs_relayout = true;
+        }
         for child in &mut self.children {
             child.update();
         }
     }
     
-    fn handle_event(&mut self, cursor_position: Point, event_type: &EventType) {
-        for child in &mut self.children {
-            child.handle_event(cursor_position, event_type);
+    fn handle_event(&mut self, _cursor_position: Point, event: &mut Event) {
+        if self.interaction.on_event(&event.event_type) {
+            self.restyle_for_interaction();
         }
     }
 
@@ -152,10 +243,46 @@ impl Element for Row {
         self.styles.clone()
     }
 
+    fn resolve_styles(&mut self, context: &StyleContext) {
+        let specified = self.interaction.apply(&self.styles, context);
+        self.computed_styles = StyleResolver::resolve_element(&SelectorSubject::of(self), &specified, context);
+        let children_style_context = context.for_children(SelectorSubject::of(self), &self.computed_styles);
+        // Children only inherit from it, so they need restyling only if an inherited property changed
+        if children_style_context.resolves_like(&self.children_style_context) {
+            return;
+        }
+        self.children_style_context = children_style_context;
+        for child in &mut self.children {
+            child.resolve_styles(&self.children_style_context);
+        }
+    }
+
+    fn get_computed_styles(&self) -> Styles {
+        self.computed_styles.clone()
+    }
+
+    fn get_style_id(&self) -> Option<String> {
+        self.style_id.clone()
+    }
+
+    fn get_class_names(&self) -> Vec<String> {
+        self.class_names.clone()
+    }
+
+    fn take_needs_relayout(&mut self) -> bool {
+        let needs_relayout = self.interaction.take_needs_relayout() | std::mem::take(&mut self.needs_relayout);
+        self.children.iter_mut().fold(needs_relayout, |needs_relayout, child| child.take_needs_relayout() || needs_relayout)
+    }
+
+    fn advance_animations(&mut self, now: Instant) -> bool {
+        let is_animating = self.interaction.advance_transition(now, &mut self.computed_styles);
+        self.children.iter_mut().fold(is_animating, |is_animating, child| child.advance_animations(now) || is_animating)
+    }
+
     fn is_variable_size(&self) -> Directions {
         let mut directions = Directions { horizontal: true, vertical: true };
 
-        if let Some(size) = &self.styles.size {
+        if let Some(size) = &self.computed_styles.size {
             if size.width.is_some() { directions.horizontal = false; }
             if size.height.is_some() { directions.vertical = false; }
         }
@@ -179,6 +306,15 @@ impl Element for Row {
         // println!("Distributing children for row with ID: {}", self.get_id());
         SpaceDistributionManager::distribute_row_children(self);
 
+        // Right-to-left rows mirror the planned positions within their own bounds
+        if let Some(Direction::RightToLeft) = self.computed_styles.direction {
+            let (left, right) = (self.position.x, self.position.x + self.size.width);
+            for child in &mut self.children {
+                let position = child.get_position();
+                child.set_position(Point::new(left + right - position.x - child.get_size().width, position.y));
+            }
+        }
+
         let child_plans = self.row_allocation_plan.child_space_allocation_plans
             .iter()
             .map(|plan| (plan.element_id.clone(), plan.child_planned_position.clone(), plan.child_planned_size.clone()))
@@ -191,4 +327,67 @@ impl Element for Row {
 
         }
     }
+
+    fn render_overlay(&self, canvas: &Canvas, viewport: &ElementSize) {
+        for child in &self.children {
+            child.render_overlay(canvas, viewport);
+        }
+    }
+
+    fn handle_overlay_event(&mut self, cursor_position: Point, event_type: &EventType, viewport: &ElementSize) -> bool {
+        // Later children are drawn on top, so they get the first chance to consume the event
+        self.children.iter_mut().rev()
+            .any(|child| child.handle_overlay_event(cursor_position, event_type, viewport))
+    }
+
+    fn build_context_menu(&self, cursor_position: Point) -> Option<Menu> {
+        let cursor_position = self.computed_styles.map_to_local(self.rect(), cursor_position);
+        if !self.rect().contains(cursor_position) {
+            return None;
+        }
+        self.children.iter().rev()
+            .find_map(|child| child.build_context_menu(cursor_position))
+            .or_else(|| self.context_menu_builder.as_ref().map(|builder| builder()))
+    }
+
+    fn find_tooltip(&self, cursor_position: Point) -> Option<(Tooltip, Rect)> {
+        let cursor_position = self.computed_styles.map_to_local(self.rect(), cursor_position);
+        if !self.rect().contains(cursor_position) {
+            return None;
+        }
+        self.children.iter().rev()
+            .find_map(|child| child.find_tooltip(cursor_position))
+            .or_else(|| self.tooltip.clone().map(|tooltip| (tooltip, self.rect())))
+            .map(|(tooltip, rect)| (tooltip, self.computed_styles.map_rect_from_local(self.rect(), rect)))
+    }
+
+    fn find_cursor(&self, cursor_position: Point) -> Option<Cursor> {
+        let cursor_position = self.computed_styles.map_to_local(self.rect(), cursor_position);
+        if !self.rect().contains(cursor_position) {
+            return None;
+        }
+        self.children.iter().rev()
+            .find_map(|child| child.find_cursor(cursor_position))
+            .or(self.computed_styles.cursor)
+    }
+
+    fn map_to_local(&self, cursor_position: Point) -> Point {
+        self.computed_styles.map_to_local(self.rect(), cursor_position)
+    }
+
+    fn can_hit_children_at(&self, cursor_position: Point) -> bool {
+        !matches!(self.computed_styles.overflow, Some(Overflow::Clip)) || self.rect().contains(cursor_position)
+    }
+
+    fn get_event_child_indices(&self) -> Vec<usize> {
+        (0..self.children.len()).collect()
+    }
+
+    fn get_event_child(&self, index: usize) -> Option<&dyn Element> {
+        self.children.get(index).map(|child| child.as_ref() as &dyn Element)
+    }
+
+    fn get_event_child_mut(&mut self, index: usize) -> Option<&mut dyn Element> {
+        self.children.get_mut(index).map(|child| child.as_mut() as &mut dyn Element)
+    }
 }
\ No newline at end of file
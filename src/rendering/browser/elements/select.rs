@@ -0,0 +1,507 @@
+use std::time::{Duration, Instant};
+
+use skia_safe::{Canvas, Contains, Paint, Point, Rect};
+use winit::{event::MouseButton, keyboard::{Key, NamedKey}};
+
+use crate::rendering::browser::internal::element_id_generator::IDGenerator;
+use crate::rendering::browser::layout::types::Position;
+use crate::rendering::browser::painting::{box_painter::BoxPainter, text_painter::TextPainter};
+use crate::rendering::browser::styling::{interaction_styles::{InteractionStyles, StateStyles}, style_resolver::{StyleContext, StyleResolver}, stylesheet::SelectorSubject};
+use crate::rendering::browser::styling::{theme::ColorToken, theme_manager::ThemeManager};
+
+use super::{common::ElementType, element::{Element, ElementSize, Event, EventType, KeyInput}, styles::{Border, Cursor, Directions, Styles}};
+
+const OPTION_HEIGHT: f32 = 28.0;
+const MAX_VISIBLE_OPTIONS: usize = 8;
+const TEXT_INSET: f32 = 8.0;
+const TYPE_AHEAD_TIMEOUT: Duration = Duration::from_millis(1000);
+
+pub struct Select {
+    _id: String,
+    position: Point,
+    size: ElementSize,
+    styles: Styles,
+    computed_styles: Styles,
+    style_id: Option<String>,
+    class_names: Vec<String>,
+    interaction: InteractionStyles,
+    options: Vec<String>,
+    selected_index: Option<usize>,
+    highlighted_index: usize,
+    first_visible_index: usize,
+    wheel_remainder: f32, // Wheel movement short of a whole option, carried over to the next one
+    is_open: bool,
+    type_ahead_buffer: String,
+    last_type_ahead: Option<Instant>,
+    tab_index: Option<i32>,
+    pub on_change: Box<dyn FnMut(usize, &str)>,
+}
+
+impl Select {
+    pub fn new(options: Vec<String>, on_change: Box<dyn FnMut(usize, &str)>) -> Self {
+        let styles = Styles::default();
+        let selected_index = if options.is_empty() { None } else { Some(0) };
+
+        Self {
+            _id: IDGenerator::get(),
+            position: Point::new(0.0, 0.0),
+            size: Select::get_size_from_styles(&styles),
+            styles,
+            computed_styles: Styles::initial(),
+            style_id: None,
+            class_names: vec![],
+            interaction: InteractionStyles::new(),
+            options,
+            selected_index,
+            highlighted_index: 0,
+            first_visible_index: 0,
+            wheel_remainder: 0.0,
+            is_open: false,
+            type_ahead_buffer: String::new(),
+            last_type_ahead: None,
+            tab_index: None,
+            on_change,
+        }
+    }
+
+    fn get_size_from_styles(styles: &Styles) -> ElementSize {
+        if let Some(size) = styles.size {
+            ElementSize { width: size.width.unwrap_or(0.0), height: size.height.unwrap_or(0.0) }
+        } else {
+            ElementSize { width: 0.0, height: 0.0 }
+        }
+    }
+
+    pub fn set_styles(mut self, styles: Styles) -> Self {
+        self.styles = styles;
+        self.size = Select::get_size_from_styles(&self.styles);
+        self
+    }
+
+    // Id and class names that stylesheet selectors match against
+    #[allow(dead_code)]
+    pub fn set_style_id(mut self, style_id: &str) -> Self {
+        self.style_id = Some(style_id.to_string());
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn add_class(mut self, class_name: &str) -> Self {
+        self.class_names.push(class_name.to_string());
+        self
+    }
+
+    // Overrides merged over the styles while the element is hovered, pressed, focused or disabled
+    #[allow(dead_code)]
+    pub fn set_state_styles(mut self, state_styles: StateStyles) -> Self {
+        self.interaction.state_styles = state_styles;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn set_disabled(mut self, is_disabled: bool) -> Self {
+        self.interaction.set_disabled(is_disabled);
+        self
+    }
+
+    fn restyle_for_interaction(&mut self) {
+        let previous_styles = self.computed_styles.clone();
+        self.resolve_styles(&self.interaction.get_context());
+        self.interaction.on_restyled(&previous_styles, &mut self.computed_styles);
+    }
+
+    // Place in Tab order: positive indices come first, negative ones are skipped
+    #[allow(dead_code)]
+    pub fn set_tab_index(mut self, tab_index: i32) -> Self {
+        self.tab_index = Some(tab_index);
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn set_selected_index(mut self, index: usize) -> Self {
+        if index < self.options.len() {
+            self.selected_index = Some(index);
+        }
+        self
+    }
+
+    pub fn get_selected_value(&self) -> Option<&str> {
+        self.selected_index.map(|index| self.options[index].as_str())
+    }
+
+    pub fn rect(&self) -> Rect {
+        Rect::from_point_and_size(self.position, (self.size.width, self.size.height))
+    }
+
+    // Popup
+    fn open(&mut self) {
+        self.is_open = true;
+        self.highlighted_index = self.selected_index.unwrap_or(0);
+        self.type_ahead_buffer.clear();
+        self.wheel_remainder = 0.0;
+        self.scroll_to_highlighted();
+    }
+
+    fn close(&mut self) {
+        self.is_open = false;
+        self.type_ahead_buffer.clear();
+    }
+
+    fn choose(&mut self, index: usize) {
+        self.selected_index = Some(index);
+        self.close();
+        (self.on_change)(index, &self.options[index]);
+    }
+
+    fn visible_option_count(&self) -> usize {
+        self.options.len().min(MAX_VISIBLE_OPTIONS)
+    }
+
+    /*
+     * Places the option list below the select, flipping it upwards
+     * when it would overflow the viewport and there is more room above
+     */
+    fn popup_rect(&self, viewport: &ElementSize) -> Rect {
+        let popup_height = self.visible_option_count() as f32 * OPTION_HEIGHT;
+        let space_below = viewport.height - (self.position.y + self.size.height);
+        let space_above = self.position.y;
+
+        let top = if popup_height > space_below && space_above > space_below {
+            self.position.y - popup_height
+        } else {
+            self.position.y + self.size.height
+        };
+
+        Rect::from_point_and_size(Point::new(self.position.x, top), (self.size.width, popup_height))
+    }
+
+    fn option_index_at(&self, cursor_position: Point, popup_rect: &Rect) -> Option<usize> {
+        if !popup_rect.contains(cursor_position) {
+            return None;
+        }
+        let row = ((cursor_position.y - popup_rect.top) / OPTION_HEIGHT) as usize;
+        let index = self.first_visible_index + row;
+
+        if index < self.options.len() { Some(index) } else { None }
+    }
+
+    fn move_highlight(&mut self, new_index: usize) {
+        if self.options.is_empty() {
+            return;
+        }
+        self.highlighted_index = new_index.min(self.options.len() - 1);
+        self.scroll_to_highlighted();
+    }
+
+    fn scroll_to_highlighted(&mut self) {
+        let visible_count = self.visible_option_count();
+        if self.highlighted_index < self.first_visible_index {
+            self.first_visible_index = self.highlighted_index;
+        } else if visible_count > 0 && self.highlighted_index >= self.first_visible_index + visible_count {
+            self.first_visible_index = self.highlighted_index + 1 - visible_count;
+        }
+    }
+
+    // Scrolls the list by whole options, leaving the highlight where it is
+    fn scroll_by_wheel(&mut self, delta_y: f32) {
+        self.wheel_remainder -= delta_y;
+        let rows = (self.wheel_remainder / OPTION_HEIGHT).trunc();
+        self.wheel_remainder -= rows * OPTION_HEIGHT;
+
+        let last_first_visible_index = self.options.len() - self.visible_option_count();
+        self.first_visible_index = (self.first_visible_index as i64 + rows as i64).clamp(0, last_first_visible_index as i64) as usize;
+    }
+
+    // Keyboard
+    fn handle_named_key(&mut self, key: &NamedKey) -> bool {
+        match key {
+            NamedKey::ArrowDown => self.move_highlight(self.highlighted_index + 1),
+            NamedKey::ArrowUp => self.move_highlight(self.highlighted_index.saturating_sub(1)),
+            NamedKey::Home => self.move_highlight(0),
+            NamedKey::End => self.move_highlight(self.options.len().saturating_sub(1)),
+            NamedKey::Enter => {
+                if !self.options.is_empty() {
+                    self.choose(self.highlighted_index);
+                }
+            }
+            NamedKey::Escape | NamedKey::Tab => self.close(),
+            _ => return false,
+        }
+        true
+    }
+
+    /*
+     * Type-ahead: characters typed in quick succession form a prefix that is matched
+     * against the option labels, starting after the highlighted option. Repeating
+     * a single character cycles through the options starting with it.
+     */
+    fn handle_type_ahead(&mut self, character: char) {
+        let now = Instant::now();
+        let is_continuation = self.last_type_ahead
+            .is_some_and(|last| now.duration_since(last) < TYPE_AHEAD_TIMEOUT);
+        if !is_continuation {
+            self.type_ahead_buffer.clear();
+        }
+        self.last_type_ahead = Some(now);
+        self.type_ahead_buffer.extend(character.to_lowercase());
+
+        let is_repeated_character = self.type_ahead_buffer.chars().all(|c| Some(c) == self.type_ahead_buffer.chars().next());
+        let prefix = if is_repeated_character {
+            self.type_ahead_buffer.chars().take(1).collect::<String>()
+        } else {
+            self.type_ahead_buffer.clone()
+        };
+        let start = if is_repeated_character { self.highlighted_index + 1 } else { self.highlighted_index };
+
+        let option_count = self.options.len();
+        let matching_index = (0..option_count)
+            .map(|offset| (start + offset) % option_count)
+            .find(|index| self.options[*index].to_lowercase().starts_with(&prefix));
+
+        if let Some(index) = matching_index {
+            self.move_highlight(index);
+        }
+    }
+
+    // Rendering
+    fn render_field(&self, canvas: &Canvas) {
+        let rect = self.rect();
+        let styles = Styles {
+            border: Some(self.computed_styles.border.unwrap_or(Border { width: 1.0, color: ColorToken::Border.into(), ..Default::default() })),
+            ..self.computed_styles.clone()
+        };
+        BoxPainter::paint_box(canvas, rect, &styles, ColorToken::SurfaceRaised.into());
+
+        let text_color = self.computed_styles.text_color.unwrap_or(ColorToken::Text.into()).resolve();
+        let arrow_center = Point::new(rect.right - TEXT_INSET - 4.0, rect.center_y());
+        if let Some(value) = self.get_selected_value() {
+            let text_rect = Rect::new(rect.left + TEXT_INSET, rect.top, arrow_center.x - TEXT_INSET, rect.bottom);
+            TextPainter::paint_line(canvas, value, text_rect, &self.computed_styles, text_color);
+        }
+
+        // Disclosure arrow
+        let mut text_paint = Paint::default();
+        text_paint.set_anti_alias(true);
+        text_paint.set_color(text_color);
+        let mut path = skia_safe::Path::new();
+        path.move_to((arrow_center.x - 4.0, arrow_center.y - 2.0));
+        path.line_to((arrow_center.x + 4.0, arrow_center.y - 2.0));
+        path.line_to((arrow_center.x, arrow_center.y + 3.0));
+        path.close();
+        canvas.draw_path(&path, &text_paint);
+    }
+}
+
+impl Element for Select {
+    fn render(&self, canvas: &Canvas) {
+        BoxPainter::begin_group(canvas, self.rect(), &self.computed_styles);
+        self.render_field(canvas);
+        if self.interaction.state.is_focus_visible {
+            BoxPainter::paint_focus_ring(canvas, self.rect(), &self.computed_styles.border.unwrap_or_default().radius);
+        }
+        BoxPainter::end_group(canvas);
+    }
+
+    fn update(&mut self) {}
+
+    fn handle_event(&mut self, _cursor_position: Point, event: &mut Event) {
+        if self.interaction.on_event(&event.event_type) {
+            self.restyle_for_interaction();
+        }
+
+        match event.event_type {
+            EventType::MouseDown { button: MouseButton::Left, .. } if !self.interaction.state.is_disabled => {
+                self.open();
+                event.set_handled();
+            }
+            // Only reaches the closed list: the open one takes keys as an overlay
+            EventType::KeyDown(KeyInput { logical_key: Key::Named(NamedKey::Enter | NamedKey::Space | NamedKey::ArrowDown | NamedKey::ArrowUp), .. })
+                if !self.interaction.state.is_disabled => {
+                self.open();
+                event.set_handled();
+            }
+            _ => (),
+        }
+    }
+
+    fn set_position(&mut self, position: Point) {
+        self.position = position;
+    }
+
+    fn set_size(&mut self, size: ElementSize) {
+        self.size = size;
+    }
+
+    fn layout(&mut self, available_space: Option<ElementSize>) {
+        if let Some(available_space) = available_space {
+            self.size = ElementSize {
+                width: self.size.width.min(available_space.width),
+                height: self.size.height.min(available_space.height),
+            }
+        }
+    }
+
+    fn get_id(&self) -> String {
+        self._id.clone()
+    }
+
+    fn get_element_type(&self) -> ElementType {
+        ElementType::Select
+    }
+
+    fn get_children_mut(&mut self) -> Option<&mut Vec<Box<dyn Element>>> {
+        None
+    }
+
+    fn get_position(&self) -> Point {
+        self.position
+    }
+
+    fn get_size(&self) -> ElementSize {
+        self.size.clone()
+    }
+
+    fn get_styles(&self) -> Styles {
+        self.styles.clone()
+    }
+
+    fn resolve_styles(&mut self, context: &StyleContext) {
+        let specified = self.interaction.apply(&self.styles, context);
+        self.computed_styles = StyleResolver::resolve_element(&SelectorSubject::of(self), &specified, context);
+    }
+
+    fn get_computed_styles(&self) -> Styles {
+        self.computed_styles.clone()
+    }
+
+    fn get_style_id(&self) -> Option<String> {
+        self.style_id.clone()
+    }
+
+    fn get_class_names(&self) -> Vec<String> {
+        self.class_names.clone()
+    }
+
+    fn take_needs_relayout(&mut self) -> bool {
+        self.interaction.take_needs_relayout()
+    }
+
+    fn advance_animations(&mut self, now: Instant) -> bool {
+        self.interaction.advance_transition(now, &mut self.computed_styles)
+    }
+
+    fn is_variable_size(&self) -> Directions {
+        let mut directions = Directions { horizontal: true, vertical: true };
+
+        if let Some(size) = &self.computed_styles.size {
+            if size.width.is_some() { directions.horizontal = false; }
+            if size.height.is_some() { directions.vertical = false; }
+        }
+
+        directions
+    }
+
+    fn compute_allocation_plan(&mut self) {
+        let size = self.computed_styles.size.unwrap_or_default();
+        self.size = ElementSize {
+            width: size.width.unwrap_or(160.0),
+            height: size.height.unwrap_or(32.0),
+        };
+    }
+
+    fn enact_allocation_plan(&mut self, allocated_position: Position, allocated_size: ElementSize) {
+        self.set_position(Point::new(allocated_position.x, allocated_position.y));
+        self.set_size(allocated_size);
+    }
+
+    fn render_overlay(&self, canvas: &Canvas, viewport: &ElementSize) {
+        if !self.is_open {
+            return;
+        }
+        let popup_rect = self.popup_rect(viewport);
+        let theme = ThemeManager::current();
+        let surface_styles = Styles::popup_surface();
+        BoxPainter::paint_box_background(canvas, popup_rect, &surface_styles, ColorToken::SurfaceRaised.into());
+
+        canvas.save();
+        BoxPainter::clip_to_padding_box(canvas, popup_rect, &surface_styles.border.unwrap_or_default());
+
+        let mut paint = Paint::default();
+        paint.set_anti_alias(true);
+
+        let visible_options = self.options.iter().enumerate()
+            .skip(self.first_visible_index)
+            .take(self.visible_option_count());
+        for (row, (index, option)) in visible_options.enumerate() {
+            let option_rect = Rect::from_point_and_size(
+                Point::new(popup_rect.left, popup_rect.top + row as f32 * OPTION_HEIGHT),
+                (popup_rect.width(), OPTION_HEIGHT)
+            );
+            let text_color = if index == self.highlighted_index {
+                paint.set_color(theme.color(ColorToken::Accent));
+                canvas.draw_rect(option_rect, &paint);
+                theme.color(ColorToken::OnAccent)
+            } else {
+                theme.color(ColorToken::Text)
+            };
+            // The popup is its own surface, so only the typography carries over from the field
+            TextPainter::paint_line(canvas, option, option_rect.with_inset((TEXT_INSET, 0.0)), &self.computed_styles, text_color);
+        }
+
+        canvas.restore();
+        BoxPainter::paint_border(canvas, popup_rect, &surface_styles.border.unwrap_or_default());
+    }
+
+    fn handle_overlay_event(&mut self, cursor_position: Point, event_type: &EventType, viewport: &ElementSize) -> bool {
+        if !self.is_open {
+            return false;
+        }
+        let popup_rect = self.popup_rect(viewport);
+
+        match event_type {
+            EventType::MouseDown { button: MouseButton::Left, .. } => {
+                match self.option_index_at(cursor_position, &popup_rect) {
+                    Some(index) => self.choose(index),
+                    None => self.close(), // Outside click dismisses the list, including one on the field itself
+                }
+                true
+            }
+            EventType::MouseMove { .. } => {
+                if let Some(index) = self.option_index_at(cursor_position, &popup_rect) {
+                    self.highlighted_index = index;
+                }
+                popup_rect.contains(cursor_position)
+            }
+            EventType::MouseWheel { delta, .. } if popup_rect.contains(cursor_position) => {
+                self.scroll_by_wheel(delta.get_pixels().1);
+                true
+            }
+            EventType::KeyDown(KeyInput { logical_key: Key::Named(key), .. }) => self.handle_named_key(key),
+            EventType::TextInput(text) => {
+                if let Some(character) = text.chars().next() {
+                    self.handle_type_ahead(character);
+                }
+                true
+            }
+            EventType::KeyDown(_) | EventType::KeyUp(_) => true, // Keys typing text, and releases, stay with the open list
+            _ => false,
+        }
+    }
+
+    fn find_cursor(&self, cursor_position: Point) -> Option<Cursor> {
+        let cursor_position = self.computed_styles.map_to_local(self.rect(), cursor_position);
+        self.rect().contains(cursor_position).then(|| self.computed_styles.cursor.unwrap_or_default())
+    }
+
+    fn map_to_local(&self, cursor_position: Point) -> Point {
+        self.computed_styles.map_to_local(self.rect(), cursor_position)
+    }
+
+    fn is_focusable(&self) -> bool {
+        !self.interaction.state.is_disabled
+    }
+
+    fn get_tab_index(&self) -> Option<i32> {
+        self.tab_index
+    }
+}
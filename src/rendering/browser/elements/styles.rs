@@ -1,31 +1,327 @@
-use skia_safe::Color;
+use std::time::Duration;
 
-#[derive(Clone, Copy)]
+use skia_safe::{BlendMode, Color, Matrix, Point, Rect};
+
+use crate::rendering::browser::{animation::easing::Easing, styling::{theme::{ColorToken, RadiusToken, Theme}, theme_manager::ThemeManager}};
+
+/*
+ * Specified styles of an element. `None` means unset: inherited properties (text colour,
+ * typography, direction, cursor) then take the parent's computed value, the others take their initial value.
+ * See StyleResolver for how the computed styles are derived.
+ */
+#[derive(Clone, Default)]
 pub struct Styles {
     pub size: Option<Size>,
     pub margin: Option<Margin>,
     pub padding: Option<Padding>,
     pub alignment: Option<RowItemsAlignment>,
     pub spacing: Option<Spacing>,
-    pub color: Option<Color>,
-    pub border: Option<Border>,
+    pub color: Option<ColorValue>,       // Background; unset lets the element use its own default
+    pub border: Option<Border>,          // Unset lets the element use its own default
+    pub overflow: Option<Overflow>,
+    pub gradient: Option<Gradient>,      // Painted over `color` when set
+    pub shadows: Option<Vec<BoxShadow>>, // Painted in order, the first one on top
+    pub opacity: Option<f32>,
+    pub blend_mode: Option<BlendMode>,
+    pub text_decoration: Option<TextDecoration>,
+    pub transition: Option<Transition>,  // How changes from interaction states animate; unset changes them at once
+    pub transform: Option<Transform>,    // Applied when painting and hit-testing only; layout ignores it
+    pub length_tokens: Option<LengthTokens>, // Resolved over the lengths above by StyleResolver
+
+    // Inherited
+    pub text_color: Option<ColorValue>,
+    pub font_families: Option<Vec<String>>, // In order of preference; glyphs missing from all of them fall back to any installed font
+    pub font_size: Option<f32>,
+    pub font_weight: Option<u16>,           // 100 (thin) to 900 (black); 400 is regular, 700 bold
+    pub font_slant: Option<FontSlant>,
+    pub line_height: Option<f32>,           // Multiple of the font size; unset uses the font's own metrics
+    pub letter_spacing: Option<f32>,
+    pub text_align: Option<TextAlign>,
+    pub direction: Option<Direction>,
+    pub cursor: Option<Cursor>,             // Mouse cursor shown while the pointer is over the element
 }
 
-impl Default for Styles {
-    fn default() -> Self {
+impl Styles {
+    // Values of properties nobody set, and of the root's inherited properties
+    pub fn initial() -> Self {
         Self {
             size: Some(Size::default()),
             margin: Some(Margin::default()),
             padding: Some(Padding::default()),
             alignment: Some(RowItemsAlignment::default()),
             spacing: Some(Spacing::default()),
-            color: Some(Color::TRANSPARENT),
-            border: Some(Border::default()),
+            color: None,
+            border: None,
+            overflow: Some(Overflow::default()),
+            gradient: None,
+            shadows: None,
+            opacity: Some(1.0),
+            blend_mode: Some(BlendMode::SrcOver),
+            text_decoration: Some(TextDecoration::default()),
+            transition: None,
+            transform: None,
+            length_tokens: None,
+            text_color: Some(ColorValue::Token(ColorToken::Text)),
+            font_families: None,
+            font_size: Some(13.0),
+            font_weight: Some(400),
+            font_slant: Some(FontSlant::default()),
+            line_height: None,
+            letter_spacing: Some(0.0),
+            text_align: Some(TextAlign::default()),
+            direction: Some(Direction::default()),
+            cursor: Some(Cursor::default()),
+        }
+    }
+
+    // Properties set here win; unset ones fall back to `base`
+    pub fn merged_over(&self, base: &Styles) -> Styles {
+        Styles {
+            size: self.size.or(base.size),
+            margin: self.margin.or(base.margin),
+            padding: self.padding.or(base.padding),
+            alignment: self.alignment.or(base.alignment),
+            spacing: self.spacing.or(base.spacing),
+            color: self.color.or(base.color),
+            border: self.border.or(base.border),
+            overflow: self.overflow.or(base.overflow),
+            gradient: self.gradient.clone().or_else(|| base.gradient.clone()),
+            shadows: self.shadows.clone().or_else(|| base.shadows.clone()),
+            opacity: self.opacity.or(base.opacity),
+            blend_mode: self.blend_mode.or(base.blend_mode),
+            text_decoration: self.text_decoration.or(base.text_decoration),
+            transition: self.transition.or(base.transition),
+            transform: self.transform.or(base.transform),
+            length_tokens: self.merged_length_tokens(base),
+            text_color: self.text_color.or(base.text_color),
+            font_families: self.font_families.clone().or_else(|| base.font_families.clone()),
+            font_size: self.font_size.or(base.font_size),
+            font_weight: self.font_weight.or(base.font_weight),
+            font_slant: self.font_slant.or(base.font_slant),
+            line_height: self.line_height.or(base.line_height),
+            letter_spacing: self.letter_spacing.or(base.letter_spacing),
+            text_align: self.text_align.or(base.text_align),
+            direction: self.direction.or(base.direction),
+            cursor: self.cursor.or(base.cursor),
+        }
+    }
+
+    // A property's tokens go with its pixel lengths: those of `self` if it sets the property either way, else those of `base`
+    fn merged_length_tokens(&self, base: &Styles) -> Option<LengthTokens> {
+        fn pick<const N: usize>(is_set: bool, own: [Option<LengthToken>; N], base: [Option<LengthToken>; N]) -> [Option<LengthToken>; N] {
+            if is_set || own.iter().any(Option::is_some) { own } else { base }
+        }
+        let (own, base_tokens) = (self.length_tokens.unwrap_or_default(), base.length_tokens.unwrap_or_default());
+        let merged = LengthTokens {
+            radius: pick(self.border.is_some(), own.radius, base_tokens.radius),
+            spacing: pick(self.spacing.is_some(), own.spacing, base_tokens.spacing),
+            padding: pick(self.padding.is_some(), own.padding, base_tokens.padding),
+            margin: pick(self.margin.is_some(), own.margin, base_tokens.margin),
+        };
+        (merged != LengthTokens::default()).then_some(merged)
+    }
+
+    // Replaces the lengths given as theme tokens with their values in the theme
+    pub fn resolve_length_tokens(&mut self, theme: &Theme) {
+        let Some(tokens) = self.length_tokens.take() else { return; };
+        let resolve = |length: &mut f32, token: Option<LengthToken>| {
+            if let Some(token) = token {
+                *length = token.resolve(theme);
+            }
+        };
+
+        if tokens.radius.iter().any(Option::is_some) {
+            let mut border = self.border.unwrap_or_default();
+            let radius = &mut border.radius;
+            for (length, token) in [&mut radius.top_left, &mut radius.top_right, &mut radius.bottom_right, &mut radius.bottom_left].into_iter().zip(tokens.radius) {
+                resolve(length, token);
+            }
+            self.border = Some(border);
+        }
+        if tokens.spacing.iter().any(Option::is_some) {
+            let mut spacing = self.spacing.unwrap_or_default();
+            for (length, token) in [&mut spacing.spacing_x, &mut spacing.spacing_y].into_iter().zip(tokens.spacing) {
+                resolve(length, token);
+            }
+            self.spacing = Some(spacing);
+        }
+        if tokens.padding.iter().any(Option::is_some) {
+            let mut padding = self.padding.unwrap_or_default();
+            for (length, token) in [&mut padding.top, &mut padding.right, &mut padding.bottom, &mut padding.left].into_iter().zip(tokens.padding) {
+                resolve(length, token);
+            }
+            self.padding = Some(padding);
+        }
+        if tokens.margin.iter().any(Option::is_some) {
+            let mut margin = self.margin.unwrap_or_default();
+            for (length, token) in [&mut margin.top, &mut margin.right, &mut margin.bottom, &mut margin.left].into_iter().zip(tokens.margin) {
+                resolve(length, token);
+            }
+            self.margin = Some(margin);
+        }
+    }
+
+    // Whether switching between the two styles can leave the layout as it is
+    pub fn has_same_layout(&self, other: &Styles) -> bool {
+        let border_widths = |styles: &Styles| styles.border.map(|border| [
+            border.get_top_side().width,
+            border.get_right_side().width,
+            border.get_bottom_side().width,
+            border.get_left_side().width,
+        ]);
+
+        self.size == other.size
+            && self.margin == other.margin
+            && self.padding == other.padding
+            && self.alignment == other.alignment
+            && self.spacing == other.spacing
+            && border_widths(self) == border_widths(other)
+            && self.font_families == other.font_families
+            && self.font_size == other.font_size
+            && self.font_weight == other.font_weight
+            && self.font_slant == other.font_slant
+            && self.line_height == other.line_height
+            && self.letter_spacing == other.letter_spacing
+            && self.direction == other.direction
+    }
+
+    // Whether children resolve the same under either as their parent's: they only take its inherited properties
+    pub fn has_same_inherited(&self, other: &Styles) -> bool {
+        self.text_color == other.text_color
+            && self.font_families == other.font_families
+            && self.font_size == other.font_size
+            && self.font_weight == other.font_weight
+            && self.font_slant == other.font_slant
+            && self.line_height == other.line_height
+            && self.letter_spacing == other.letter_spacing
+            && self.text_align == other.text_align
+            && self.direction == other.direction
+            && self.cursor == other.cursor
+    }
+
+    // The cursor position in the element's untransformed coordinates, where its layout rect and children are
+    pub fn map_to_local(&self, rect: Rect, point: Point) -> Point {
+        match self.transform {
+            Some(transform) => transform.map_to_local(rect, point),
+            None => point,
+        }
+    }
+
+    // Where a rect inside the element, e.g. a child's, ends up on screen once the element is transformed
+    pub fn map_rect_from_local(&self, rect: Rect, local_rect: Rect) -> Rect {
+        match self.transform {
+            Some(transform) => transform.get_matrix(rect).map_rect(local_rect).0,
+            None => local_rect,
+        }
+    }
+
+    // Shared look of menus, select popups and other surfaces floating above the page
+    pub fn popup_surface() -> Self {
+        Self {
+            color: Some(ColorValue::TokenWithAlpha(ColorToken::SurfaceRaised, 245)),
+            border: Some(Border {
+                width: 1.0,
+                color: ColorValue::Token(ColorToken::Border),
+                radius: BorderRadius::uniform(6.0),
+                ..Default::default()
+            }),
+            shadows: Some(vec![
+                BoxShadow { offset_y: 1.0, blur_radius: 3.0, ..Default::default() },
+                BoxShadow { offset_y: 6.0, blur_radius: 16.0, ..Default::default() },
+            ]),
+            ..Default::default()
         }
     }
 }
 
-#[derive(Clone, Copy)]
+/*
+ * A colour in styles: a literal, or a theme token looked up in the active theme whenever it is
+ * painted, so token colours follow theme switches without rebuilding the styles
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ColorValue {
+    Literal(Color),
+    Token(ColorToken),
+    TokenWithAlpha(ColorToken, u8), // The token's colour with its alpha replaced
+}
+
+impl ColorValue {
+    pub fn resolve(&self) -> Color {
+        match self {
+            ColorValue::Literal(color) => *color,
+            ColorValue::Token(token) => ThemeManager::current().color(*token),
+            ColorValue::TokenWithAlpha(token, alpha) => ThemeManager::current().color(*token).with_a(*alpha),
+        }
+    }
+}
+
+impl From<Color> for ColorValue {
+    fn from(color: Color) -> Self {
+        ColorValue::Literal(color)
+    }
+}
+
+impl From<ColorToken> for ColorValue {
+    fn from(token: ColorToken) -> Self {
+        ColorValue::Token(token)
+    }
+}
+
+// A length as written in a stylesheet: pixels, or a token of the theme
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Length {
+    Px(f32),
+    Token(LengthToken),
+}
+
+impl Length {
+    // The pixels to specify, and the token StyleResolver resolves over them if any
+    pub fn split(self) -> (f32, Option<LengthToken>) {
+        match self {
+            Length::Px(length) => (length, None),
+            Length::Token(token) => (0.0, Some(token)),
+        }
+    }
+}
+
+// A length named by the theme's radius or spacing scale, so that it follows theme switches
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LengthToken {
+    Radius(RadiusToken),
+    Space(usize), // Step of the spacing scale
+}
+
+impl LengthToken {
+    pub fn resolve(&self, theme: &Theme) -> f32 {
+        match *self {
+            LengthToken::Radius(token) => theme.radius(token),
+            LengthToken::Space(step) => theme.spacing(step),
+        }
+    }
+}
+
+impl From<RadiusToken> for LengthToken {
+    fn from(token: RadiusToken) -> Self {
+        LengthToken::Radius(token)
+    }
+}
+
+// Lengths given as tokens, per corner or side in CSS order, and x then y for spacing
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct LengthTokens {
+    pub radius: [Option<LengthToken>; 4],
+    pub spacing: [Option<LengthToken>; 2],
+    pub padding: [Option<LengthToken>; 4],
+    pub margin: [Option<LengthToken>; 4],
+}
+
+impl LengthTokens {
+    pub fn uniform_radius(token: impl Into<LengthToken>) -> Self {
+        Self { radius: [Some(token.into()); 4], ..Default::default() }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
 pub struct Size {
     pub width: Option<f32>,
     pub height: Option<f32>,
@@ -42,7 +338,7 @@ impl Default for Size {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq)]
 pub enum SizeMode {
     FitContent,    // Fit content to the available space.
     Exact(Directions),  // Use the specified width and height exactly.
@@ -58,7 +354,7 @@ impl Default for SizeMode {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq)]
 pub struct Spacing {
     pub spacing_x: f32,
     pub spacing_y: f32,
@@ -73,7 +369,7 @@ impl Default for Spacing {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq)]
 pub struct Margin {
     pub top: f32,
     pub right: f32,
@@ -92,7 +388,7 @@ impl Default for Margin {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq)]
 pub struct Padding {
     pub top: f32,
     pub right: f32,
@@ -111,7 +407,7 @@ impl Default for Padding {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq)]
 pub enum RowItemsAlignment {
     Start,
     Center,
@@ -127,20 +423,96 @@ impl Default for RowItemsAlignment {
 #[derive(Clone, Copy)]
 pub struct Border {
     pub width: f32,
-    pub color: Color,
+    pub color: ColorValue,
+    pub style: BorderStyle,
     pub radius: BorderRadius,
+    // Per-side overrides of width, color and style
+    pub top: Option<BorderSide>,
+    pub right: Option<BorderSide>,
+    pub bottom: Option<BorderSide>,
+    pub left: Option<BorderSide>,
 }
 
 impl Default for Border {
     fn default() -> Self {
         Self {
             width: 0.0,
-            color: Color::TRANSPARENT,
+            color: ColorValue::Literal(Color::TRANSPARENT),
+            style: BorderStyle::default(),
             radius: BorderRadius::default(),
+            top: None,
+            right: None,
+            bottom: None,
+            left: None,
+        }
+    }
+}
+
+impl Border {
+    fn get_uniform_side(&self) -> BorderSide {
+        BorderSide { width: self.width, color: self.color, style: self.style }
+    }
+
+    pub fn get_top_side(&self) -> BorderSide {
+        self.top.unwrap_or(self.get_uniform_side())
+    }
+
+    pub fn get_right_side(&self) -> BorderSide {
+        self.right.unwrap_or(self.get_uniform_side())
+    }
+
+    pub fn get_bottom_side(&self) -> BorderSide {
+        self.bottom.unwrap_or(self.get_uniform_side())
+    }
+
+    pub fn get_left_side(&self) -> BorderSide {
+        self.left.unwrap_or(self.get_uniform_side())
+    }
+
+    pub fn get_horizontal_width(&self) -> f32 {
+        self.get_left_side().width + self.get_right_side().width
+    }
+
+    pub fn get_vertical_width(&self) -> f32 {
+        self.get_top_side().width + self.get_bottom_side().width
+    }
+
+    pub fn is_uniform(&self) -> bool {
+        let top = self.get_top_side();
+        [self.get_right_side(), self.get_bottom_side(), self.get_left_side()].iter().all(|side| *side == top)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct BorderSide {
+    pub width: f32,
+    pub color: ColorValue,
+    pub style: BorderStyle,
+}
+
+impl Default for BorderSide {
+    fn default() -> Self {
+        Self {
+            width: 0.0,
+            color: ColorValue::Literal(Color::TRANSPARENT),
+            style: BorderStyle::default(),
         }
     }
 }
 
+#[derive(Clone, Copy, PartialEq)]
+pub enum BorderStyle {
+    Solid,
+    Dashed,
+    Dotted,
+}
+
+impl Default for BorderStyle {
+    fn default() -> Self {
+        Self::Solid
+    }
+}
+
 #[derive(Clone, Copy)]
 pub struct BorderRadius {
     pub top_left: f32,
@@ -160,7 +532,79 @@ impl Default for BorderRadius {
     }
 }
 
+impl BorderRadius {
+    pub fn uniform(radius: f32) -> Self {
+        Self {
+            top_left: radius,
+            top_right: radius,
+            bottom_right: radius,
+            bottom_left: radius,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum Overflow {
+    Visible, // Children may draw outside the element's bounds.
+    Clip,    // Children are clipped to the element's (rounded) bounds.
+}
+
+impl Default for Overflow {
+    fn default() -> Self {
+        Self::Visible
+    }
+}
+
 #[derive(Clone, Copy)]
+pub struct ColorStop {
+    pub offset: f32, // 0.0 at the start of the gradient, 1.0 at its end
+    pub color: ColorValue,
+}
+
+#[derive(Clone)]
+pub enum Gradient {
+    // Angle in degrees, clockwise from a top-to-bottom gradient
+    Linear { angle: f32, stops: Vec<ColorStop> },
+    // Centre relative to the element's size, radius relative to its larger side
+    Radial { center_x: f32, center_y: f32, radius: f32, stops: Vec<ColorStop> },
+}
+
+#[derive(Clone, Copy)]
+pub struct BoxShadow {
+    pub offset_x: f32,
+    pub offset_y: f32,
+    pub blur_radius: f32,
+    pub spread: f32,
+    pub color: ColorValue,
+    pub inset: bool, // Drawn inside the padding box instead of around the element
+}
+
+impl Default for BoxShadow {
+    fn default() -> Self {
+        Self {
+            offset_x: 0.0,
+            offset_y: 0.0,
+            blur_radius: 0.0,
+            spread: 0.0,
+            color: ColorValue::Token(ColorToken::Shadow),
+            inset: false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Direction {
+    LeftToRight,
+    RightToLeft, // Rows lay their children out from the right edge
+}
+
+impl Default for Direction {
+    fn default() -> Self {
+        Self::LeftToRight
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
 pub struct Directions {
     pub horizontal: bool,
     pub vertical: bool,
@@ -173,4 +617,136 @@ impl Default for Directions {
             vertical: true,
         }
     }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum FontSlant {
+    Normal,
+    Italic,
+    Oblique,
+}
+
+impl Default for FontSlant {
+    fn default() -> Self {
+        Self::Normal
+    }
+}
+
+// Start and End follow the direction, like Row alignment
+#[derive(Clone, Copy, PartialEq)]
+pub enum TextAlign {
+    Start,
+    Center,
+    End,
+}
+
+impl Default for TextAlign {
+    fn default() -> Self {
+        Self::Start
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum TextDecoration {
+    None,
+    Underline,
+    Overline,
+    LineThrough,
+}
+
+impl Default for TextDecoration {
+    fn default() -> Self {
+        Self::None
+    }
+}
+
+// Mouse cursor icons, named as in CSS
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Cursor {
+    Default,
+    Pointer,     // Clickable, e.g. buttons
+    Text,        // Selectable or editable text
+    Wait,        // Busy; nothing can be done until it finishes
+    Progress,    // Busy in the background, but still interactive
+    NotAllowed,
+    Crosshair,
+    Move,
+    Grab,
+    Grabbing,
+    Copy,        // Dragged data would be dropped here
+    NoDrop,      // Dragged data can't be dropped here
+    ColResize,   // Splitters between columns
+    RowResize,   // Splitters between rows
+}
+
+impl Default for Cursor {
+    fn default() -> Self {
+        Self::Default
+    }
+}
+
+// Numeric and colour properties are interpolated; the others switch to their new value at once
+#[derive(Clone, Copy, PartialEq)]
+pub struct Transition {
+    pub duration: Duration,
+    pub easing: Easing,
+}
+
+impl Default for Transition {
+    fn default() -> Self {
+        Self {
+            duration: Duration::from_millis(150),
+            easing: Easing::default(),
+        }
+    }
+}
+
+/*
+ * 2D transform of an element and its subtree, like CSS `transform`. Around the origin, the
+ * element is scaled, then rotated, then translated. The origin is relative to the element's
+ * rect: (0.5, 0.5) is its centre.
+ */
+#[derive(Clone, Copy, PartialEq)]
+pub struct Transform {
+    pub translate_x: f32,
+    pub translate_y: f32,
+    pub scale_x: f32,
+    pub scale_y: f32,
+    pub rotate: f32, // Degrees, clockwise
+    pub origin_x: f32,
+    pub origin_y: f32,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            translate_x: 0.0,
+            translate_y: 0.0,
+            scale_x: 1.0,
+            scale_y: 1.0,
+            rotate: 0.0,
+            origin_x: 0.5,
+            origin_y: 0.5,
+        }
+    }
+}
+
+impl Transform {
+    pub fn get_matrix(&self, rect: Rect) -> Matrix {
+        let origin = Point::new(rect.left + rect.width() * self.origin_x, rect.top + rect.height() * self.origin_y);
+
+        let mut matrix = Matrix::translate((origin.x + self.translate_x, origin.y + self.translate_y));
+        matrix.pre_rotate(self.rotate, None);
+        matrix.pre_scale((self.scale_x, self.scale_y), None);
+        matrix.pre_translate((-origin.x, -origin.y));
+        matrix
+    }
+
+    // A transform scaled down to nothing can't be inverted. Nothing is left to hit then, so the point lands nowhere
+    pub fn map_to_local(&self, rect: Rect, point: Point) -> Point {
+        match self.get_matrix(rect).invert() {
+            Some(inverse) => inverse.map_point(point),
+            None => Point::new(f32::NAN, f32::NAN),
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,208 @@
+use skia_safe::{Canvas, Color, Image, Paint, PaintStyle, Point, Rect};
+
+use crate::rendering::browser::internal::element_id_generator::IDGenerator;
+use crate::rendering::browser::layout::types::Position;
+use crate::rendering::browser::painting::{box_painter::BoxPainter, text_painter::TextPainter};
+use crate::rendering::browser::styling::{style_resolver::{StyleContext, StyleResolver}, stylesheet::SelectorSubject};
+use crate::rendering::browser::styling::{theme::ColorToken, theme_manager::ThemeManager};
+
+use super::{common::ElementType, element::{Element, ElementSize, Event}, styles::{Border, BorderRadius, BoxShadow, ColorValue, Directions, Styles}};
+
+pub const TAB_MAX_WIDTH: f32 = 220.0;
+pub const TAB_MIN_WIDTH: f32 = 72.0;
+const FAVICON_SIZE: f32 = 16.0;
+const CLOSE_BUTTON_SIZE: f32 = 16.0;
+const CONTENT_INSET: f32 = 8.0;
+const CORNER_RADIUS: f32 = 6.0;
+
+#[derive(Clone)]
+pub struct TabInfo {
+    pub title: String,
+    pub favicon: Option<Image>,
+}
+
+impl TabInfo {
+    pub fn new(title: &str) -> Self {
+        Self { title: title.to_string(), favicon: None }
+    }
+}
+
+/*
+ * A single tab of the TabStrip; the strip owns the tab model and rebuilds these when it changes
+ */
+pub struct Tab {
+    _id: String,
+    position: Point,
+    size: ElementSize,
+    styles: Styles,
+    computed_styles: Styles,
+    info: TabInfo,
+    is_active: bool,
+}
+
+impl Tab {
+    pub fn new(info: TabInfo, is_active: bool, height: f32) -> Self {
+        Self {
+            _id: IDGenerator::get(),
+            position: Point::new(0.0, 0.0),
+            size: ElementSize { width: TAB_MAX_WIDTH, height },
+            styles: Styles::default(),
+            computed_styles: Styles::initial(),
+            info,
+            is_active,
+        }
+    }
+
+    pub fn rect(&self) -> Rect {
+        Rect::from_point_and_size(self.position, (self.size.width, self.size.height))
+    }
+
+    pub fn close_button_rect(position: Point, size: &ElementSize) -> Rect {
+        Rect::from_point_and_size(
+            Point::new(
+                position.x + size.width - CONTENT_INSET - CLOSE_BUTTON_SIZE,
+                position.y + (size.height - CLOSE_BUTTON_SIZE) / 2.0
+            ),
+            (CLOSE_BUTTON_SIZE, CLOSE_BUTTON_SIZE)
+        )
+    }
+
+    fn favicon_rect(&self) -> Rect {
+        Rect::from_point_and_size(
+            Point::new(self.position.x + CONTENT_INSET, self.position.y + (self.size.height - FAVICON_SIZE) / 2.0),
+            (FAVICON_SIZE, FAVICON_SIZE)
+        )
+    }
+
+    fn render_favicon(&self, canvas: &Canvas) {
+        let favicon_rect = self.favicon_rect();
+        let mut paint = Paint::default();
+        paint.set_anti_alias(true);
+
+        match &self.info.favicon {
+            Some(favicon) => {
+                canvas.draw_image_rect(favicon, None, favicon_rect, &paint);
+            }
+            None => { // Empty slot until the page provides an icon
+                paint.set_color(ThemeManager::current().color(ColorToken::TextMuted));
+                canvas.draw_circle(favicon_rect.center(), FAVICON_SIZE / 2.0 - 1.0, &paint);
+            }
+        }
+    }
+
+    fn render_title(&self, canvas: &Canvas) {
+        let favicon_rect = self.favicon_rect();
+        let close_rect = Tab::close_button_rect(self.position, &self.size);
+        let title_rect = Rect::new(favicon_rect.right + CONTENT_INSET, self.position.y, close_rect.left - CONTENT_INSET / 2.0, self.position.y + self.size.height);
+        if title_rect.width() <= 0.0 {
+            return;
+        }
+
+        let text_color = self.computed_styles.text_color.unwrap_or(ColorToken::Text.into()).resolve();
+        TextPainter::paint_line(canvas, &self.info.title, title_rect, &self.computed_styles, text_color);
+    }
+
+    fn render_close_button(&self, canvas: &Canvas) {
+        let close_rect = Tab::close_button_rect(self.position, &self.size).with_inset((4.0, 4.0));
+        let mut paint = Paint::default();
+        paint.set_anti_alias(true);
+        paint.set_style(PaintStyle::Stroke);
+        paint.set_stroke_width(1.5);
+        paint.set_color(ThemeManager::current().color(ColorToken::TextMuted));
+
+        canvas.draw_line((close_rect.left, close_rect.top), (close_rect.right, close_rect.bottom), &paint);
+        canvas.draw_line((close_rect.right, close_rect.top), (close_rect.left, close_rect.bottom), &paint);
+    }
+
+    // The active tab is lifted off the strip by a shadow; inactive ones let the strip show through
+    fn get_surface_styles(&self) -> Styles {
+        let border = Border {
+            radius: BorderRadius { top_left: CORNER_RADIUS, top_right: CORNER_RADIUS, ..Default::default() },
+            ..Default::default()
+        };
+
+        if self.is_active {
+            Styles {
+                color: Some(ColorToken::SurfaceRaised.into()),
+                border: Some(border),
+                shadows: Some(vec![BoxShadow { blur_radius: 6.0, ..Default::default() }]),
+                ..Default::default()
+            }
+        } else {
+            Styles {
+                color: Some(ColorValue::TokenWithAlpha(ColorToken::SurfaceRaised, 110)),
+                border: Some(border),
+                ..Default::default()
+            }
+        }
+    }
+}
+
+impl Element for Tab {
+    fn render(&self, canvas: &Canvas) {
+        BoxPainter::paint_box(canvas, self.rect(), &self.get_surface_styles(), Color::TRANSPARENT.into());
+
+        self.render_favicon(canvas);
+        self.render_title(canvas);
+        self.render_close_button(canvas);
+    }
+
+    fn update(&mut self) {}
+
+    fn handle_event(&mut self, _cursor_position: Point, _event: &mut Event) {}
+
+    fn set_position(&mut self, position: Point) {
+        self.position = position;
+    }
+
+    fn set_size(&mut self, size: ElementSize) {
+        self.size = size;
+    }
+
+    fn layout(&mut self, _available_space: Option<ElementSize>) {}
+
+    fn get_id(&self) -> String {
+        self._id.clone()
+    }
+
+    fn get_element_type(&self) -> ElementType {
+        ElementType::Tab
+    }
+
+    fn get_children_mut(&mut self) -> Option<&mut Vec<Box<dyn Element>>> {
+        None
+    }
+
+    fn get_position(&self) -> Point {
+        self.position
+    }
+
+    fn get_size(&self) -> ElementSize {
+        self.size.clone()
+    }
+
+    fn get_styles(&self) -> Styles {
+        self.styles.clone()
+    }
+
+    fn resolve_styles(&mut self, context: &StyleContext) {
+        self.computed_styles = StyleResolver::resolve_element(&SelectorSubject::of(self), &self.styles, context);
+    }
+
+    fn get_computed_styles(&self) -> Styles {
+        self.computed_styles.clone()
+    }
+
+    fn is_variable_size(&self) -> Directions {
+        Directions { horizontal: true, vertical: false }
+    }
+
+    fn compute_allocation_plan(&mut self) {
+        self.size.width = TAB_MAX_WIDTH;
+    }
+
+    fn enact_allocation_plan(&mut self, allocated_position: Position, allocated_size: ElementSize) {
+        self.set_position(Point::new(allocated_position.x, allocated_position.y));
+        self.set_size(allocated_size);
+    }
+}
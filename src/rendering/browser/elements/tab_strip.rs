@@ -0,0 +1,605 @@
+use std::time::Instant;
+
+use skia_safe::{Canvas, Contains, Paint, PaintStyle, Point, Rect};
+use winit::{event::MouseButton, keyboard::{Key, NamedKey}};
+
+use crate::rendering::browser::internal::element_id_generator::IDGenerator;
+use crate::rendering::browser::painting::box_painter::BoxPainter;
+use crate::rendering::browser::shortcuts::command::Command;
+use crate::rendering::browser::state::app_state::{AppState, NEW_TAB_TITLE};
+use crate::rendering::browser::layout::{row_layout_manager::RowLayoutManager, space_deficit_resolver::SpaceDeficitResolver, types::Position};
+use crate::rendering::browser::styling::{interaction_styles::{InteractionStyles, StateStyles}, style_resolver::{StyleContext, StyleResolver}, stylesheet::SelectorSubject};
+use crate::rendering::browser::styling::{theme::ColorToken, theme_manager::ThemeManager};
+
+use super::{common::ElementType, element::{Element, ElementSize, Event, EventType, Gesture, KeyInput}, menu::Menu, row::Row, styles::{Cursor, Directions, Spacing, Styles}, tab::{Tab, TabInfo, TAB_MIN_WIDTH}, tooltip::Tooltip};
+
+const TAB_SPACING: f32 = 2.0;
+const NEW_TAB_BUTTON_WIDTH: f32 = 32.0;
+const DRAGGED_TAB_OPACITY: f32 = 0.85;
+
+pub enum TabStripEvent {
+    Select(usize),
+    Close(usize),
+    Reorder { from: usize, to: usize },
+    New(usize),
+}
+
+struct TabDrag {
+    index: usize,
+    press_x: f32,
+    current_x: f32,
+    is_dragging: bool,
+}
+
+pub struct TabStrip {
+    _id: String,
+    position: Point,
+    size: ElementSize,
+    styles: Styles,
+    computed_styles: Styles,
+    style_id: Option<String>,
+    class_names: Vec<String>,
+    interaction: InteractionStyles,
+    children_style_context: StyleContext, // Kept for children created after the last restyle
+    tabs: Vec<TabInfo>,
+    active_index: Option<usize>,
+    tabs_row: Row,
+    scroll_offset: f32,
+    drag: Option<TabDrag>,
+    pub on_event: Box<dyn FnMut(TabStripEvent)>,
+    tab_context_menu_builder: Option<Box<dyn Fn(usize) -> Menu>>,
+    state_binding: Option<Box<dyn FnMut(&mut TabStrip, &AppState)>>,
+}
+
+impl TabStrip {
+    pub fn new(tabs: Vec<TabInfo>, on_event: Box<dyn FnMut(TabStripEvent)>) -> Self {
+        let active_index = if tabs.is_empty() { None } else { Some(0) };
+
+        Self {
+            _id: IDGenerator::get(),
+            position: Point::new(0.0, 0.0),
+            size: ElementSize::default(),
+            styles: Styles::default(),
+            computed_styles: Styles::initial(),
+            style_id: None,
+            class_names: vec![],
+            interaction: InteractionStyles::new(),
+            children_style_context: StyleContext::default(),
+            tabs,
+            active_index,
+            tabs_row: Row::new().set_styles(Styles {
+                spacing: Some(Spacing { spacing_x: TAB_SPACING, spacing_y: 0.0 }),
+                ..Default::default()
+            }),
+            scroll_offset: 0.0,
+            drag: None,
+            on_event,
+            tab_context_menu_builder: None,
+            state_binding: None,
+        }
+    }
+
+    pub fn set_styles(mut self, styles: Styles) -> Self {
+        self.styles = styles;
+        self
+    }
+
+    // Id and class names that stylesheet selectors match against
+    #[allow(dead_code)]
+    pub fn set_style_id(mut self, style_id: &str) -> Self {
+        self.style_id = Some(style_id.to_string());
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn add_class(mut self, class_name: &str) -> Self {
+        self.class_names.push(class_name.to_string());
+        self
+    }
+
+    // Overrides merged over the styles while the element is hovered, pressed, focused or disabled
+    #[allow(dead_code)]
+    pub fn set_state_styles(mut self, state_styles: StateStyles) -> Self {
+        self.interaction.state_styles = state_styles;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn set_disabled(mut self, is_disabled: bool) -> Self {
+        self.interaction.set_disabled(is_disabled);
+        self
+    }
+
+    fn restyle_for_interaction(&mut self) {
+        let previous_styles = self.computed_styles.clone();
+        self.resolve_styles(&self.interaction.get_context());
+        self.interaction.on_restyled(&previous_styles, &mut self.computed_styles);
+    }
+
+    // Builds the context menu of the tab at the given index
+    #[allow(dead_code)]
+    pub fn set_tab_context_menu(mut self, tab_context_menu_builder: Box<dyn Fn(usize) -> Menu>) -> Self {
+        self.tab_context_menu_builder = Some(tab_context_menu_builder);
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn set_active_index(mut self, index: usize) -> Self {
+        if index < self.tabs.len() {
+            self.active_index = Some(index);
+        }
+        self
+    }
+
+    // Updates the strip from the application state whenever it changes, e.g. to follow its tabs
+    #[allow(dead_code)]
+    pub fn set_state_binding(mut self, state_binding: Box<dyn FnMut(&mut TabStrip, &AppState)>) -> Self {
+        self.state_binding = Some(state_binding);
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn get_tabs(&self) -> &Vec<TabInfo> {
+        &self.tabs
+    }
+
+    // Tab operations
+    pub fn open_tab(&mut self, info: TabInfo) {
+        self.tabs.push(info);
+        let index = self.tabs.len() - 1;
+        self.active_index = Some(index);
+        self.layout_tabs();
+        self.scroll_to_tab(index);
+        (self.on_event)(TabStripEvent::New(index));
+    }
+
+    pub fn close_tab(&mut self, index: usize) {
+        if index >= self.tabs.len() {
+            return;
+        }
+        self.tabs.remove(index);
+        self.active_index = match self.active_index {
+            _ if self.tabs.is_empty() => None,
+            Some(active) if active > index => Some(active - 1),
+            Some(active) => Some(active.min(self.tabs.len() - 1)),
+            None => None,
+        };
+        self.layout_tabs();
+        (self.on_event)(TabStripEvent::Close(index));
+    }
+
+    pub fn select_tab(&mut self, index: usize) {
+        if index >= self.tabs.len() || self.active_index == Some(index) {
+            return;
+        }
+        self.active_index = Some(index);
+        self.layout_tabs();
+        self.scroll_to_tab(index);
+        (self.on_event)(TabStripEvent::Select(index));
+    }
+
+    // Replaces the tabs without reporting a TabStripEvent; does nothing if the titles and active tab are the same
+    pub fn sync_tabs(&mut self, tabs: Vec<TabInfo>, active_index: Option<usize>) {
+        let active_index = active_index.filter(|index| *index < tabs.len());
+        let is_same_tabs = tabs.len() == self.tabs.len() && tabs.iter().zip(&self.tabs).all(|(tab, current)| tab.title == current.title);
+        if is_same_tabs && active_index == self.active_index {
+            return;
+        }
+
+        self.tabs = tabs;
+        self.active_index = active_index;
+        self.drag = None;
+        self.layout_tabs();
+        if let Some(index) = active_index {
+            self.scroll_to_tab(index);
+        }
+    }
+
+    fn move_tab(&mut self, from: usize, to: usize) {
+        let tab = self.tabs.remove(from);
+        self.tabs.insert(to, tab);
+        if self.active_index == Some(from) {
+            self.active_index = Some(to);
+        }
+        self.layout_tabs();
+        (self.on_event)(TabStripEvent::Reorder { from, to });
+    }
+
+    // Layout
+    fn rect(&self) -> Rect {
+        Rect::from_point_and_size(self.position, (self.size.width, self.size.height))
+    }
+
+    fn tabs_area_rect(&self) -> Rect {
+        Rect::from_point_and_size(self.position, ((self.size.width - NEW_TAB_BUTTON_WIDTH).max(0.0), self.size.height))
+    }
+
+    fn new_tab_button_rect(&self) -> Rect {
+        let tabs_width = self.tabs_row.requested_size.width.min(self.tabs_area_rect().width());
+        Rect::from_point_and_size(
+            Point::new(self.position.x + tabs_width, self.position.y),
+            (NEW_TAB_BUTTON_WIDTH, self.size.height)
+        )
+    }
+
+    fn max_scroll_offset(&self) -> f32 {
+        (self.tabs_row.requested_size.width - self.tabs_area_rect().width()).max(0.0)
+    }
+
+    /*
+     * Tabs request their maximum width; when the row does not fit, the deficit resolver
+     * shrinks them evenly down to the minimum tab width, past which the strip scrolls
+     */
+    fn layout_tabs(&mut self) {
+        let tab_height = self.size.height;
+        let tabs: Vec<Box<dyn Element>> = self.tabs.iter().enumerate()
+            .map(|(index, info)| Box::new(Tab::new(info.clone(), self.active_index == Some(index), tab_height)) as Box<dyn Element>)
+            .collect();
+
+        // The row is kept, so only its tabs are rebuilt
+        self.tabs_row.position = self.position;
+        self.tabs_row.resolve_styles(&self.children_style_context);
+        self.tabs_row.replace_children(tabs);
+        self.tabs_row.compute_allocation_plan();
+
+        let available_width = self.tabs_area_rect().width();
+        if self.tabs_row.requested_size.width > available_width {
+            let mut deficit = ElementSize { width: self.tabs_row.requested_size.width - available_width, height: 0.0 };
+            let report = SpaceDeficitResolver::resolve_space_deficit(&mut self.tabs_row, &mut deficit);
+
+            for tab in self.tabs_row.children.iter_mut() {
+                if let Some(adjustment) = report.get_adjustment(&tab.get_id()) {
+                    let size = tab.get_size();
+                    tab.set_size(ElementSize { width: (size.width - adjustment.width_reduction).max(TAB_MIN_WIDTH), height: size.height });
+                }
+            }
+            RowLayoutManager::layout_first_pass(&mut self.tabs_row);
+        }
+
+        self.scroll_offset = self.scroll_offset.clamp(0.0, self.max_scroll_offset());
+        self.enact_tabs_row();
+    }
+
+    fn enact_tabs_row(&mut self) {
+        let row_size = self.tabs_row.requested_size.clone();
+        self.tabs_row.enact_allocation_plan(
+            Position { x: self.position.x - self.scroll_offset, y: self.position.y },
+            row_size
+        );
+    }
+
+    fn scroll_to_tab(&mut self, index: usize) {
+        let tabs_area_rect = self.tabs_area_rect();
+        if let Some(tab) = self.tabs_row.children.get(index) {
+            let tab_left = tab.get_position().x + self.scroll_offset - self.position.x;
+            let tab_right = tab_left + tab.get_size().width;
+
+            if tab_left < self.scroll_offset {
+                self.scroll_offset = tab_left;
+            } else if tab_right > self.scroll_offset + tabs_area_rect.width() {
+                self.scroll_offset = tab_right - tabs_area_rect.width();
+            }
+            self.scroll_offset = self.scroll_offset.clamp(0.0, self.max_scroll_offset());
+            self.enact_tabs_row();
+        }
+    }
+
+    fn tab_index_at(&self, cursor_position: Point) -> Option<usize> {
+        if !self.tabs_area_rect().contains(cursor_position) {
+            return None;
+        }
+        self.tabs_row.children.iter().position(|tab| {
+            Rect::from_point_and_size(tab.get_position(), (tab.get_size().width, tab.get_size().height)).contains(cursor_position)
+        })
+    }
+
+    // Events
+    fn handle_press(&mut self, cursor_position: Point) {
+        if self.new_tab_button_rect().contains(cursor_position) {
+            self.open_tab(TabInfo::new(NEW_TAB_TITLE));
+            return;
+        }
+
+        if let Some(index) = self.tab_index_at(cursor_position) {
+            let tab = &self.tabs_row.children[index];
+            if Tab::close_button_rect(tab.get_position(), &tab.get_size()).contains(cursor_position) {
+                self.close_tab(index);
+                return;
+            }
+
+            self.select_tab(index);
+            self.drag = Some(TabDrag { index, press_x: cursor_position.x, current_x: cursor_position.x, is_dragging: false });
+        }
+    }
+
+    /*
+     * While dragging, the tab follows the cursor and swaps places with a neighbour
+     * as soon as its centre crosses the neighbour's centre
+     */
+    fn handle_drag(&mut self, cursor_position: Point) {
+        let Some(drag) = self.drag.as_mut() else { return; };
+        if !drag.is_dragging {
+            return;
+        }
+        drag.current_x = cursor_position.x;
+
+        let index = drag.index;
+        let offset = drag.current_x - drag.press_x;
+        let children = &self.tabs_row.children;
+        let dragged_center = children[index].get_position().x + children[index].get_size().width / 2.0 + offset;
+
+        let neighbour_center = |neighbour: usize| children[neighbour].get_position().x + children[neighbour].get_size().width / 2.0;
+        let target = if index + 1 < children.len() && dragged_center > neighbour_center(index + 1) {
+            Some(index + 1)
+        } else if index > 0 && dragged_center < neighbour_center(index - 1) {
+            Some(index - 1)
+        } else {
+            None
+        };
+
+        if let Some(target) = target {
+            let old_x = children[index].get_position().x;
+            self.move_tab(index, target);
+            let new_x = self.tabs_row.children[target].get_position().x;
+
+            if let Some(drag) = self.drag.as_mut() {
+                drag.index = target;
+                drag.press_x += new_x - old_x; // Keep the tab under the cursor now that its slot moved
+            }
+        }
+    }
+
+    // Arrows move between tabs like the tabs of a tablist; the strip is a single Tab stop
+    fn handle_named_key(&mut self, key: &NamedKey) -> bool {
+        let Some(last_index) = self.tabs.len().checked_sub(1) else { return false; };
+        let active_index = self.active_index.unwrap_or(0);
+        match key {
+            NamedKey::ArrowLeft => self.select_tab(active_index.saturating_sub(1)),
+            NamedKey::ArrowRight => self.select_tab((active_index + 1).min(last_index)),
+            NamedKey::Home => self.select_tab(0),
+            NamedKey::End => self.select_tab(last_index),
+            _ => return false,
+        }
+        true
+    }
+
+    fn handle_command(&mut self, command: Command) -> bool {
+        let tab_count = self.tabs.len();
+        match (command, self.active_index) {
+            (Command::NewTab, _) => self.open_tab(TabInfo::new(NEW_TAB_TITLE)),
+            (Command::CloseTab, Some(active_index)) => self.close_tab(active_index),
+            (Command::NextTab, Some(active_index)) => self.select_tab((active_index + 1) % tab_count),
+            (Command::PreviousTab, Some(active_index)) => self.select_tab((active_index + tab_count - 1) % tab_count),
+            _ => return false,
+        }
+        true
+    }
+
+    fn handle_wheel(&mut self, cursor_position: Point, delta_x: f32, delta_y: f32) {
+        if !self.tabs_area_rect().contains(cursor_position) {
+            return;
+        }
+        let delta = if delta_x.abs() > delta_y.abs() { delta_x } else { delta_y };
+        self.scroll_offset = (self.scroll_offset - delta).clamp(0.0, self.max_scroll_offset());
+        self.enact_tabs_row();
+    }
+
+    fn render_new_tab_button(&self, canvas: &Canvas) {
+        let rect = self.new_tab_button_rect();
+        let center = rect.center();
+        let mut paint = Paint::default();
+        paint.set_anti_alias(true);
+        paint.set_style(PaintStyle::Stroke);
+        paint.set_stroke_width(1.5);
+        paint.set_color(ThemeManager::current().color(ColorToken::TextMuted));
+
+        canvas.draw_line((center.x - 6.0, center.y), (center.x + 6.0, center.y), &paint);
+        canvas.draw_line((center.x, center.y - 6.0), (center.x, center.y + 6.0), &paint);
+    }
+}
+
+impl Element for TabStrip {
+    fn render(&self, canvas: &Canvas) {
+        let rect = self.rect();
+        BoxPainter::begin_group(canvas, rect, &self.computed_styles);
+        BoxPainter::paint_box(canvas, rect, &self.computed_styles, ColorToken::SurfaceSunken.into());
+
+        canvas.save();
+        canvas.clip_rect(self.tabs_area_rect(), None, true);
+
+        let dragged_index = self.drag.as_ref().filter(|drag| drag.is_dragging).map(|drag| drag.index);
+        for (index, tab) in self.tabs_row.children.iter().enumerate() {
+            if Some(index) != dragged_index {
+                tab.render(canvas);
+            }
+        }
+        if let (Some(drag), Some(index)) = (&self.drag, dragged_index) {
+            // The dragged tab floats translucently above its neighbours
+            BoxPainter::begin_group(canvas, rect, &Styles { opacity: Some(DRAGGED_TAB_OPACITY), ..Default::default() });
+            canvas.translate((drag.current_x - drag.press_x, 0.0));
+            self.tabs_row.children[index].render(canvas);
+            BoxPainter::end_group(canvas);
+        }
+
+        canvas.restore();
+
+        let active_tab = self.active_index.and_then(|index| self.tabs_row.children.get(index));
+        if let Some(tab) = active_tab.filter(|_| self.interaction.state.is_focus_visible) {
+            let tab_rect = Rect::from_point_and_size(tab.get_position(), (tab.get_size().width, tab.get_size().height));
+            BoxPainter::paint_focus_ring(canvas, tab_rect, &tab.get_computed_styles().border.unwrap_or_default().radius);
+        }
+
+        self.render_new_tab_button(canvas);
+        BoxPainter::end_group(canvas);
+    }
+
+    fn update(&mut self) {}
+
+    fn handle_event(&mut self, cursor_position: Point, event: &mut Event) {
+        if self.interaction.on_event(&event.event_type) {
+            self.restyle_for_interaction();
+        }
+
+        match &event.event_type {
+            EventType::MouseDown { button: MouseButton::Left, .. } => self.handle_press(cursor_position),
+            // The press on a tab becomes a drag once the pointer has moved far enough
+            EventType::DragStart { button: MouseButton::Left, .. } => {
+                if let Some(drag) = self.drag.as_mut() {
+                    drag.is_dragging = true;
+                }
+            }
+            EventType::DragMove { .. } => self.handle_drag(cursor_position),
+            EventType::MouseUp { button: MouseButton::Left, .. } | EventType::DragEnd { .. } | EventType::FocusLost => self.drag = None,
+            EventType::MouseWheel { delta, .. } if !event.is_handled() => {
+                let (delta_x, delta_y) = delta.get_pixels();
+                self.handle_wheel(cursor_position, delta_x, delta_y);
+                event.set_handled();
+            }
+            EventType::Gesture(Gesture::Pan { delta_x, delta_y, .. }) if !event.is_handled() => {
+                self.handle_wheel(cursor_position, *delta_x, *delta_y);
+                event.set_handled();
+            }
+            EventType::KeyDown(KeyInput { logical_key: Key::Named(key), .. }) if self.handle_named_key(key) => event.set_handled(),
+            EventType::Command(command) if !event.is_handled() && self.handle_command(*command) => event.set_handled(),
+            _ => (),
+        }
+    }
+
+    fn set_position(&mut self, position: Point) {
+        self.position = position;
+    }
+
+    fn set_size(&mut self, size: ElementSize) {
+        self.size = size;
+    }
+
+    fn layout(&mut self, _available_space: Option<ElementSize>) {
+        self.layout_tabs();
+    }
+
+    fn get_id(&self) -> String {
+        self._id.clone()
+    }
+
+    fn get_element_type(&self) -> ElementType {
+        ElementType::TabStrip
+    }
+
+    fn get_children_mut(&mut self) -> Option<&mut Vec<Box<dyn Element>>> {
+        None
+    }
+
+    fn get_position(&self) -> Point {
+        self.position
+    }
+
+    fn get_size(&self) -> ElementSize {
+        self.size.clone()
+    }
+
+    fn get_styles(&self) -> Styles {
+        self.styles.clone()
+    }
+
+    fn resolve_styles(&mut self, context: &StyleContext) {
+        let specified = self.interaction.apply(&self.styles, context);
+        self.computed_styles = StyleResolver::resolve_element(&SelectorSubject::of(self), &specified, context);
+        let children_style_context = context.for_children(SelectorSubject::of(self), &self.computed_styles);
+        // Children only inherit from it, so they need restyling only if an inherited property changed
+        if children_style_context.resolves_like(&self.children_style_context) {
+            return;
+        }
+        self.children_style_context = children_style_context;
+        self.tabs_row.resolve_styles(&self.children_style_context);
+    }
+
+    fn get_computed_styles(&self) -> Styles {
+        self.computed_styles.clone()
+    }
+
+    fn get_style_id(&self) -> Option<String> {
+        self.style_id.clone()
+    }
+
+    fn get_class_names(&self) -> Vec<String> {
+        self.class_names.clone()
+    }
+
+    fn take_needs_relayout(&mut self) -> bool {
+        let needs_relayout = self.interaction.take_needs_relayout();
+        self.tabs_row.take_needs_relayout() || needs_relayout
+    }
+
+    fn advance_animations(&mut self, now: Instant) -> bool {
+        let is_animating = self.interaction.advance_transition(now, &mut self.computed_styles);
+        self.tabs_row.advance_animations(now) || is_animating
+    }
+
+    fn is_variable_size(&self) -> Directions {
+        let mut directions = Directions { horizontal: true, vertical: true };
+
+        if let Some(size) = &self.computed_styles.size {
+            if size.width.is_some() { directions.horizontal = false; }
+            if size.height.is_some() { directions.vertical = false; }
+        }
+
+        directions
+    }
+
+    fn compute_allocation_plan(&mut self) {
+        let size = self.computed_styles.size.unwrap_or_default();
+        self.size = ElementSize {
+            width: size.width.unwrap_or(600.0),
+            height: size.height.unwrap_or(36.0),
+        };
+    }
+
+    fn enact_allocation_plan(&mut self, allocated_position: Position, allocated_size: ElementSize) {
+        self.set_position(Point::new(allocated_position.x, allocated_position.y));
+        self.set_size(allocated_size);
+        self.layout_tabs();
+    }
+
+    fn build_context_menu(&self, cursor_position: Point) -> Option<Menu> {
+        let cursor_position = self.computed_styles.map_to_local(self.rect(), cursor_position);
+        let index = self.tab_index_at(cursor_position)?;
+        self.tab_context_menu_builder.as_ref().map(|builder| builder(index))
+    }
+
+    fn find_tooltip(&self, cursor_position: Point) -> Option<(Tooltip, Rect)> {
+        let cursor_position = self.computed_styles.map_to_local(self.rect(), cursor_position);
+        let to_screen = |rect: Rect| self.computed_styles.map_rect_from_local(self.rect(), rect);
+        if self.new_tab_button_rect().contains(cursor_position) {
+            return Some((Tooltip::new("New tab"), to_screen(self.new_tab_button_rect())));
+        }
+        let index = self.tab_index_at(cursor_position)?;
+        let tab = &self.tabs_row.children[index];
+        let tab_rect = Rect::from_point_and_size(tab.get_position(), (tab.get_size().width, tab.get_size().height));
+
+        Some((Tooltip::new(&self.tabs[index].title), to_screen(tab_rect)))
+    }
+
+    fn find_cursor(&self, cursor_position: Point) -> Option<Cursor> {
+        if self.drag.as_ref().is_some_and(|drag| drag.is_dragging) {
+            return Some(Cursor::Grabbing); // Wherever the cursor is, the tab follows it until released
+        }
+        let cursor_position = self.computed_styles.map_to_local(self.rect(), cursor_position);
+        self.rect().contains(cursor_position).then(|| self.computed_styles.cursor.unwrap_or_default())
+    }
+
+    fn map_to_local(&self, cursor_position: Point) -> Point {
+        self.computed_styles.map_to_local(self.rect(), cursor_position)
+    }
+
+    fn is_focusable(&self) -> bool {
+        !self.interaction.state.is_disabled && !self.tabs.is_empty()
+    }
+
+    fn on_state_changed(&mut self, state: &AppState) {
+        // Taken out while it runs, as it gets the element itself
+        if let Some(mut state_binding) = self.state_binding.take() {
+            state_binding(self, state);
+            self.state_binding = Some(state_binding);
+        }
+    }
+}
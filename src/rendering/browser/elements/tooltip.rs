@@ -0,0 +1,21 @@
+use std::time::Duration;
+
+const DEFAULT_DELAY: Duration = Duration::from_millis(600);
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Tooltip {
+    pub text: String,
+    pub delay: Duration,
+}
+
+impl Tooltip {
+    pub fn new(text: &str) -> Self {
+        Self { text: text.to_string(), delay: DEFAULT_DELAY }
+    }
+
+    #[allow(dead_code)]
+    pub fn set_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+}
@@ -0,0 +1,597 @@
+use std::time::Instant;
+
+use skia_safe::{Canvas, Color, Contains, Paint, Point, Rect};
+
+use crate::rendering::browser::animation::momentum::Momentum;
+use crate::rendering::browser::internal::element_id_generator::IDGenerator;
+use crate::rendering::browser::layout::types::Position;
+use crate::rendering::browser::painting::box_painter::BoxPainter;
+use crate::rendering::browser::styling::{interaction_styles::{InteractionStyles, StateStyles}, style_resolver::{StyleContext, StyleResolver}, stylesheet::SelectorSubject};
+use crate::rendering::browser::styling::{theme::ColorToken, theme_manager::ThemeManager};
+
+use super::{common::ElementType, element::{Element, ElementSize, Event, EventPhase, EventType, Gesture, GesturePhase}, menu::Menu, styles::{Cursor, Directions, Styles}, tooltip::Tooltip};
+
+const OVERSCAN_ITEMS: usize = 4;
+const MAX_RECYCLED_ITEMS: usize = 32;
+const SCROLLBAR_WIDTH: f32 = 6.0;
+
+// Re-targets an item instance to the item at the index
+type ItemBinder<T> = Box<dyn FnMut(usize, &mut T)>;
+
+#[derive(Clone, Copy)]
+pub enum ItemHeight {
+    Fixed(f32),
+    Measured { estimated: f32 }, // Items are laid out with their own height once realized
+}
+
+/*
+ * Offsets of the items along the list; with measured heights, the heights of items not yet
+ * realized are estimates, and offsets are recomputed lazily after measurements change
+ */
+struct ItemExtents {
+    item_height: ItemHeight,
+    item_count: usize,
+    heights: Vec<f32>,
+    offsets: Vec<f32>,
+    are_offsets_stale: bool,
+}
+
+impl ItemExtents {
+    fn new(item_height: ItemHeight, item_count: usize) -> Self {
+        let heights = match item_height {
+            ItemHeight::Fixed(_) => vec![],
+            ItemHeight::Measured { estimated } => vec![estimated; item_count],
+        };
+        let mut extents = Self { item_height, item_count, heights, offsets: vec![], are_offsets_stale: true };
+        extents.update_offsets();
+        extents
+    }
+
+    fn update_offsets(&mut self) {
+        if !self.are_offsets_stale || matches!(self.item_height, ItemHeight::Fixed(_)) {
+            return;
+        }
+        let mut offset = 0.0;
+        self.offsets = self.heights.iter().map(|height| {
+            let item_offset = offset;
+            offset += height;
+            item_offset
+        }).collect();
+        self.offsets.push(offset);
+        self.are_offsets_stale = false;
+    }
+
+    fn set_measured_height(&mut self, index: usize, height: f32) {
+        if let ItemHeight::Measured { .. } = self.item_height {
+            if self.heights[index] != height {
+                self.heights[index] = height;
+                self.are_offsets_stale = true;
+            }
+        }
+    }
+
+    fn height_of(&self, index: usize) -> f32 {
+        match self.item_height {
+            ItemHeight::Fixed(height) => height,
+            ItemHeight::Measured { .. } => self.heights[index],
+        }
+    }
+
+    fn offset_of(&self, index: usize) -> f32 {
+        match self.item_height {
+            ItemHeight::Fixed(height) => index as f32 * height,
+            ItemHeight::Measured { .. } => self.offsets[index],
+        }
+    }
+
+    fn total_height(&self) -> f32 {
+        self.offset_of(self.item_count)
+    }
+
+    fn index_at(&self, offset: f32) -> usize {
+        if self.item_count == 0 {
+            return 0;
+        }
+        let index = match self.item_height {
+            ItemHeight::Fixed(height) if height > 0.0 => (offset / height).max(0.0) as usize,
+            ItemHeight::Fixed(_) => 0,
+            ItemHeight::Measured { .. } => self.offsets.partition_point(|item_offset| *item_offset <= offset).saturating_sub(1),
+        };
+        index.min(self.item_count - 1)
+    }
+}
+
+/*
+ * Scrollable list that only realizes the items inside, or near, the visible window.
+ * Items scrolled out of view are kept for reuse: the binder re-targets a recycled instance
+ * to a new index, and the builder is only called when no instance is available.
+ */
+pub struct VirtualList<T: Element + 'static> {
+    _id: String,
+    position: Point,
+    size: ElementSize,
+    styles: Styles,
+    computed_styles: Styles,
+    style_id: Option<String>,
+    class_names: Vec<String>,
+    interaction: InteractionStyles,
+    children_style_context: StyleContext, // Kept for children created after the last restyle
+    extents: ItemExtents,
+    item_builder: Box<dyn FnMut(usize) -> T>,
+    item_binder: ItemBinder<T>,
+    realized_items: Vec<(usize, T)>,
+    recycled_items: Vec<T>,
+    scroll_offset: f32,
+    momentum: Option<Momentum>, // Scrolling on after a fling
+}
+
+impl<T: Element + 'static> VirtualList<T> {
+    pub fn new(
+        item_count: usize,
+        item_height: ItemHeight,
+        item_builder: Box<dyn FnMut(usize) -> T>,
+        item_binder: ItemBinder<T>,
+    ) -> Self {
+        Self {
+            _id: IDGenerator::get(),
+            position: Point::new(0.0, 0.0),
+            size: ElementSize::default(),
+            styles: Styles::default(),
+            computed_styles: Styles::initial(),
+            style_id: None,
+            class_names: vec![],
+            interaction: InteractionStyles::new(),
+            children_style_context: StyleContext::default(),
+            extents: ItemExtents::new(item_height, item_count),
+            item_builder,
+            item_binder,
+            realized_items: vec![],
+            recycled_items: vec![],
+            scroll_offset: 0.0,
+            momentum: None,
+        }
+    }
+
+    pub fn set_styles(mut self, styles: Styles) -> Self {
+        self.styles = styles;
+        self
+    }
+
+    // Id and class names that stylesheet selectors match against
+    pub fn set_style_id(mut self, style_id: &str) -> Self {
+        self.style_id = Some(style_id.to_string());
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn add_class(mut self, class_name: &str) -> Self {
+        self.class_names.push(class_name.to_string());
+        self
+    }
+
+    // Overrides merged over the styles while the element is hovered, pressed, focused or disabled
+    #[allow(dead_code)]
+    pub fn set_state_styles(mut self, state_styles: StateStyles) -> Self {
+        self.interaction.state_styles = state_styles;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn set_disabled(mut self, is_disabled: bool) -> Self {
+        self.interaction.set_disabled(is_disabled);
+        self
+    }
+
+    fn restyle_for_interaction(&mut self) {
+        let previous_styles = self.computed_styles.clone();
+        self.resolve_styles(&self.interaction.get_context());
+        self.interaction.on_restyled(&previous_styles, &mut self.computed_styles);
+    }
+
+    #[allow(dead_code)]
+    pub fn set_item_count(&mut self, item_count: usize) {
+        let realized_items = std::mem::take(&mut self.realized_items);
+        self.recycle(realized_items);
+        self.extents = ItemExtents::new(self.extents.item_height, item_count);
+        self.scroll_offset = self.scroll_offset.clamp(0.0, self.max_scroll_offset());
+        self.update_realized_items();
+    }
+
+    #[allow(dead_code)]
+    pub fn scroll_to_item(&mut self, index: usize) {
+        if index >= self.extents.item_count {
+            return;
+        }
+        self.extents.update_offsets();
+        self.scroll_offset = self.extents.offset_of(index).clamp(0.0, self.max_scroll_offset());
+        self.update_realized_items();
+    }
+
+    fn rect(&self) -> Rect {
+        Rect::from_point_and_size(self.position, (self.size.width, self.size.height))
+    }
+
+    fn max_scroll_offset(&self) -> f32 {
+        (self.extents.total_height() - self.size.height).max(0.0)
+    }
+
+    fn visible_range(&self) -> (usize, usize) {
+        if self.extents.item_count == 0 {
+            return (0, 0);
+        }
+        let first = self.extents.index_at(self.scroll_offset).saturating_sub(OVERSCAN_ITEMS);
+        let last = (self.extents.index_at(self.scroll_offset + self.size.height) + OVERSCAN_ITEMS)
+            .min(self.extents.item_count - 1);
+        (first, last + 1)
+    }
+
+    /*
+     * Workflow:
+     *   A. Recycle the items that left the visible range
+     *   B. Realize the items that entered it, reusing recycled instances first
+     *   C. Measure the realized items (measured heights only) and position them
+     */
+    fn update_realized_items(&mut self) {
+        self.extents.update_offsets();
+        let (start, end) = self.visible_range();
+
+        let (kept, left): (Vec<_>, Vec<_>) = self.realized_items.drain(..)
+            .partition(|(index, _)| *index >= start && *index < end);
+        self.recycle(left);
+
+        let mut realized_items = Vec::with_capacity(end - start);
+        let mut kept = kept.into_iter().peekable();
+        for index in start..end {
+            if kept.peek().is_some_and(|(kept_index, _)| *kept_index == index) {
+                realized_items.push(kept.next().unwrap());
+                continue;
+            }
+            let mut item = match self.recycled_items.pop() {
+                Some(mut item) => {
+                    (self.item_binder)(index, &mut item);
+                    item
+                }
+                None => (self.item_builder)(index),
+            };
+            item.resolve_styles(&self.children_style_context);
+            realized_items.push((index, item));
+        }
+        self.recycled_items.truncate(MAX_RECYCLED_ITEMS);
+
+        for (index, item) in realized_items.iter_mut() {
+            item.compute_allocation_plan();
+            self.extents.set_measured_height(*index, item.get_size().height);
+        }
+        self.extents.update_offsets();
+
+        for (index, item) in realized_items.iter_mut() {
+            item.enact_allocation_plan(
+                Position { x: self.position.x, y: self.position.y + self.extents.offset_of(*index) - self.scroll_offset },
+                ElementSize { width: self.size.width - SCROLLBAR_WIDTH, height: self.extents.height_of(*index) }
+            );
+        }
+        self.realized_items = realized_items;
+    }
+
+    // Items leave hovered, pressed and focused behind, as they'll show other items when reused.
+    // UIManager forgets the paths to them, since they can no longer be reached
+    fn recycle(&mut self, items: Vec<(usize, T)>) {
+        for (_, mut item) in items {
+            for event_type in [EventType::MouseLeave, EventType::FocusLost, EventType::Blur] {
+                item.handle_event(Point::new(f32::NAN, f32::NAN), &mut Event::new(event_type));
+            }
+            self.recycled_items.push(item);
+        }
+    }
+
+    fn handle_wheel(&mut self, cursor_position: Point, delta_y: f32) {
+        if !self.rect().contains(cursor_position) {
+            return;
+        }
+        self.momentum = None;
+        self.scroll_by(-delta_y);
+    }
+
+    // Returns whether the list moved, rather than being at the end already
+    fn scroll_by(&mut self, delta: f32) -> bool {
+        let scroll_offset = (self.scroll_offset + delta).clamp(0.0, self.max_scroll_offset());
+        let has_moved = scroll_offset != self.scroll_offset;
+        self.scroll_offset = scroll_offset;
+        self.update_realized_items();
+        has_moved
+    }
+
+    // Returns whether the list is still scrolling after a fling; it stops at either end
+    fn advance_momentum(&mut self, now: Instant) -> bool {
+        let Some(momentum) = &mut self.momentum else { return false; };
+        let has_moved = match momentum.step(now) {
+            Some((_, delta_y)) => self.scroll_by(delta_y),
+            None => false,
+        };
+        if !has_moved {
+            self.momentum = None;
+        }
+        has_moved
+    }
+
+    fn render_scrollbar(&self, canvas: &Canvas) {
+        let total_height = self.extents.total_height();
+        if total_height <= self.size.height {
+            return;
+        }
+        let thumb_height = (self.size.height * self.size.height / total_height).max(24.0);
+        let thumb_top = self.position.y + (self.size.height - thumb_height) * (self.scroll_offset / self.max_scroll_offset());
+
+        let mut paint = Paint::default();
+        paint.set_anti_alias(true);
+        paint.set_color(ThemeManager::current().color(ColorToken::TextMuted).with_a(160));
+        canvas.draw_rect(Rect::from_xywh(self.position.x + self.size.width - SCROLLBAR_WIDTH, thumb_top, SCROLLBAR_WIDTH, thumb_height), &paint);
+    }
+}
+
+impl<T: Element + 'static> Element for VirtualList<T> {
+    fn render(&self, canvas: &Canvas) {
+        let border = self.computed_styles.border.unwrap_or_default();
+        BoxPainter::begin_group(canvas, self.rect(), &self.computed_styles);
+        BoxPainter::paint_box_background(canvas, self.rect(), &self.computed_styles, Color::TRANSPARENT.into());
+
+        canvas.save();
+        BoxPainter::clip_to_padding_box(canvas, self.rect(), &border);
+        for (_, item) in &self.realized_items {
+            item.render(canvas);
+        }
+        self.render_scrollbar(canvas);
+        canvas.restore();
+
+        BoxPainter::paint_border(canvas, self.rect(), &border);
+        BoxPainter::end_group(canvas);
+    }
+
+    fn update(&mut self) {
+        for (_, item) in &mut self.realized_items {
+            item.update();
+        }
+    }
+
+    fn handle_event(&mut self, cursor_position: Point, event: &mut Event) {
+        if self.interaction.on_event(&event.event_type) {
+            self.restyle_for_interaction();
+        }
+
+        // Scrolls for wheel events and pans over its items too, unless a scrollable item used them already.
+        // Pans and flings are left to the page when there's nothing to scroll
+        if event.phase == EventPhase::Capturing || event.is_handled() {
+            return;
+        }
+        let can_scroll = self.max_scroll_offset() > 0.0;
+        match &event.event_type {
+            EventType::MouseWheel { delta, .. } => {
+                self.handle_wheel(cursor_position, delta.get_pixels().1);
+                event.set_handled();
+            }
+            EventType::Gesture(Gesture::Pan { delta_y, phase, .. }) if can_scroll => {
+                if *phase == GesturePhase::Started {
+                    self.momentum = None;
+                }
+                self.scroll_by(-delta_y);
+                event.set_handled();
+            }
+            EventType::Gesture(Gesture::Fling { velocity_y, .. }) if can_scroll => {
+                self.momentum = Some(Momentum::new(0.0, -velocity_y, Instant::now()));
+                event.set_handled();
+            }
+            _ => (),
+        }
+    }
+
+    fn set_position(&mut self, position: Point) {
+        self.position = position;
+    }
+
+    fn set_size(&mut self, size: ElementSize) {
+        self.size = size;
+    }
+
+    fn layout(&mut self, _available_space: Option<ElementSize>) {
+        self.update_realized_items();
+    }
+
+    fn get_id(&self) -> String {
+        self._id.clone()
+    }
+
+    fn get_element_type(&self) -> ElementType {
+        ElementType::VirtualList
+    }
+
+    fn get_children_mut(&mut self) -> Option<&mut Vec<Box<dyn Element>>> {
+        None
+    }
+
+    fn get_position(&self) -> Point {
+        self.position
+    }
+
+    fn get_size(&self) -> ElementSize {
+        self.size.clone()
+    }
+
+    fn get_styles(&self) -> Styles {
+        self.styles.clone()
+    }
+
+    fn resolve_styles(&mut self, context: &StyleContext) {
+        let specified = self.interaction.apply(&self.styles, context);
+        self.computed_styles = StyleResolver::resolve_element(&SelectorSubject::of(self), &specified, context);
+        let children_style_context = context.for_children(SelectorSubject::of(self), &self.computed_styles);
+        // Children only inherit from it, so they need restyling only if an inherited property changed
+        if children_style_context.resolves_like(&self.children_style_context) {
+            return;
+        }
+        self.children_style_context = children_style_context;
+        for (_, item) in &mut self.realized_items {
+            item.resolve_styles(&self.children_style_context);
+        }
+    }
+
+    fn get_computed_styles(&self) -> Styles {
+        self.computed_styles.clone()
+    }
+
+    fn get_style_id(&self) -> Option<String> {
+        self.style_id.clone()
+    }
+
+    fn get_class_names(&self) -> Vec<String> {
+        self.class_names.clone()
+    }
+
+    fn take_needs_relayout(&mut self) -> bool {
+        let needs_relayout = self.interaction.take_needs_relayout();
+        self.realized_items.iter_mut().fold(needs_relayout, |needs_relayout, (_, item)| item.take_needs_relayout() || needs_relayout)
+    }
+
+    fn advance_animations(&mut self, now: Instant) -> bool {
+        let is_scrolling = self.advance_momentum(now);
+        let is_animating = self.interaction.advance_transition(now, &mut self.computed_styles) || is_scrolling;
+        self.realized_items.iter_mut().fold(is_animating, |is_animating, (_, item)| item.advance_animations(now) || is_animating)
+    }
+
+    fn is_variable_size(&self) -> Directions {
+        let mut directions = Directions { horizontal: true, vertical: true };
+
+        if let Some(size) = &self.computed_styles.size {
+            if size.width.is_some() { directions.horizontal = false; }
+            if size.height.is_some() { directions.vertical = false; }
+        }
+
+        directions
+    }
+
+    fn compute_allocation_plan(&mut self) {
+        let size = self.computed_styles.size.unwrap_or_default();
+        self.size = ElementSize {
+            width: size.width.unwrap_or(300.0),
+            height: size.height.unwrap_or(400.0),
+        };
+    }
+
+    fn enact_allocation_plan(&mut self, allocated_position: Position, allocated_size: ElementSize) {
+        self.set_position(Point::new(allocated_position.x, allocated_position.y));
+        self.set_size(allocated_size);
+        self.scroll_offset = self.scroll_offset.clamp(0.0, self.max_scroll_offset());
+        self.update_realized_items();
+    }
+
+    fn render_overlay(&self, canvas: &Canvas, viewport: &ElementSize) {
+        for (_, item) in &self.realized_items {
+            item.render_overlay(canvas, viewport);
+        }
+    }
+
+    fn handle_overlay_event(&mut self, cursor_position: Point, event_type: &EventType, viewport: &ElementSize) -> bool {
+        self.realized_items.iter_mut().rev()
+            .any(|(_, item)| item.handle_overlay_event(cursor_position, event_type, viewport))
+    }
+
+    fn build_context_menu(&self, cursor_position: Point) -> Option<Menu> {
+        let cursor_position = self.computed_styles.map_to_local(self.rect(), cursor_position);
+        if !self.rect().contains(cursor_position) {
+            return None;
+        }
+        self.realized_items.iter().rev().find_map(|(_, item)| item.build_context_menu(cursor_position))
+    }
+
+    fn find_tooltip(&self, cursor_position: Point) -> Option<(Tooltip, Rect)> {
+        let cursor_position = self.computed_styles.map_to_local(self.rect(), cursor_position);
+        if !self.rect().contains(cursor_position) {
+            return None;
+        }
+        self.realized_items.iter().rev().find_map(|(_, item)| item.find_tooltip(cursor_position))
+            .map(|(tooltip, rect)| (tooltip, self.computed_styles.map_rect_from_local(self.rect(), rect)))
+    }
+
+    fn find_cursor(&self, cursor_position: Point) -> Option<Cursor> {
+        let cursor_position = self.computed_styles.map_to_local(self.rect(), cursor_position);
+        if !self.rect().contains(cursor_position) {
+            return None;
+        }
+        self.realized_items.iter().rev()
+            .find_map(|(_, item)| item.find_cursor(cursor_position))
+            .or(self.computed_styles.cursor)
+    }
+
+    fn map_to_local(&self, cursor_position: Point) -> Point {
+        self.computed_styles.map_to_local(self.rect(), cursor_position)
+    }
+
+    // Items scrolled partially out of view can't be hit outside the list
+    fn can_hit_children_at(&self, cursor_position: Point) -> bool {
+        self.rect().contains(cursor_position)
+    }
+
+    // Items are reached by their index in the list rather than among the realized ones, so that
+    // scrolling doesn't move the hovered or focused path onto another item
+    fn get_event_child_indices(&self) -> Vec<usize> {
+        self.realized_items.iter().map(|(index, _)| *index).collect()
+    }
+
+    fn get_event_child(&self, index: usize) -> Option<&dyn Element> {
+        let position = self.realized_items.binary_search_by_key(&index, |(item_index, _)| *item_index).ok()?;
+        Some(&self.realized_items[position].1 as &dyn Element)
+    }
+
+    fn get_event_child_mut(&mut self, index: usize) -> Option<&mut dyn Element> {
+        let position = self.realized_items.binary_search_by_key(&index, |(item_index, _)| *item_index).ok()?;
+        Some(&mut self.realized_items[position].1 as &mut dyn Element)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rendering::browser::event_dispatcher::tests::{EventLog, Node};
+
+    use super::*;
+
+    #[test]
+    fn fixed_heights_place_items_at_multiples_of_the_height() {
+        let extents = ItemExtents::new(ItemHeight::Fixed(10.0), 100);
+        assert_eq!((extents.offset_of(0), extents.offset_of(3), extents.total_height()), (0.0, 30.0, 1000.0));
+        assert_eq!([-5.0, 0.0, 9.9, 10.0, 995.0, 5000.0].map(|offset| extents.index_at(offset)), [0, 0, 0, 1, 99, 99]);
+        assert_eq!(ItemExtents::new(ItemHeight::Fixed(10.0), 0).index_at(20.0), 0);
+    }
+
+    #[test]
+    fn measured_heights_move_the_items_after_them() {
+        let mut extents = ItemExtents::new(ItemHeight::Measured { estimated: 10.0 }, 5);
+        assert_eq!((extents.offset_of(2), extents.total_height()), (20.0, 50.0));
+
+        extents.set_measured_height(1, 30.0);
+        extents.update_offsets();
+        assert_eq!((extents.height_of(1), extents.offset_of(1), extents.offset_of(2)), (30.0, 10.0, 40.0));
+        assert_eq!(extents.total_height(), 70.0);
+        assert_eq!([0.0, 9.9, 10.0, 39.9, 40.0, 69.9, 500.0].map(|offset| extents.index_at(offset)), [0, 0, 1, 1, 2, 4, 4]);
+    }
+
+    fn list(item_count: usize, scroll_offset: f32) -> VirtualList<Node> {
+        let log = EventLog::default();
+        let mut list = VirtualList::new(
+            item_count,
+            ItemHeight::Fixed(10.0),
+            Box::new(move |_| Node::new("item", (0.0, 0.0, 100.0, 10.0), &log)),
+            Box::new(|_, _| {}),
+        );
+        list.set_size(ElementSize { width: 100.0, height: 50.0 });
+        list.scroll_offset = scroll_offset;
+        list
+    }
+
+    #[test]
+    fn the_visible_range_reaches_overscan_items_past_each_edge() {
+        assert_eq!(list(100, 0.0).visible_range(), (0, 5 + OVERSCAN_ITEMS + 1));
+        assert_eq!(list(100, 200.0).visible_range(), (20 - OVERSCAN_ITEMS, 25 + OVERSCAN_ITEMS + 1));
+        assert_eq!(list(100, 950.0).visible_range(), (95 - OVERSCAN_ITEMS, 100));
+        assert_eq!(list(3, 0.0).visible_range(), (0, 3));
+        assert_eq!(list(0, 0.0).visible_range(), (0, 0));
+    }
+}
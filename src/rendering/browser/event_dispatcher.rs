@@ -0,0 +1,261 @@
+use skia_safe::Point;
+
+use super::elements::element::{Element, Event, EventPhase, EventType};
+
+// Child indices from the root down to an element, see Element::get_event_child_indices; empty for the root itself
+pub type HitPath = Vec<usize>;
+
+pub struct EventDispatcher {
+
+}
+
+/*
+ * Finds the element an event is for and sends it there, as in the DOM:
+ *   A. Hit-testing walks down from the root, trying children topmost first. A child is only
+ *      tried where its parent doesn't clip it away, and each element maps the position into
+ *      its own coordinates first, so transforms are respected. The deepest element under the
+ *      cursor is the target, and the indices leading to it are its path
+ *   B. The event is then dispatched along the path: capturing down through the ancestors,
+ *      at the target, and bubbling back up through the ancestors, until a handler stops it
+ *   C. Enter and leave events aren't propagated: UIManager sends them to each element joining
+ *      or leaving the hovered path, see `update_path`. Focus and blur only go to their target
+ */
+impl EventDispatcher {
+    // Path to the topmost element under the cursor, or None if the cursor is outside the tree
+    pub fn hit_test(element: &dyn Element, cursor_position: Point) -> Option<HitPath> {
+        let local_position = element.map_to_local(cursor_position);
+
+        if element.can_hit_children_at(local_position) {
+            for index in element.get_event_child_indices().into_iter().rev() {
+                let Some(child) = element.get_event_child(index) else { continue; };
+                if let Some(mut path) = EventDispatcher::hit_test(child, local_position) {
+                    path.insert(0, index);
+                    return Some(path);
+                }
+            }
+        }
+        element.contains_point(local_position).then(HitPath::new)
+    }
+
+    pub fn dispatch(element: &mut dyn Element, path: &[usize], cursor_position: Point, event: &mut Event) {
+        let local_position = element.map_to_local(cursor_position);
+
+        // A path gone stale, e.g. to an item a virtual list has recycled since, ends at the last element it still reaches
+        let Some((index, rest)) = path.split_first().filter(|(index, _)| element.get_event_child(**index).is_some()) else {
+            event.phase = EventPhase::AtTarget;
+            element.handle_event(local_position, event);
+            return;
+        };
+
+        event.phase = EventPhase::Capturing;
+        element.handle_event(local_position, event);
+        if event.is_propagation_stopped() {
+            return;
+        }
+
+        if let Some(child) = element.get_event_child_mut(*index) {
+            EventDispatcher::dispatch(child, rest, local_position, event);
+        }
+        if event.is_propagation_stopped() {
+            return;
+        }
+
+        event.phase = EventPhase::Bubbling;
+        element.handle_event(local_position, event);
+    }
+
+    // Sends the event to the element at the end of the path only
+    pub fn dispatch_to_target(element: &mut dyn Element, path: &[usize], cursor_position: Point, event: &mut Event) {
+        let local_position = element.map_to_local(cursor_position);
+        match path.split_first().and_then(|(index, rest)| element.get_event_child_mut(*index).map(|child| (child, rest))) {
+            Some((child, rest)) => EventDispatcher::dispatch_to_target(child, rest, local_position, event),
+            None => {
+                event.phase = EventPhase::AtTarget;
+                element.handle_event(local_position, event);
+            }
+        }
+    }
+
+    // Sends the event to every element of the tree, e.g. when the window loses focus
+    pub fn broadcast(element: &mut dyn Element, event: &mut Event) {
+        event.phase = EventPhase::AtTarget;
+        element.handle_event(Point::new(f32::NAN, f32::NAN), event);
+
+        for index in element.get_event_child_indices() {
+            if let Some(child) = element.get_event_child_mut(index) {
+                EventDispatcher::broadcast(child, event);
+            }
+        }
+    }
+
+    /*
+     * Moves a path, like the hovered one, from `from` to `to`. Each element on `from` but not on
+     * `to` gets `leave`, deepest first; then each element on `to` but not on `from` gets `enter`,
+     * shallowest first. Elements both paths go through hear nothing.
+     */
+    pub fn update_path(
+        root: &mut dyn Element,
+        from: Option<&[usize]>,
+        to: Option<&[usize]>,
+        cursor_position: Point,
+        leave: EventType,
+        enter: EventType,
+    ) {
+        // Number of elements on both paths, the root included
+        let shared_count = match (from, to) {
+            (Some(from), Some(to)) => from.iter().zip(to).take_while(|(from, to)| from == to).count() + 1,
+            _ => 0,
+        };
+
+        if let Some(from) = from {
+            for depth in (shared_count..=from.len()).rev() {
+                EventDispatcher::dispatch_to_target(root, &from[..depth], cursor_position, &mut Event::new(leave.clone()));
+            }
+        }
+        if let Some(to) = to {
+            for depth in shared_count..=to.len() {
+                EventDispatcher::dispatch_to_target(root, &to[..depth], cursor_position, &mut Event::new(enter.clone()));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use skia_safe::{Canvas, Rect};
+
+    use crate::rendering::browser::{elements::{common::ElementType, element::ElementSize, styles::{Directions, Styles}}, layout::types::Position, styling::style_resolver::StyleContext};
+
+    use super::*;
+
+    pub(crate) type EventLog = Rc<RefCell<Vec<(&'static str, EventPhase, String)>>>;
+
+    // An element with a fixed rect, logging the events it gets as (name, phase, event type)
+    pub(crate) struct Node {
+        name: &'static str,
+        rect: Rect,
+        children: Vec<Box<dyn Element>>,
+        tab_index: Option<Option<i32>>, // Focusable when set
+        log: EventLog,
+    }
+
+    impl Node {
+        pub(crate) fn new(name: &'static str, (x, y, width, height): (f32, f32, f32, f32), log: &EventLog) -> Self {
+            Self { name, rect: Rect::from_xywh(x, y, width, height), children: vec![], tab_index: None, log: log.clone() }
+        }
+
+        pub(crate) fn focusable(mut self, tab_index: Option<i32>) -> Self {
+            self.tab_index = Some(tab_index);
+            self
+        }
+
+        pub(crate) fn add_children(mut self, children: Vec<Node>) -> Self {
+            self.children.extend(children.into_iter().map(|child| Box::new(child) as Box<dyn Element>));
+            self
+        }
+    }
+
+    impl Element for Node {
+        fn render(&self, _canvas: &Canvas) {}
+        fn update(&mut self) {}
+        fn handle_event(&mut self, _cursor_position: Point, event: &mut Event) {
+            let event_name = format!("{:?}", event.event_type);
+            self.log.borrow_mut().push((self.name, event.phase, event_name));
+        }
+        fn set_position(&mut self, _position: Point) {}
+        fn set_size(&mut self, _size: ElementSize) {}
+        fn layout(&mut self, _available_space: Option<ElementSize>) {}
+        fn get_id(&self) -> String {
+            self.name.to_string()
+        }
+        fn get_element_type(&self) -> ElementType {
+            ElementType::Row
+        }
+        fn get_children_mut(&mut self) -> Option<&mut Vec<Box<dyn Element>>> {
+            Some(&mut self.children)
+        }
+        fn get_position(&self) -> Point {
+            Point::new(self.rect.left, self.rect.top)
+        }
+        fn get_size(&self) -> ElementSize {
+            ElementSize { width: self.rect.width(), height: self.rect.height() }
+        }
+        fn get_styles(&self) -> Styles {
+            Styles::default()
+        }
+        fn is_variable_size(&self) -> Directions {
+            Directions { horizontal: false, vertical: false }
+        }
+        fn compute_allocation_plan(&mut self) {}
+        fn enact_allocation_plan(&mut self, _allocated_position: Position, _allocated_size: ElementSize) {}
+        fn resolve_styles(&mut self, _context: &StyleContext) {}
+        fn get_computed_styles(&self) -> Styles {
+            Styles::default()
+        }
+        fn is_focusable(&self) -> bool {
+            self.tab_index.is_some()
+        }
+        fn get_tab_index(&self) -> Option<i32> {
+            self.tab_index.flatten()
+        }
+        fn get_event_child_indices(&self) -> Vec<usize> {
+            (0..self.children.len()).collect()
+        }
+        fn get_event_child(&self, index: usize) -> Option<&dyn Element> {
+            self.children.get(index).map(|child| child.as_ref() as &dyn Element)
+        }
+        fn get_event_child_mut(&mut self, index: usize) -> Option<&mut dyn Element> {
+            self.children.get_mut(index).map(|child| child.as_mut() as &mut dyn Element)
+        }
+    }
+
+    // root: a, with a1 inside, then b overlapping a and painted over it
+    fn tree(log: &EventLog) -> Node {
+        Node::new("root", (0.0, 0.0, 100.0, 100.0), log).add_children(vec![
+            Node::new("a", (0.0, 0.0, 50.0, 50.0), log).add_children(vec![Node::new("a1", (10.0, 10.0, 10.0, 10.0), log)]),
+            Node::new("b", (25.0, 25.0, 50.0, 50.0), log),
+        ])
+    }
+
+    fn take_names(log: &EventLog) -> Vec<(&'static str, EventPhase)> {
+        log.borrow_mut().drain(..).map(|(name, phase, _)| (name, phase)).collect()
+    }
+
+    #[test]
+    fn hit_testing_finds_the_deepest_topmost_element() {
+        let root = tree(&EventLog::default());
+        assert_eq!(EventDispatcher::hit_test(&root, Point::new(15.0, 15.0)), Some(vec![0, 0]));
+        assert_eq!(EventDispatcher::hit_test(&root, Point::new(30.0, 30.0)), Some(vec![1]));
+        assert_eq!(EventDispatcher::hit_test(&root, Point::new(90.0, 5.0)), Some(vec![]));
+        assert_eq!(EventDispatcher::hit_test(&root, Point::new(150.0, 5.0)), None);
+    }
+
+    #[test]
+    fn events_are_captured_down_the_path_and_bubble_back_up() {
+        let log = EventLog::default();
+        let mut root = tree(&log);
+        EventDispatcher::dispatch(&mut root, &[0, 0], Point::new(15.0, 15.0), &mut Event::new(EventType::FocusGained));
+        assert_eq!(take_names(&log), [
+            ("root", EventPhase::Capturing),
+            ("a", EventPhase::Capturing),
+            ("a1", EventPhase::AtTarget),
+            ("a", EventPhase::Bubbling),
+            ("root", EventPhase::Bubbling),
+        ]);
+
+        // A stale path ends at the last element it reaches
+        EventDispatcher::dispatch(&mut root, &[0, 3], Point::new(15.0, 15.0), &mut Event::new(EventType::FocusGained));
+        assert_eq!(take_names(&log), [("root", EventPhase::Capturing), ("a", EventPhase::AtTarget), ("root", EventPhase::Bubbling)]);
+    }
+
+    #[test]
+    fn moving_a_path_leaves_deepest_first_and_enters_shallowest_first() {
+        let log = EventLog::default();
+        let mut root = tree(&log);
+        EventDispatcher::update_path(&mut root, Some(&[0, 0]), Some(&[1]), Point::new(30.0, 30.0), EventType::MouseLeave, EventType::MouseEnter);
+        let events: Vec<_> = log.borrow().iter().map(|(name, _, event_name)| format!("{} {}", name, event_name)).collect();
+        assert_eq!(events, ["a1 MouseLeave", "a MouseLeave", "b MouseEnter"]);
+    }
+}
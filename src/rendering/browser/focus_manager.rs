@@ -0,0 +1,122 @@
+use super::{elements::element::Element, event_dispatcher::HitPath};
+
+pub struct FocusManager {
+
+}
+
+/*
+ * Decides which element gets keyboard focus; UIManager keeps the focused element's path and
+ * sends it the key events:
+ *   A. A click focuses the clicked element if it's focusable, otherwise its closest focusable
+ *      ancestor; a click on nothing focusable clears the focus
+ *   B. Tab moves to the next focusable element in Tab order and Shift+Tab to the previous one,
+ *      wrapping around at either end. Elements with a positive tab index come first, by index,
+ *      then the others in tree order, so that the order follows the layout unless overridden
+ * Only elements events can reach take part, so items a virtual list hasn't realized are skipped.
+ */
+impl FocusManager {
+    pub fn find_click_target(root: &dyn Element, hit_path: &[usize]) -> Option<HitPath> {
+        let mut element = root;
+        let mut target = root.is_focusable().then(HitPath::new);
+
+        for (depth, index) in hit_path.iter().enumerate() {
+            let Some(child) = element.get_event_child(*index) else { break; };
+            element = child;
+            if element.is_focusable() {
+                target = Some(hit_path[..=depth].to_vec());
+            }
+        }
+        target
+    }
+
+    pub fn find_next(root: &dyn Element, current: Option<&[usize]>, is_backwards: bool) -> Option<HitPath> {
+        let mut focusable = vec![];
+        FocusManager::collect_focusable(root, &mut HitPath::new(), &mut focusable);
+
+        // Stable, so elements without a tab index keep their tree order
+        focusable.retain(|(_, tab_index)| *tab_index >= 0);
+        focusable.sort_by_key(|(_, tab_index)| if *tab_index > 0 { (0, *tab_index) } else { (1, 0) });
+        if focusable.is_empty() {
+            return None;
+        }
+
+        let count = focusable.len();
+        let next_index = match focusable.iter().position(|(path, _)| Some(path.as_slice()) == current) {
+            Some(index) if is_backwards => (index + count - 1) % count,
+            Some(index) => (index + 1) % count,
+            None if is_backwards => count - 1,
+            None => 0,
+        };
+        Some(focusable.swap_remove(next_index).0)
+    }
+
+    // Focusable elements with their tab index, in tree order
+    fn collect_focusable(element: &dyn Element, path: &mut HitPath, focusable: &mut Vec<(HitPath, i32)>) {
+        if element.is_focusable() {
+            focusable.push((path.clone(), element.get_tab_index().unwrap_or(0)));
+        }
+
+        for index in element.get_event_child_indices() {
+            if let Some(child) = element.get_event_child(index) {
+                path.push(index);
+                FocusManager::collect_focusable(child, path, focusable);
+                path.pop();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::event_dispatcher::tests::{EventLog, Node}, *};
+
+    // Focusable a, b (tab index 2), c (1) and d (-1), then e, which isn't, holding focusable e1
+    fn tree() -> Node {
+        let log = EventLog::default();
+        let node = |name| Node::new(name, (0.0, 0.0, 10.0, 10.0), &log);
+        node("root").add_children(vec![
+            node("a").focusable(None),
+            node("b").focusable(Some(2)),
+            node("c").focusable(Some(1)),
+            node("d").focusable(Some(-1)),
+            node("e").add_children(vec![node("e1").focusable(None)]),
+        ])
+    }
+
+    fn tab_order(root: &Node, is_backwards: bool) -> Vec<HitPath> {
+        let mut current = None;
+        (0..5).map(|_| {
+            current = FocusManager::find_next(root, current.as_deref(), is_backwards);
+            current.clone().unwrap()
+        }).collect()
+    }
+
+    #[test]
+    fn tab_goes_through_positive_indices_first_then_tree_order_and_wraps() {
+        let root = tree();
+        assert_eq!(tab_order(&root, false), [vec![2], vec![1], vec![0], vec![4, 0], vec![2]]);
+    }
+
+    #[test]
+    fn shift_tab_goes_backwards_and_wraps() {
+        let root = tree();
+        assert_eq!(tab_order(&root, true), [vec![4, 0], vec![0], vec![1], vec![2], vec![4, 0]]);
+        // From an element Tab skips, like one focused by clicking, Tab starts over
+        assert_eq!(FocusManager::find_next(&root, Some(&[3]), false), Some(vec![2]));
+    }
+
+    #[test]
+    fn clicks_focus_the_closest_focusable_ancestor() {
+        let log = EventLog::default();
+        let node = |name| Node::new(name, (0.0, 0.0, 10.0, 10.0), &log);
+        let root = node("root").add_children(vec![
+            node("button").focusable(None).add_children(vec![node("label").add_children(vec![node("icon")])]),
+            node("text"),
+        ]);
+
+        assert_eq!(FocusManager::find_click_target(&root, &[0, 0, 0]), Some(vec![0]));
+        assert_eq!(FocusManager::find_click_target(&root, &[0]), Some(vec![0]));
+        assert_eq!(FocusManager::find_click_target(&root, &[1]), None);
+        assert_eq!(FocusManager::find_click_target(&root, &[0, 7]), Some(vec![0]));
+    }
+}
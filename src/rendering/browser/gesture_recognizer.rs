@@ -0,0 +1,327 @@
+use std::time::{Duration, Instant};
+
+use skia_safe::Point;
+use winit::event::TouchPhase;
+
+use super::elements::element::{Gesture, GesturePhase};
+
+const TOUCH_SLOP: f32 = 8.0; // Distance fingers move before they're panning rather than resting
+const LONG_PRESS_DELAY: Duration = Duration::from_millis(500);
+const FLING_MIN_VELOCITY: f32 = 300.0; // Pixels per second
+const VELOCITY_WINDOW: Duration = Duration::from_millis(100); // Movement before the release the fling velocity is measured over
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum RecognizerState {
+    Idle,
+    Possible,    // Fingers are down but haven't moved far enough to be panning
+    Panning,     // Panning, and pinching too with two fingers or more
+    LongPressed, // Held still long enough; the touches do nothing more until lifted
+}
+
+struct TrackedTouch {
+    id: u64,
+    position: Point,
+}
+
+pub struct GestureRecognizer {
+    touches: Vec<TrackedTouch>,
+    state: RecognizerState,
+    start_centroid: Point,
+    last_centroid: Point,
+    last_span: Option<f32>, // Average distance of the touches from their centroid; None with a single touch
+    is_pinching: bool,
+    long_press_deadline: Option<Instant>,
+    recent_centroids: Vec<(Instant, Point)>,
+}
+
+/*
+ * Recognizes gestures from the touches of one touch sequence, from the first finger down to the
+ * last one up. Touches are fed in with their time, so any sequence can be replayed:
+ *   A. Fingers held still for the long press delay make a long press
+ *   B. Fingers moving further than the touch slop make a pan, following their centroid. With
+ *      two fingers or more it's a pinch as well, scaling with their spread
+ *   C. A pan that ends fast enough ends in a fling
+ * Fingers joining or leaving during a pan move the centroid, so it's measured again from there.
+ */
+impl GestureRecognizer {
+    pub fn new() -> Self {
+        Self {
+            touches: vec![],
+            state: RecognizerState::Idle,
+            start_centroid: Point::new(0.0, 0.0),
+            last_centroid: Point::new(0.0, 0.0),
+            last_span: None,
+            is_pinching: false,
+            long_press_deadline: None,
+            recent_centroids: vec![],
+        }
+    }
+
+    pub fn on_touch(&mut self, id: u64, phase: TouchPhase, position: Point, now: Instant) -> Vec<Gesture> {
+        let mut gestures = vec![];
+        match phase {
+            TouchPhase::Started => {
+                self.touches.push(TrackedTouch { id, position });
+                if self.state == RecognizerState::Idle {
+                    self.state = RecognizerState::Possible;
+                    self.long_press_deadline = Some(now + LONG_PRESS_DELAY);
+                } else {
+                    self.long_press_deadline = None; // More fingers make a pan or a pinch
+                }
+                self.reset_baseline();
+            }
+            TouchPhase::Moved => {
+                let Some(touch) = self.touches.iter_mut().find(|touch| touch.id == id) else { return gestures; };
+                touch.position = position;
+                self.on_moved(now, &mut gestures);
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                self.touches.retain(|touch| touch.id != id);
+                if self.touches.is_empty() {
+                    self.finish(now, phase == TouchPhase::Ended, &mut gestures);
+                    return gestures;
+                }
+                if self.is_pinching && self.touches.len() < 2 {
+                    self.is_pinching = false;
+                    gestures.push(Gesture::Pinch { scale: 1.0, phase: GesturePhase::Ended });
+                }
+                self.reset_baseline();
+            }
+        }
+        gestures
+    }
+
+    // Recognizes a long press once its delay has passed
+    pub fn on_timer(&mut self, now: Instant) -> Vec<Gesture> {
+        match self.long_press_deadline {
+            Some(deadline) if now >= deadline => {
+                self.long_press_deadline = None;
+                self.state = RecognizerState::LongPressed;
+                vec![Gesture::LongPress]
+            }
+            _ => vec![],
+        }
+    }
+
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.long_press_deadline
+    }
+
+    // Whether fingers are down, so that a finger going down joins the touch sequence rather than starting one
+    pub fn is_tracking(&self) -> bool {
+        !self.touches.is_empty()
+    }
+
+    // Whether a gesture took the touches over, so that they no longer act as a pointer
+    pub fn has_taken_over(&self) -> bool {
+        matches!(self.state, RecognizerState::Panning | RecognizerState::LongPressed)
+    }
+
+    // Centroid of the touches as of the last gesture; where gestures are sent
+    pub fn get_position(&self) -> Point {
+        self.last_centroid
+    }
+
+    fn on_moved(&mut self, now: Instant, gestures: &mut Vec<Gesture>) {
+        let centroid = self.get_centroid();
+        let span = self.get_span();
+        let delta = centroid - self.last_centroid;
+
+        match self.state {
+            RecognizerState::Possible => {
+                let has_spread = matches!((span, self.last_span), (Some(span), Some(last_span)) if (span - last_span).abs() > TOUCH_SLOP);
+                if (centroid - self.start_centroid).length() <= TOUCH_SLOP && !has_spread {
+                    return;
+                }
+                self.state = RecognizerState::Panning;
+                self.long_press_deadline = None;
+                gestures.push(Gesture::Pan { delta_x: delta.x, delta_y: delta.y, phase: GesturePhase::Started });
+            }
+            RecognizerState::Panning => {
+                gestures.push(Gesture::Pan { delta_x: delta.x, delta_y: delta.y, phase: GesturePhase::Moved });
+            }
+            RecognizerState::Idle | RecognizerState::LongPressed => return,
+        }
+
+        if let (Some(span), Some(last_span)) = (span, self.last_span.filter(|last_span| *last_span > 0.0)) {
+            let phase = if self.is_pinching { GesturePhase::Moved } else { GesturePhase::Started };
+            self.is_pinching = true;
+            gestures.push(Gesture::Pinch { scale: span / last_span, phase });
+        }
+
+        self.last_centroid = centroid;
+        self.last_span = span;
+        self.recent_centroids.retain(|(time, _)| now.duration_since(*time) <= VELOCITY_WINDOW);
+        self.recent_centroids.push((now, centroid));
+    }
+
+    // The last finger was lifted, or the touches were cancelled
+    fn finish(&mut self, now: Instant, is_lifted: bool, gestures: &mut Vec<Gesture>) {
+        if self.state == RecognizerState::Panning {
+            if self.is_pinching {
+                gestures.push(Gesture::Pinch { scale: 1.0, phase: GesturePhase::Ended });
+            }
+            gestures.push(Gesture::Pan { delta_x: 0.0, delta_y: 0.0, phase: GesturePhase::Ended });
+
+            let (velocity_x, velocity_y) = self.get_velocity(now);
+            if is_lifted && velocity_x.hypot(velocity_y) >= FLING_MIN_VELOCITY {
+                gestures.push(Gesture::Fling { velocity_x, velocity_y });
+            }
+        }
+
+        self.state = RecognizerState::Idle;
+        self.is_pinching = false;
+        self.long_press_deadline = None;
+        self.recent_centroids.clear();
+    }
+
+    fn reset_baseline(&mut self) {
+        let centroid = self.get_centroid();
+        if self.state == RecognizerState::Possible {
+            self.start_centroid = centroid;
+        }
+        self.last_centroid = centroid;
+        self.last_span = self.get_span();
+        self.recent_centroids.clear();
+    }
+
+    fn get_centroid(&self) -> Point {
+        let sum = self.touches.iter().fold(Point::new(0.0, 0.0), |sum, touch| sum + touch.position);
+        let count = self.touches.len().max(1) as f32;
+        Point::new(sum.x / count, sum.y / count)
+    }
+
+    fn get_span(&self) -> Option<f32> {
+        if self.touches.len() < 2 {
+            return None;
+        }
+        let centroid = self.get_centroid();
+        let total_distance: f32 = self.touches.iter().map(|touch| (touch.position - centroid).length()).sum();
+        Some(total_distance / self.touches.len() as f32)
+    }
+
+    // Centroid velocity over the last moments of movement, in pixels per second
+    fn get_velocity(&self, now: Instant) -> (f32, f32) {
+        let recent: Vec<&(Instant, Point)> = self.recent_centroids.iter()
+            .filter(|(time, _)| now.duration_since(*time) <= VELOCITY_WINDOW)
+            .collect();
+        let (Some((first_time, first)), Some((last_time, last))) = (recent.first(), recent.last()) else { return (0.0, 0.0); };
+
+        let elapsed = last_time.duration_since(*first_time).as_secs_f32();
+        if elapsed <= 0.0 {
+            return (0.0, 0.0);
+        }
+        ((last.x - first.x) / elapsed, (last.y - first.y) / elapsed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Touch sequences are replayed against a fixed start, so their timing is exact
+    fn at(start: Instant, milliseconds: u64) -> Instant {
+        start + Duration::from_millis(milliseconds)
+    }
+
+    fn touch(recognizer: &mut GestureRecognizer, id: u64, phase: TouchPhase, x: f32, y: f32, now: Instant) -> Vec<Gesture> {
+        recognizer.on_touch(id, phase, Point::new(x, y), now)
+    }
+
+    // A single finger going down at (100, 100), then moving right to each x in turn, 10ms apart
+    fn pan(recognizer: &mut GestureRecognizer, start: Instant, xs: &[f32]) -> Vec<Gesture> {
+        let mut gestures = touch(recognizer, 1, TouchPhase::Started, 100.0, 100.0, start);
+        for (index, x) in xs.iter().enumerate() {
+            gestures.extend(touch(recognizer, 1, TouchPhase::Moved, *x, 100.0, at(start, 10 * (index as u64 + 1))));
+        }
+        gestures
+    }
+
+    #[test]
+    fn movement_within_the_slop_is_no_pan() {
+        let start = Instant::now();
+        let mut recognizer = GestureRecognizer::new();
+
+        let mut gestures = touch(&mut recognizer, 1, TouchPhase::Started, 100.0, 100.0, start);
+        gestures.extend(touch(&mut recognizer, 1, TouchPhase::Moved, 104.0, 103.0, at(start, 16)));
+        gestures.extend(touch(&mut recognizer, 1, TouchPhase::Ended, 104.0, 103.0, at(start, 32)));
+
+        assert!(gestures.is_empty(), "{:?}", gestures);
+        assert!(!recognizer.has_taken_over());
+    }
+
+    #[test]
+    fn crossing_the_slop_starts_moves_and_ends_a_pan() {
+        let start = Instant::now();
+        let mut recognizer = GestureRecognizer::new();
+
+        let started = touch(&mut recognizer, 1, TouchPhase::Started, 100.0, 100.0, start);
+        assert!(started.is_empty());
+        let crossed = touch(&mut recognizer, 1, TouchPhase::Moved, 120.0, 100.0, at(start, 16));
+        assert!(matches!(crossed[..], [Gesture::Pan { delta_x, phase: GesturePhase::Started, .. }] if delta_x == 20.0), "{:?}", crossed);
+        assert!(recognizer.has_taken_over());
+
+        let moved = touch(&mut recognizer, 1, TouchPhase::Moved, 130.0, 100.0, at(start, 32));
+        assert!(matches!(moved[..], [Gesture::Pan { delta_x, phase: GesturePhase::Moved, .. }] if delta_x == 10.0), "{:?}", moved);
+
+        // Lifted long after the last movement, so too slowly to fling
+        let ended = touch(&mut recognizer, 1, TouchPhase::Ended, 130.0, 100.0, at(start, 1000));
+        assert!(matches!(ended[..], [Gesture::Pan { phase: GesturePhase::Ended, .. }]), "{:?}", ended);
+        assert!(!recognizer.has_taken_over());
+    }
+
+    #[test]
+    fn two_fingers_spreading_pinch_out() {
+        let start = Instant::now();
+        let mut recognizer = GestureRecognizer::new();
+
+        touch(&mut recognizer, 1, TouchPhase::Started, 100.0, 100.0, start);
+        touch(&mut recognizer, 2, TouchPhase::Started, 140.0, 100.0, at(start, 5));
+        assert_eq!(recognizer.next_deadline(), None, "A second finger makes no long press");
+
+        let gestures = touch(&mut recognizer, 2, TouchPhase::Moved, 180.0, 100.0, at(start, 16));
+        assert!(gestures.iter().any(|gesture| matches!(gesture, Gesture::Pinch { scale, phase: GesturePhase::Started } if *scale > 1.0)), "{:?}", gestures);
+
+        let lifted = touch(&mut recognizer, 2, TouchPhase::Ended, 180.0, 100.0, at(start, 1000));
+        assert!(matches!(lifted[..], [Gesture::Pinch { phase: GesturePhase::Ended, .. }]), "{:?}", lifted);
+    }
+
+    #[test]
+    fn holding_still_past_the_delay_is_a_long_press() {
+        let start = Instant::now();
+        let mut recognizer = GestureRecognizer::new();
+
+        touch(&mut recognizer, 1, TouchPhase::Started, 100.0, 100.0, start);
+        assert_eq!(recognizer.next_deadline(), Some(start + LONG_PRESS_DELAY));
+        assert!(recognizer.on_timer(start + LONG_PRESS_DELAY - Duration::from_millis(1)).is_empty());
+
+        let gestures = recognizer.on_timer(start + LONG_PRESS_DELAY);
+        assert!(matches!(gestures[..], [Gesture::LongPress]), "{:?}", gestures);
+        assert!(recognizer.has_taken_over());
+
+        // Once pressed, moving the finger doesn't pan
+        assert!(touch(&mut recognizer, 1, TouchPhase::Moved, 200.0, 100.0, start + LONG_PRESS_DELAY + Duration::from_millis(16)).is_empty());
+    }
+
+    #[test]
+    fn lifting_fast_flings() {
+        let start = Instant::now();
+        let mut recognizer = GestureRecognizer::new();
+
+        pan(&mut recognizer, start, &[150.0, 200.0, 250.0]);
+        let ended = touch(&mut recognizer, 1, TouchPhase::Ended, 250.0, 100.0, at(start, 30));
+
+        assert!(matches!(ended[..], [Gesture::Pan { phase: GesturePhase::Ended, .. }, Gesture::Fling { velocity_x, .. }] if velocity_x > FLING_MIN_VELOCITY), "{:?}", ended);
+    }
+
+    #[test]
+    fn cancelled_touches_dont_fling() {
+        let start = Instant::now();
+        let mut recognizer = GestureRecognizer::new();
+
+        pan(&mut recognizer, start, &[150.0, 200.0, 250.0]);
+        let cancelled = touch(&mut recognizer, 1, TouchPhase::Cancelled, 250.0, 100.0, at(start, 30));
+
+        assert!(matches!(cancelled[..], [Gesture::Pan { phase: GesturePhase::Ended, .. }]), "{:?}", cancelled);
+    }
+}
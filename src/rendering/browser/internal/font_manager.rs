@@ -0,0 +1,72 @@
+use std::{cell::RefCell, fs, path::Path};
+
+use skia_safe::{textlayout::{FontCollection, TypefaceFontProvider}, FontMgr};
+
+thread_local! {
+    static FONT_COLLECTION: RefCell<FontCollection> = RefCell::new(FontManager::create_font_collection());
+    static BUNDLED_FONTS: RefCell<TypefaceFontProvider> = RefCell::new(TypefaceFontProvider::new());
+}
+
+const FONT_FILE_EXTENSIONS: [&str; 4] = ["ttf", "otf", "ttc", "woff2"];
+
+pub struct FontManager {
+
+}
+
+/*
+ * Owns the one font collection all text is shaped with:
+ *   A. Bundled font files, registered from a directory at startup, are searched first
+ *   B. Then the fonts installed on the system
+ *   C. Glyphs none of the requested families have (CJK, emoji, ...) fall back to any
+ *      installed font that has them
+ */
+impl FontManager {
+    fn create_font_collection() -> FontCollection {
+        let mut font_collection = FontCollection::new();
+        font_collection.set_default_font_manager(FontMgr::new(), None);
+        font_collection.enable_font_fallback();
+        font_collection
+    }
+
+    pub fn get_font_collection() -> FontCollection {
+        FONT_COLLECTION.with(|font_collection| font_collection.borrow().clone())
+    }
+
+    // Registers every font file in the directory under its own family name; returns how many were loaded
+    pub fn load_bundled_fonts(directory: &Path) -> usize {
+        let Ok(entries) = fs::read_dir(directory) else { return 0; }; // No bundled fonts
+
+        let font_paths = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension()
+                .and_then(|extension| extension.to_str())
+                .is_some_and(|extension| FONT_FILE_EXTENSIONS.contains(&extension.to_lowercase().as_str())));
+
+        let mut loaded_count = 0;
+        for font_path in font_paths {
+            match fs::read(&font_path) {
+                Ok(data) if FontManager::register_font_data(&data, None) => loaded_count += 1,
+                Ok(_) => eprintln!("Ignoring font {}: unsupported format", font_path.display()),
+                Err(error) => eprintln!("Ignoring font {}: {}", font_path.display(), error),
+            }
+        }
+        loaded_count
+    }
+
+    // Registers a font file's contents, under `alias` instead of its own family name if given
+    pub fn register_font_data(data: &[u8], alias: Option<&str>) -> bool {
+        let Some(typeface) = FontMgr::new().new_from_data(data, None) else { return false; };
+
+        let bundled_fonts = BUNDLED_FONTS.with(|bundled_fonts| {
+            let mut bundled_fonts = bundled_fonts.borrow_mut();
+            bundled_fonts.register_typeface(typeface, alias);
+            bundled_fonts.clone()
+        });
+        FONT_COLLECTION.with(|font_collection| {
+            let mut font_collection = font_collection.borrow_mut();
+            font_collection.set_asset_font_manager(Some(bundled_fonts.into()));
+            font_collection.clear_caches(); // Families looked up before may now resolve differently
+        });
+        true
+    }
+}
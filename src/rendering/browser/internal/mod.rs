@@ -1 +1,2 @@
-pub mod element_id_generator;
\ No newline at end of file
+pub mod element_id_generator;
+pub mod font_manager;
\ No newline at end of file
@@ -14,14 +14,14 @@ impl SizeEvaluator {
         let max_children_height = row.children.iter().map(|child| child.get_size().height)
             .max_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal)).unwrap_or(0.0);
 
-        let padding = row.styles.padding.clone().unwrap_or_default();
-        let border = row.styles.border.clone().unwrap_or_default();
+        let padding = row.computed_styles.padding.clone().unwrap_or_default();
+        let border = row.computed_styles.border.clone().unwrap_or_default();
 
         let natural_row_width = SizeEvaluator::determine_natural_row_width(row, total_children_width, &padding, &border);
         let natural_row_height = SizeEvaluator::determine_natural_row_height(max_children_height, &padding, &border);
 
-        let requested_row_width = SizeEvaluator::determine_requested_row_width(row.styles.size.clone().unwrap_or_default(), natural_row_width);
-        let requested_row_height = SizeEvaluator::determine_requested_row_height(row.styles.size.clone().unwrap_or_default(), natural_row_height);
+        let requested_row_width = SizeEvaluator::determine_requested_row_width(row.computed_styles.size.clone().unwrap_or_default(), natural_row_width);
+        let requested_row_height = SizeEvaluator::determine_requested_row_height(row.computed_styles.size.clone().unwrap_or_default(), natural_row_height);
 
         row.size = ElementSize { width: requested_row_width, height: requested_row_height };
         println!("Row size: {:?}", row.size.clone());
@@ -37,7 +37,7 @@ impl SizeEvaluator {
         border: &Border
     ) -> f32 {
         let base_width = total_children_width + row.get_spacing_x() * (row.children.len() as f32 - 1.0)
-            + padding.left + padding.right + 2.0 * border.width;
+            + padding.left + padding.right + border.get_horizontal_width();
 
         base_width
     }
@@ -47,7 +47,7 @@ impl SizeEvaluator {
         padding: &Padding,
         border: &Border
     ) -> f32 {
-        let base_height = max_children_height + padding.top + padding.bottom + 2.0 * border.width;
+        let base_height = max_children_height + padding.top + padding.bottom + border.get_vertical_width();
 
         base_height
     }
@@ -91,7 +91,7 @@ impl SizeEvaluator {
     
     fn get_child_effective_width(child: &Box<dyn Element>) -> f32 {
         child.get_size().width + 
-        child.get_styles().margin.clone().unwrap_or_default().left + 
-        child.get_styles().margin.clone().unwrap_or_default().right
+        child.get_computed_styles().margin.clone().unwrap_or_default().left + 
+        child.get_computed_styles().margin.clone().unwrap_or_default().right
     }
 }
\ No newline at end of file
@@ -26,9 +26,9 @@ impl SpaceAllocationPlanEnacter {
                 if planned_allocation.request.request_type == SpaceRequestType::ChildSize {
                     let position = Point::new(cursor_x, child_plan.child_planned_position.y);
                     println!("Setting position for child ID {} to {:?}", child.get_id(), position);
-                    let size = ElementSize {
+                    let size = ElementSize { // Child size requests only carry the width
                         width: planned_allocation.request.requested_space.horizontal(),
-                        height: planned_allocation.request.requested_space.vertical(),
+                        height: child.get_size().height,
                     };
                     child.set_position(position);
                     child.set_size(size);
@@ -27,7 +27,7 @@ impl SpaceAllocationPlanManager {
             
             let child_allocation_plan = SpaceAllocationPlanner::plan_child_space_allocations(
                 child, space_allocation_requests, &mut available_width, &mut cursor_x, 
-                &row.styles.alignment, available_height, base_y
+                &row.computed_styles.alignment, available_height, base_y
             );
             
             plan.child_space_allocation_plans.push(child_allocation_plan);
@@ -42,9 +42,9 @@ impl SpaceAllocationPlanManager {
     ) -> (f32, f32, Padding, f32) {
         let available_width = row.requested_size.width.clone();
         let available_height = row.requested_size.height.clone();
-        // let margin = row.styles.margin.clone().unwrap_or_default();
-        let padding = row.styles.padding.clone().unwrap_or_default();
-        // let border = row.styles.border.clone().unwrap_or_default();
+        // let margin = row.computed_styles.margin.clone().unwrap_or_default();
+        let padding = row.computed_styles.padding.clone().unwrap_or_default();
+        // let border = row.computed_styles.border.clone().unwrap_or_default();
         let spacing_x = row.get_spacing_x();
 
         (available_width, available_height, padding, spacing_x)
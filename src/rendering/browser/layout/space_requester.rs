@@ -33,12 +33,12 @@ impl SpaceRequester {
             ChildSpaceRequest::new(
                 child.get_id(),
                 SpaceRequestType::Margin,
-                Space { left: child.get_styles().margin.clone().unwrap_or_default().left, ..Default::default() }
+                Space { left: child.get_computed_styles().margin.clone().unwrap_or_default().left, ..Default::default() }
             ),
             ChildSpaceRequest::new(
                 child.get_id(),
                 SpaceRequestType::Border,
-                Space { left: child.get_styles().border.clone().unwrap_or_default().width, ..Default::default() }
+                Space { left: child.get_computed_styles().border.clone().unwrap_or_default().get_left_side().width, ..Default::default() }
             ),
             ChildSpaceRequest::new(
                 child.get_id(),
@@ -48,12 +48,12 @@ impl SpaceRequester {
             ChildSpaceRequest::new(
                 child.get_id(),
                 SpaceRequestType::Border,
-                Space { right: child.get_styles().border.clone().unwrap_or_default().width, ..Default::default() }
+                Space { right: child.get_computed_styles().border.clone().unwrap_or_default().get_right_side().width, ..Default::default() }
             ),
             ChildSpaceRequest::new(
                 child.get_id(),
                 SpaceRequestType::Margin,
-                Space { right: child.get_styles().margin.clone().unwrap_or_default().right, ..Default::default() }
+                Space { right: child.get_computed_styles().margin.clone().unwrap_or_default().right, ..Default::default() }
             ),
         ];
         needed_space_allocations.extend(children_space_allocations);
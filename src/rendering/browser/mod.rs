@@ -1,5 +1,17 @@
 pub mod ui_manager;
+pub mod event_dispatcher;
+pub mod focus_manager;
+pub mod drag_manager;
+pub mod gesture_recognizer;
+pub mod tooltip_manager;
 pub mod ui_body;
+pub mod ui_macro;
 pub mod layout;
 pub mod elements;
-mod internal;
\ No newline at end of file
+pub mod painting;
+pub mod styling;
+pub mod animation;
+pub mod reactive;
+pub mod shortcuts;
+pub mod state;
+pub(crate) mod internal;
\ No newline at end of file
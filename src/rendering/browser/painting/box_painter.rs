@@ -0,0 +1,265 @@
+use skia_safe::{
+    canvas::SaveLayerRec, paint::Cap, BlendMode, BlurStyle, Canvas, ClipOp, Color, MaskFilter, Paint, PaintStyle, Path,
+    PathEffect, PathFillType, Point, RRect, Rect, Shader, TileMode, Vector,
+};
+
+use crate::rendering::browser::elements::styles::{Border, BorderRadius, BorderSide, BorderStyle, BoxShadow, ColorValue, Gradient, Styles};
+use crate::rendering::browser::styling::theme::ColorToken;
+
+const FOCUS_RING_WIDTH: f32 = 2.0;
+const FOCUS_RING_GAP: f32 = 2.0; // Between the border box and the ring
+
+pub struct BoxPainter;
+
+/*
+ * Paints an element's box (shadows, background and border) and clips its contents:
+ *   A. The box shape is the element rect with the border radius corners
+ *   B. Drop shadows go around the shape, then the background color and gradient fill it,
+ *      then inset shadows are drawn inside the border
+ *   C. A uniform border is stroked in one pass along the shape, inset by half its width
+ *   D. A non-uniform border is stroked side by side, each side clipped to the triangle between
+ *      the box centre and its two corners so that neighbouring sides meet on the diagonal
+ *   E. Children are clipped to the shape inside the border (the padding box)
+ *
+ * Opacity and blend modes apply to the element and its subtree as a group: `begin_group` opens a
+ * save layer that is composited once by `end_group`, so overlapping children don't show through
+ * each other. A transform is concatenated inside the group, so it moves the composited result.
+ */
+impl BoxPainter {
+    pub fn begin_group(canvas: &Canvas, rect: Rect, styles: &Styles) {
+        let opacity = styles.opacity.unwrap_or(1.0).clamp(0.0, 1.0);
+        let blend_mode = styles.blend_mode.unwrap_or(BlendMode::SrcOver);
+
+        if opacity >= 1.0 && blend_mode == BlendMode::SrcOver {
+            // Nothing to composite; a plain save keeps begin/end balanced without an offscreen layer
+            canvas.save();
+        } else {
+            let mut paint = Paint::default();
+            paint.set_alpha_f(opacity);
+            paint.set_blend_mode(blend_mode);
+            canvas.save_layer(&SaveLayerRec::default().paint(&paint));
+        }
+
+        if let Some(transform) = styles.transform {
+            canvas.concat(&transform.get_matrix(rect));
+        }
+    }
+
+    pub fn end_group(canvas: &Canvas) {
+        canvas.restore();
+    }
+
+    pub fn paint_box(canvas: &Canvas, rect: Rect, styles: &Styles, default_color: ColorValue) {
+        BoxPainter::paint_box_background(canvas, rect, styles, default_color);
+        BoxPainter::paint_border(canvas, rect, &styles.border.unwrap_or_default());
+    }
+
+    // Everything but the border, for elements whose content is drawn between the two
+    pub fn paint_box_background(canvas: &Canvas, rect: Rect, styles: &Styles, default_color: ColorValue) {
+        let border = styles.border.unwrap_or_default();
+        let shadows = styles.shadows.as_deref().unwrap_or_default();
+
+        BoxPainter::paint_shadows(canvas, rect, &border, shadows, false);
+        BoxPainter::paint_background(canvas, rect, styles.color.unwrap_or(default_color).resolve(), &border);
+        if let Some(gradient) = &styles.gradient {
+            BoxPainter::paint_gradient(canvas, rect, gradient, &border);
+        }
+        BoxPainter::paint_shadows(canvas, rect, &border, shadows, true);
+    }
+
+    pub fn get_border_box(rect: Rect, radius: &BorderRadius) -> RRect {
+        // Corners in skia order: upper left, upper right, lower right, lower left
+        let radii = [
+            Vector::new(radius.top_left, radius.top_left),
+            Vector::new(radius.top_right, radius.top_right),
+            Vector::new(radius.bottom_right, radius.bottom_right),
+            Vector::new(radius.bottom_left, radius.bottom_left),
+        ];
+        RRect::new_rect_radii(rect, &radii)
+    }
+
+    pub fn get_padding_box(rect: Rect, border: &Border) -> RRect {
+        let top = border.get_top_side().width;
+        let right = border.get_right_side().width;
+        let bottom = border.get_bottom_side().width;
+        let left = border.get_left_side().width;
+
+        let inner_rect = Rect::new(rect.left + left, rect.top + top, rect.right - right, rect.bottom - bottom);
+        // Inner corners shrink by the widths of the sides that meet there
+        let radii = [
+            Vector::new((border.radius.top_left - left).max(0.0), (border.radius.top_left - top).max(0.0)),
+            Vector::new((border.radius.top_right - right).max(0.0), (border.radius.top_right - top).max(0.0)),
+            Vector::new((border.radius.bottom_right - right).max(0.0), (border.radius.bottom_right - bottom).max(0.0)),
+            Vector::new((border.radius.bottom_left - left).max(0.0), (border.radius.bottom_left - bottom).max(0.0)),
+        ];
+        RRect::new_rect_radii(inner_rect, &radii)
+    }
+
+    pub fn paint_background(canvas: &Canvas, rect: Rect, color: Color, border: &Border) {
+        if color.a() == 0 {
+            return;
+        }
+
+        let mut paint = Paint::default();
+        paint.set_anti_alias(true);
+        paint.set_style(PaintStyle::Fill);
+        paint.set_color(color);
+        canvas.draw_rrect(BoxPainter::get_border_box(rect, &border.radius), &paint);
+    }
+
+    pub fn paint_gradient(canvas: &Canvas, rect: Rect, gradient: &Gradient, border: &Border) {
+        let shader = match gradient {
+            Gradient::Linear { angle, stops } => {
+                // The gradient line passes through the centre and is long enough to reach the corners
+                let (sin, cos) = angle.to_radians().sin_cos();
+                let half_length = (rect.width() / 2.0 * sin).abs() + (rect.height() / 2.0 * cos).abs();
+                let direction = Vector::new(sin * half_length, cos * half_length);
+                let center = rect.center();
+
+                let colors: Vec<Color> = stops.iter().map(|stop| stop.color.resolve()).collect();
+                let offsets: Vec<f32> = stops.iter().map(|stop| stop.offset).collect();
+                Shader::linear_gradient((center - direction, center + direction), colors.as_slice(), offsets.as_slice(), TileMode::Clamp, None, None)
+            }
+            Gradient::Radial { center_x, center_y, radius, stops } => {
+                let center = Point::new(rect.left + rect.width() * center_x, rect.top + rect.height() * center_y);
+                let radius = rect.width().max(rect.height()) * radius;
+
+                let colors: Vec<Color> = stops.iter().map(|stop| stop.color.resolve()).collect();
+                let offsets: Vec<f32> = stops.iter().map(|stop| stop.offset).collect();
+                Shader::radial_gradient(center, radius, colors.as_slice(), offsets.as_slice(), TileMode::Clamp, None, None)
+            }
+        };
+        let Some(shader) = shader else { return; }; // Fewer than two stops
+
+        let mut paint = Paint::default();
+        paint.set_anti_alias(true);
+        paint.set_shader(shader);
+        canvas.draw_rrect(BoxPainter::get_border_box(rect, &border.radius), &paint);
+    }
+
+    // Shadows are listed top-most first, so they are painted in reverse
+    pub fn paint_shadows(canvas: &Canvas, rect: Rect, border: &Border, shadows: &[BoxShadow], inset: bool) {
+        for shadow in shadows.iter().rev().filter(|shadow| shadow.inset == inset) {
+            let mut paint = Paint::default();
+            paint.set_anti_alias(true);
+            paint.set_color(shadow.color.resolve());
+            if shadow.blur_radius > 0.0 {
+                paint.set_mask_filter(MaskFilter::blur(BlurStyle::Normal, shadow.blur_radius / 2.0, None));
+            }
+
+            canvas.save();
+            if inset {
+                // Fill everything around a hole shaped like the padding box, seen only through that box
+                let padding_box = BoxPainter::get_padding_box(rect, border);
+                let hole = padding_box
+                    .with_inset((shadow.spread, shadow.spread))
+                    .with_offset((shadow.offset_x, shadow.offset_y));
+                let extent = shadow.blur_radius + shadow.spread.abs() + shadow.offset_x.abs() + shadow.offset_y.abs() + 1.0;
+
+                let mut path = Path::new();
+                path.add_rect(padding_box.rect().with_outset((extent, extent)), None);
+                path.add_rrect(hole, None);
+                path.set_fill_type(PathFillType::EvenOdd);
+
+                canvas.clip_rrect(padding_box, ClipOp::Intersect, true);
+                canvas.draw_path(&path, &paint);
+            } else {
+                // Drop shadows never show through the element itself, even with a translucent background
+                let border_box = BoxPainter::get_border_box(rect, &border.radius);
+                let shadow_box = border_box
+                    .with_outset((shadow.spread, shadow.spread))
+                    .with_offset((shadow.offset_x, shadow.offset_y));
+
+                canvas.clip_rrect(border_box, ClipOp::Difference, true);
+                canvas.draw_rrect(shadow_box, &paint);
+            }
+            canvas.restore();
+        }
+    }
+
+    pub fn paint_border(canvas: &Canvas, rect: Rect, border: &Border) {
+        let border_box = BoxPainter::get_border_box(rect, &border.radius);
+
+        if border.is_uniform() {
+            BoxPainter::stroke_side(canvas, &border_box, &border.get_top_side());
+            return;
+        }
+
+        let center = rect.center();
+        let sides = [
+            (border.get_top_side(), [Point::new(rect.left, rect.top), Point::new(rect.right, rect.top)]),
+            (border.get_right_side(), [Point::new(rect.right, rect.top), Point::new(rect.right, rect.bottom)]),
+            (border.get_bottom_side(), [Point::new(rect.right, rect.bottom), Point::new(rect.left, rect.bottom)]),
+            (border.get_left_side(), [Point::new(rect.left, rect.bottom), Point::new(rect.left, rect.top)]),
+        ];
+
+        for (side, [start, end]) in sides {
+            if side.width <= 0.0 || side.color.resolve().a() == 0 {
+                continue;
+            }
+
+            let mut triangle = Path::new();
+            triangle.move_to(center);
+            triangle.line_to(start);
+            triangle.line_to(end);
+            triangle.close();
+
+            canvas.save();
+            canvas.clip_path(&triangle, ClipOp::Intersect, true);
+            BoxPainter::stroke_side(canvas, &border_box, &side);
+            canvas.restore();
+        }
+    }
+
+    // Outline around the border box, following its corners, of the element focused from the keyboard
+    pub fn paint_focus_ring(canvas: &Canvas, rect: Rect, radius: &BorderRadius) {
+        let outset = FOCUS_RING_GAP + FOCUS_RING_WIDTH / 2.0;
+        let grow = |corner: f32| if corner > 0.0 { corner + outset } else { 0.0 };
+        let ring_radius = BorderRadius {
+            top_left: grow(radius.top_left),
+            top_right: grow(radius.top_right),
+            bottom_right: grow(radius.bottom_right),
+            bottom_left: grow(radius.bottom_left),
+        };
+
+        let mut paint = Paint::default();
+        paint.set_anti_alias(true);
+        paint.set_style(PaintStyle::Stroke);
+        paint.set_stroke_width(FOCUS_RING_WIDTH);
+        paint.set_color(ColorValue::Token(ColorToken::FocusRing).resolve());
+        canvas.draw_rrect(BoxPainter::get_border_box(rect.with_outset((outset, outset)), &ring_radius), &paint);
+    }
+
+    // Clips subsequent drawing to the inside of the border; callers wrap this in save/restore
+    pub fn clip_to_padding_box(canvas: &Canvas, rect: Rect, border: &Border) {
+        canvas.clip_rrect(BoxPainter::get_padding_box(rect, border), ClipOp::Intersect, true);
+    }
+
+    fn stroke_side(canvas: &Canvas, border_box: &RRect, side: &BorderSide) {
+        if side.width <= 0.0 || side.color.resolve().a() == 0 {
+            return;
+        }
+
+        let mut paint = Paint::default();
+        paint.set_anti_alias(true);
+        paint.set_style(PaintStyle::Stroke);
+        paint.set_stroke_width(side.width);
+        paint.set_color(side.color.resolve());
+
+        match side.style {
+            BorderStyle::Solid => (),
+            BorderStyle::Dashed => {
+                paint.set_path_effect(PathEffect::dash(&[3.0 * side.width, 2.0 * side.width], 0.0));
+            }
+            BorderStyle::Dotted => {
+                // Zero-length dashes with round caps draw as dots one width in diameter
+                paint.set_stroke_cap(Cap::Round);
+                paint.set_path_effect(PathEffect::dash(&[0.0, 2.0 * side.width], 0.0));
+            }
+        }
+
+        // Strokes are centred on the path, so inset by half the width to keep them inside the box
+        let half_width = side.width / 2.0;
+        canvas.draw_rrect(border_box.with_inset((half_width, half_width)), &paint);
+    }
+}
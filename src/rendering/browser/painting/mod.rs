@@ -0,0 +1,2 @@
+pub mod box_painter;
+pub mod text_painter;
\ No newline at end of file
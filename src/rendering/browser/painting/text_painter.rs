@@ -0,0 +1,108 @@
+use skia_safe::{
+    font_style::{Slant, Weight, Width},
+    textlayout::{self, Paragraph, ParagraphBuilder, ParagraphStyle, TextDirection, TextStyle},
+    Canvas, Color, FontStyle, Point, Rect,
+};
+
+use crate::rendering::browser::{
+    elements::styles::{Direction, FontSlant, Styles, TextAlign, TextDecoration},
+    internal::font_manager::FontManager,
+};
+
+const ELLIPSIS: &str = "\u{2026}";
+
+pub struct TextPainter {
+
+}
+
+/*
+ * Lays out and paints text with the typography of computed styles:
+ *   A. Family list, size, weight, slant, letter spacing and line height make up the text style
+ *   B. Alignment and direction make up the paragraph style; Start and End follow the direction
+ *   C. The paragraph is shaped through FontManager's shared collection, which handles fallback
+ * Labels in the chrome are single lines, cut off with an ellipsis when they don't fit.
+ */
+impl TextPainter {
+    // Paints one line of text vertically centred in the rect
+    pub fn paint_line(canvas: &Canvas, text: &str, rect: Rect, styles: &Styles, color: Color) {
+        if rect.width() <= 0.0 {
+            return;
+        }
+        let paragraph = TextPainter::layout_line(text, styles, color, rect.width());
+        let top = rect.top + (rect.height() - paragraph.height()) / 2.0;
+        paragraph.paint(canvas, Point::new(rect.left, top));
+    }
+
+    // Width of the text on one line, without cutting it off
+    pub fn measure_width(text: &str, styles: &Styles) -> f32 {
+        let mut paragraph = TextPainter::build_paragraph(text, styles, Color::BLACK, None);
+        paragraph.layout(f32::INFINITY);
+        paragraph.max_intrinsic_width().ceil()
+    }
+
+    fn layout_line(text: &str, styles: &Styles, color: Color, width: f32) -> Paragraph {
+        let mut paragraph = TextPainter::build_paragraph(text, styles, color, Some(1));
+        paragraph.layout(width);
+        paragraph
+    }
+
+    fn build_paragraph(text: &str, styles: &Styles, color: Color, max_lines: Option<usize>) -> Paragraph {
+        let text_style = TextPainter::get_text_style(styles, color);
+
+        let mut paragraph_style = ParagraphStyle::new();
+        paragraph_style.set_text_style(&text_style);
+        paragraph_style.set_text_direction(match styles.direction.unwrap_or_default() {
+            Direction::LeftToRight => TextDirection::LTR,
+            Direction::RightToLeft => TextDirection::RTL,
+        });
+        paragraph_style.set_text_align(match styles.text_align.unwrap_or_default() {
+            TextAlign::Start => textlayout::TextAlign::Start,
+            TextAlign::Center => textlayout::TextAlign::Center,
+            TextAlign::End => textlayout::TextAlign::End,
+        });
+        if let Some(max_lines) = max_lines {
+            paragraph_style.set_max_lines(max_lines);
+            paragraph_style.set_ellipsis(ELLIPSIS);
+        }
+
+        let mut builder = ParagraphBuilder::new(&paragraph_style, FontManager::get_font_collection());
+        builder.push_style(&text_style);
+        builder.add_text(text);
+        builder.build()
+    }
+
+    fn get_text_style(styles: &Styles, color: Color) -> TextStyle {
+        let initial = Styles::initial();
+        let mut text_style = TextStyle::new();
+        text_style.set_color(color);
+        text_style.set_font_size(styles.font_size.or(initial.font_size).unwrap_or_default());
+        if let Some(font_families) = &styles.font_families {
+            text_style.set_font_families(font_families.as_slice());
+        }
+
+        let slant = match styles.font_slant.unwrap_or_default() {
+            FontSlant::Normal => Slant::Upright,
+            FontSlant::Italic => Slant::Italic,
+            FontSlant::Oblique => Slant::Oblique,
+        };
+        let weight = styles.font_weight.or(initial.font_weight).unwrap_or_default();
+        text_style.set_font_style(FontStyle::new(Weight::from(weight as i32), Width::NORMAL, slant));
+
+        text_style.set_letter_spacing(styles.letter_spacing.unwrap_or_default());
+        if let Some(line_height) = styles.line_height {
+            text_style.set_height(line_height);
+            text_style.set_height_override(true);
+        }
+
+        let decoration = match styles.text_decoration.unwrap_or_default() {
+            TextDecoration::None => textlayout::TextDecoration::NO_DECORATION,
+            TextDecoration::Underline => textlayout::TextDecoration::UNDERLINE,
+            TextDecoration::Overline => textlayout::TextDecoration::OVERLINE,
+            TextDecoration::LineThrough => textlayout::TextDecoration::LINE_THROUGH,
+        };
+        text_style.set_decoration_type(decoration);
+        text_style.set_decoration_color(color);
+
+        text_style
+    }
+}
@@ -0,0 +1,28 @@
+use std::rc::Rc;
+
+use super::runtime::{ObserverKind, ObserverNode};
+
+/*
+ * An element property computed from signals, like the text of a label. The element pulls the
+ * value when UIManager updates the tree, see Element::update, and only when a signal it read
+ * changed in the meantime; dropping the element drops the binding with it.
+ */
+pub struct Binding<T> {
+    observer: Rc<ObserverNode>,
+    compute: Box<dyn Fn() -> T>,
+}
+
+impl<T: PartialEq> Binding<T> {
+    pub fn new(compute: impl Fn() -> T + 'static) -> Self {
+        Self { observer: ObserverNode::new(ObserverKind::Binding), compute: Box::new(compute) }
+    }
+
+    // The new value, if a signal it read changed and the value is different from the element's current one
+    pub fn take_changed(&self, current: &T) -> Option<T> {
+        if !self.observer.is_stale() {
+            return None;
+        }
+        let value = self.observer.track(|| (self.compute)());
+        (value != *current).then_some(value)
+    }
+}
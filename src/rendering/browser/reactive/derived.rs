@@ -0,0 +1,39 @@
+use std::{cell::RefCell, rc::Rc};
+
+use super::runtime::{ObserverKind, ObserverNode, Subscribers};
+
+struct DerivedNode<T> {
+    observer: Rc<ObserverNode>,
+    compute: Box<dyn Fn() -> T>,
+    value: RefCell<Option<T>>, // None until first read
+}
+
+// A value computed from signals and other derived values; recomputed lazily, once read after they change
+pub struct Derived<T> {
+    node: Rc<DerivedNode<T>>,
+}
+
+impl<T> Clone for Derived<T> {
+    fn clone(&self) -> Self {
+        Self { node: self.node.clone() }
+    }
+}
+
+impl<T: Clone + 'static> Derived<T> {
+    pub fn new(compute: impl Fn() -> T + 'static) -> Self {
+        let observer = ObserverNode::new(ObserverKind::Derived(Subscribers::default()));
+        Self { node: Rc::new(DerivedNode { observer, compute: Box::new(compute), value: RefCell::new(None) }) }
+    }
+
+    pub fn get(&self) -> T {
+        if let Some(subscribers) = self.node.observer.get_subscribers() {
+            subscribers.track();
+        }
+        let is_computed = self.node.value.borrow().is_some();
+        if self.node.observer.is_stale() || !is_computed {
+            let value = self.node.observer.track(|| (self.node.compute)());
+            *self.node.value.borrow_mut() = Some(value);
+        }
+        self.node.value.borrow().clone().expect("Derived value is computed above")
+    }
+}
@@ -0,0 +1,17 @@
+use std::{cell::RefCell, rc::Rc};
+
+use super::runtime::{ObserverKind, ObserverNode};
+
+// Runs a closure now, and again whenever a signal it read changes, for as long as the handle is kept
+#[must_use = "the effect stops when its handle is dropped"]
+pub struct Effect {
+    observer: Rc<ObserverNode>,
+}
+
+impl Effect {
+    pub fn new(run: impl FnMut() + 'static) -> Self {
+        let observer = ObserverNode::new(ObserverKind::Effect(RefCell::new(Some(Box::new(run)))));
+        observer.run_effect();
+        Self { observer }
+    }
+}
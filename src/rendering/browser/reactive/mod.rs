@@ -0,0 +1,5 @@
+pub mod binding;
+pub mod derived;
+pub mod effect;
+pub mod runtime;
+pub mod signal;
\ No newline at end of file
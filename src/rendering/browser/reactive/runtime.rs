@@ -0,0 +1,266 @@
+use std::{cell::{Cell, RefCell}, rc::{Rc, Weak}};
+
+thread_local! {
+    static RUNNING_OBSERVERS: RefCell<Vec<Rc<ObserverNode>>> = const { RefCell::new(vec![]) }; // Innermost last; reads subscribe it
+    static PENDING_EFFECTS: RefCell<Vec<Weak<ObserverNode>>> = const { RefCell::new(vec![]) };
+    static BATCH_DEPTH: Cell<usize> = const { Cell::new(0) };
+    static HAS_STALE_BINDINGS: Cell<bool> = const { Cell::new(false) };
+}
+
+pub(super) enum ObserverKind {
+    Derived(Subscribers),                      // Passes the change on to what read it
+    Effect(RefCell<Option<Box<dyn FnMut()>>>), // Runs again; taken out while it runs
+    Binding,                                   // Waits for its element to pull the new value, see UIManager::update
+}
+
+// Something computed from signals: a derived value, an effect or an element binding
+pub(super) struct ObserverNode {
+    kind: ObserverKind,
+    run_count: Cell<u64>, // Subscriptions made by earlier runs are stale
+    is_stale: Cell<bool>,
+}
+
+impl ObserverNode {
+    // Stale until it first runs
+    pub(super) fn new(kind: ObserverKind) -> Rc<Self> {
+        Rc::new(Self { kind, run_count: Cell::new(0), is_stale: Cell::new(true) })
+    }
+
+    pub(super) fn is_stale(&self) -> bool {
+        self.is_stale.get()
+    }
+
+    pub(super) fn get_subscribers(&self) -> Option<&Subscribers> {
+        match &self.kind {
+            ObserverKind::Derived(subscribers) => Some(subscribers),
+            _ => None,
+        }
+    }
+
+    // Runs the computation with the signals it reads becoming its dependencies, in place of those of the last run
+    pub(super) fn track<R>(self: &Rc<Self>, compute: impl FnOnce() -> R) -> R {
+        self.run_count.set(self.run_count.get() + 1);
+        self.is_stale.set(false);
+        RUNNING_OBSERVERS.with(|observers| observers.borrow_mut().push(self.clone()));
+        let result = compute();
+        RUNNING_OBSERVERS.with(|observers| observers.borrow_mut().pop());
+        result
+    }
+
+    pub(super) fn run_effect(self: &Rc<Self>) {
+        let ObserverKind::Effect(callback) = &self.kind else { return; };
+        let Some(mut run) = callback.borrow_mut().take() else { return; }; // Already running
+        self.track(&mut run);
+        *callback.borrow_mut() = Some(run);
+    }
+
+    // A dependency changed; notifying again before the next run changes nothing
+    fn notify(self: &Rc<Self>) {
+        if self.is_stale.replace(true) {
+            return;
+        }
+        match &self.kind {
+            ObserverKind::Derived(subscribers) => subscribers.notify(),
+            ObserverKind::Effect(_) => PENDING_EFFECTS.with(|effects| effects.borrow_mut().push(Rc::downgrade(self))),
+            ObserverKind::Binding => HAS_STALE_BINDINGS.with(|has_stale_bindings| has_stale_bindings.set(true)),
+        }
+    }
+}
+
+// Observers that read a signal or derived value since it last changed
+#[derive(Default)]
+pub(super) struct Subscribers {
+    observers: RefCell<Vec<(Weak<ObserverNode>, u64)>>, // With the run that read it
+}
+
+impl Subscribers {
+    // Subscribes the observer running now, if any
+    pub(super) fn track(&self) {
+        let Some(observer) = RUNNING_OBSERVERS.with(|observers| observers.borrow().last().cloned()) else { return; };
+        let run_count = observer.run_count.get();
+        let mut observers = self.observers.borrow_mut();
+        let is_subscribed = observers.iter().any(|(subscriber, subscribed_run)| *subscribed_run == run_count && subscriber.as_ptr() == Rc::as_ptr(&observer));
+        if !is_subscribed {
+            observers.push((Rc::downgrade(&observer), run_count));
+        }
+    }
+
+    // Each subscription is notified once: observers subscribe again as they run again
+    pub(super) fn notify(&self) {
+        let observers = std::mem::take(&mut *self.observers.borrow_mut());
+        for (observer, subscribed_run) in observers {
+            if let Some(observer) = observer.upgrade().filter(|observer| observer.run_count.get() == subscribed_run) {
+                observer.notify();
+            }
+        }
+    }
+}
+
+pub struct ReactiveRuntime {
+
+}
+
+/*
+ * Tracks which signals each derived value, effect and element binding reads, so a change
+ * reaches only those that depend on it:
+ *   A. Derived values are marked stale and recompute the next time they're read
+ *   B. Effects run again straight after the change, or once at the end of a batch
+ *   C. Bindings are marked stale and their elements pull the new value when UIManager updates
+ *      the tree; only elements whose value actually changed restyle, lay out or redraw
+ * Dependencies are tracked again on every run, so branches not taken aren't depended on.
+ */
+impl ReactiveRuntime {
+    // Effects run once after all the changes, rather than after each of them
+    pub fn batch<R>(changes: impl FnOnce() -> R) -> R {
+        BATCH_DEPTH.with(|depth| depth.set(depth.get() + 1));
+        let result = changes();
+        BATCH_DEPTH.with(|depth| depth.set(depth.get() - 1));
+        ReactiveRuntime::run_pending_effects();
+        result
+    }
+
+    // Whether any binding went stale since the last call, so the tree needs updating
+    pub fn take_has_stale_bindings() -> bool {
+        HAS_STALE_BINDINGS.with(|has_stale_bindings| has_stale_bindings.replace(false))
+    }
+
+    pub(super) fn run_pending_effects() {
+        if BATCH_DEPTH.with(|depth| depth.get()) > 0 {
+            return;
+        }
+        // Effects may set signals and make more effects pending; those run in the next round
+        BATCH_DEPTH.with(|depth| depth.set(1));
+        loop {
+            let effects = PENDING_EFFECTS.with(|effects| std::mem::take(&mut *effects.borrow_mut()));
+            if effects.is_empty() {
+                break;
+            }
+            for effect in effects.iter().filter_map(Weak::upgrade) {
+                effect.run_effect();
+            }
+        }
+        BATCH_DEPTH.with(|depth| depth.set(0));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::{Cell, RefCell}, rc::Rc};
+
+    use super::{super::{binding::Binding, derived::Derived, effect::Effect, signal::Signal}, ReactiveRuntime};
+
+    // Counts the runs of a computation
+    fn counter() -> (Rc<Cell<usize>>, Rc<Cell<usize>>) {
+        let count = Rc::new(Cell::new(0));
+        (count.clone(), count)
+    }
+
+    #[test]
+    fn effects_run_now_and_after_each_change_until_dropped() {
+        let signal = Signal::new(1);
+        let seen = Rc::new(RefCell::new(vec![]));
+        let (effect_signal, effect_seen) = (signal.clone(), seen.clone());
+        let effect = Effect::new(move || effect_seen.borrow_mut().push(effect_signal.get()));
+
+        signal.set(2);
+        signal.set(2); // The same value changes nothing
+        signal.set(3);
+        assert_eq!(*seen.borrow(), [1, 2, 3]);
+
+        drop(effect);
+        signal.set(4);
+        assert_eq!(*seen.borrow(), [1, 2, 3]);
+    }
+
+    #[test]
+    fn only_signals_read_by_the_last_run_are_depended_on() {
+        let (is_first, first, second) = (Signal::new(true), Signal::new("a"), Signal::new("b"));
+        let (runs, effect_runs) = counter();
+        let (effect_is_first, effect_first, effect_second) = (is_first.clone(), first.clone(), second.clone());
+        let _effect = Effect::new(move || {
+            effect_runs.set(effect_runs.get() + 1);
+            let _ = if effect_is_first.get() { effect_first.get() } else { effect_second.get() };
+        });
+
+        second.set("c"); // Not read yet
+        assert_eq!(runs.get(), 1);
+        is_first.set(false);
+        assert_eq!(runs.get(), 2);
+        first.set("d"); // Read only by the earlier run
+        assert_eq!(runs.get(), 2);
+        second.set("e");
+        assert_eq!(runs.get(), 3);
+    }
+
+    #[test]
+    fn batched_changes_run_effects_once() {
+        let (first, second) = (Signal::new(0), Signal::new(0));
+        let (runs, effect_runs) = counter();
+        let (effect_first, effect_second) = (first.clone(), second.clone());
+        let _effect = Effect::new(move || {
+            effect_runs.set(effect_runs.get() + 1);
+            let _ = effect_first.get() + effect_second.get();
+        });
+
+        ReactiveRuntime::batch(|| {
+            first.set(1);
+            second.set(1);
+            ReactiveRuntime::batch(|| first.set(2));
+            assert_eq!(runs.get(), 1);
+        });
+        assert_eq!(runs.get(), 2);
+    }
+
+    #[test]
+    fn derived_values_recompute_lazily_once_a_dependency_changed() {
+        let signal = Signal::new(2);
+        let (runs, compute_runs) = counter();
+        let compute_signal = signal.clone();
+        let doubled = Derived::new(move || {
+            compute_runs.set(compute_runs.get() + 1);
+            compute_signal.get() * 2
+        });
+        assert_eq!(runs.get(), 0);
+
+        assert_eq!((doubled.get(), doubled.get()), (4, 4));
+        assert_eq!(runs.get(), 1);
+
+        signal.set(5);
+        assert_eq!(runs.get(), 1);
+        assert_eq!(doubled.get(), 10);
+        assert_eq!(runs.get(), 2);
+    }
+
+    #[test]
+    fn effects_follow_signals_through_derived_values() {
+        let signal = Signal::new(1);
+        let compute_signal = signal.clone();
+        let doubled = Derived::new(move || compute_signal.get() * 2);
+        let seen = Rc::new(RefCell::new(vec![]));
+        let effect_seen = seen.clone();
+        let _effect = Effect::new(move || effect_seen.borrow_mut().push(doubled.get()));
+
+        signal.set(3);
+        assert_eq!(*seen.borrow(), [2, 6]);
+    }
+
+    #[test]
+    fn bindings_give_a_value_only_once_it_changed() {
+        ReactiveRuntime::take_has_stale_bindings();
+        let signal = Signal::new(1);
+        let compute_signal = signal.clone();
+        let binding = Binding::new(move || compute_signal.get() % 2);
+
+        assert_eq!(binding.take_changed(&0), Some(1)); // Stale until it first runs
+        assert_eq!(binding.take_changed(&1), None);
+        assert!(!ReactiveRuntime::take_has_stale_bindings());
+
+        signal.set(3); // The value it computes stays the same
+        assert!(ReactiveRuntime::take_has_stale_bindings());
+        assert_eq!(binding.take_changed(&1), None);
+
+        signal.set(4);
+        assert_eq!(binding.take_changed(&1), Some(0));
+        assert_eq!(binding.take_changed(&0), None);
+    }
+}
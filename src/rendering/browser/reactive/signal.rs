@@ -0,0 +1,53 @@
+use std::{cell::RefCell, rc::Rc};
+
+use super::runtime::{ReactiveRuntime, Subscribers};
+
+struct SignalNode<T> {
+    value: RefCell<T>,
+    subscribers: Subscribers,
+}
+
+// A value that derived values, effects and element bindings reading it follow; clones share it
+pub struct Signal<T> {
+    node: Rc<SignalNode<T>>,
+}
+
+impl<T> Clone for Signal<T> {
+    fn clone(&self) -> Self {
+        Self { node: self.node.clone() }
+    }
+}
+
+impl<T: Clone + PartialEq + 'static> Signal<T> {
+    pub fn new(value: T) -> Self {
+        Self { node: Rc::new(SignalNode { value: RefCell::new(value), subscribers: Subscribers::default() }) }
+    }
+
+    // The value, making whatever is being computed depend on it
+    pub fn get(&self) -> T {
+        self.node.subscribers.track();
+        self.node.value.borrow().clone()
+    }
+
+    #[allow(dead_code)]
+    pub fn get_untracked(&self) -> T {
+        self.node.value.borrow().clone()
+    }
+
+    // Dependents only hear of it if the value changed
+    pub fn set(&self, value: T) {
+        if *self.node.value.borrow() == value {
+            return;
+        }
+        *self.node.value.borrow_mut() = value;
+        self.node.subscribers.notify();
+        ReactiveRuntime::run_pending_effects();
+    }
+
+    #[allow(dead_code)]
+    pub fn update(&self, change: impl FnOnce(&mut T)) {
+        let mut value = self.get_untracked();
+        change(&mut value);
+        self.set(value);
+    }
+}
@@ -0,0 +1,39 @@
+// Actions a shortcut can trigger; named in keymap files by `get_name`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Command {
+    FocusAddressBar,
+    NewTab,
+    CloseTab,
+    NextTab,
+    PreviousTab,
+    Reload,
+    Quit,
+}
+
+impl Command {
+    pub const ALL: [Command; 7] = [
+        Command::FocusAddressBar,
+        Command::NewTab,
+        Command::CloseTab,
+        Command::NextTab,
+        Command::PreviousTab,
+        Command::Reload,
+        Command::Quit,
+    ];
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Command::FocusAddressBar => "focus-address-bar",
+            Command::NewTab => "new-tab",
+            Command::CloseTab => "close-tab",
+            Command::NextTab => "next-tab",
+            Command::PreviousTab => "previous-tab",
+            Command::Reload => "reload",
+            Command::Quit => "quit",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Command> {
+        Command::ALL.into_iter().find(|command| command.get_name() == name)
+    }
+}
@@ -0,0 +1,184 @@
+use std::fmt;
+
+use winit::keyboard::{Key, ModifiersState, NamedKey};
+
+use crate::rendering::browser::elements::element::KeyInput;
+
+// Modifier of the platform's standard shortcuts, written `primary` in keymaps: Cmd on macOS, Ctrl elsewhere
+pub const PRIMARY_MODIFIER: ModifiersState = if cfg!(target_os = "macos") { ModifiersState::SUPER } else { ModifiersState::CONTROL };
+
+const MODIFIER_NAMES: [(&str, ModifiersState); 7] = [
+    ("primary", PRIMARY_MODIFIER),
+    ("ctrl", ModifiersState::CONTROL),
+    ("control", ModifiersState::CONTROL),
+    ("alt", ModifiersState::ALT),
+    ("shift", ModifiersState::SHIFT),
+    ("super", ModifiersState::SUPER),
+    ("cmd", ModifiersState::SUPER),
+];
+
+const KEY_NAMES: [(&str, NamedKey); 27] = [
+    ("tab", NamedKey::Tab),
+    ("enter", NamedKey::Enter),
+    ("escape", NamedKey::Escape),
+    ("space", NamedKey::Space),
+    ("backspace", NamedKey::Backspace),
+    ("delete", NamedKey::Delete),
+    ("insert", NamedKey::Insert),
+    ("left", NamedKey::ArrowLeft),
+    ("right", NamedKey::ArrowRight),
+    ("up", NamedKey::ArrowUp),
+    ("down", NamedKey::ArrowDown),
+    ("home", NamedKey::Home),
+    ("end", NamedKey::End),
+    ("pageup", NamedKey::PageUp),
+    ("pagedown", NamedKey::PageDown),
+    ("f1", NamedKey::F1),
+    ("f2", NamedKey::F2),
+    ("f3", NamedKey::F3),
+    ("f4", NamedKey::F4),
+    ("f5", NamedKey::F5),
+    ("f6", NamedKey::F6),
+    ("f7", NamedKey::F7),
+    ("f8", NamedKey::F8),
+    ("f9", NamedKey::F9),
+    ("f10", NamedKey::F10),
+    ("f11", NamedKey::F11),
+    ("f12", NamedKey::F12),
+];
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ChordKey {
+    Character(String), // Lowercase, as the keyboard layout maps it
+    Named(NamedKey),
+}
+
+// One key pressed with modifiers held, e.g. `ctrl+shift+tab`
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct KeyChord {
+    pub modifiers: ModifiersState,
+    pub key: ChordKey,
+}
+
+impl KeyChord {
+    // `+` separated modifiers followed by a key name or character; `ctrl++` is Ctrl with the plus key
+    pub fn parse(text: &str) -> Result<KeyChord, String> {
+        let text = text.to_lowercase();
+        let mut rest = text.as_str();
+        let mut modifiers = ModifiersState::empty();
+
+        while let Some((name, modifier)) = MODIFIER_NAMES.iter().find(|(name, _)| rest.strip_prefix(name).is_some_and(|after| after.starts_with('+'))) {
+            modifiers |= *modifier;
+            rest = &rest[name.len() + 1..];
+        }
+
+        let key = match KEY_NAMES.iter().find(|(name, _)| *name == rest) {
+            Some((_, named_key)) => ChordKey::Named(*named_key),
+            None if rest.chars().count() == 1 => ChordKey::Character(rest.to_string()),
+            None if rest.is_empty() => return Err(format!("'{}' has no key", text)),
+            None => return Err(format!("unknown key '{}'", rest)),
+        };
+        Ok(KeyChord { modifiers, key })
+    }
+
+    // Whitespace separated chords pressed one after the other, e.g. `ctrl+k ctrl+t`
+    pub fn parse_sequence(text: &str) -> Result<Vec<KeyChord>, String> {
+        let chords = text.split_whitespace().map(KeyChord::parse).collect::<Result<Vec<_>, _>>()?;
+        if chords.is_empty() {
+            return Err(String::from("expected keys"));
+        }
+        Ok(chords)
+    }
+
+    // None for modifier keys on their own and keys a chord can't name
+    pub fn from_key_input(key_input: &KeyInput) -> Option<KeyChord> {
+        let key = match &key_input.logical_key {
+            Key::Character(character) => ChordKey::Character(character.to_lowercase()),
+            Key::Named(named_key) if KEY_NAMES.iter().any(|(_, known_key)| known_key == named_key) => ChordKey::Named(*named_key),
+            _ => return None,
+        };
+        Some(KeyChord { modifiers: key_input.modifiers, key })
+    }
+
+    /*
+     * Shift is only compared for letters and named keys: for other characters it's part of
+     * typing the character itself, e.g. `ctrl++` on a layout where `+` needs Shift
+     */
+    pub fn matches(&self, pressed: &KeyChord) -> bool {
+        let is_shift_significant = match &self.key {
+            ChordKey::Character(character) => character.chars().all(char::is_alphabetic),
+            ChordKey::Named(_) => true,
+        };
+        let mask = if is_shift_significant { ModifiersState::all() } else { !ModifiersState::SHIFT };
+        self.key == pressed.key && (self.modifiers & mask) == (pressed.modifiers & mask)
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        for (name, modifier) in [("ctrl", ModifiersState::CONTROL), ("alt", ModifiersState::ALT), ("shift", ModifiersState::SHIFT), ("super", ModifiersState::SUPER)] {
+            if self.modifiers.contains(modifier) {
+                write!(formatter, "{}+", name)?;
+            }
+        }
+        match &self.key {
+            ChordKey::Character(character) => write!(formatter, "{}", character),
+            ChordKey::Named(named_key) => {
+                let name = KEY_NAMES.iter().find(|(_, known_key)| known_key == named_key).map_or("?", |(name, _)| *name);
+                write!(formatter, "{}", name)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::keyboard::{NativeKeyCode, PhysicalKey};
+
+    use super::*;
+
+    fn chord(text: &str) -> KeyChord {
+        KeyChord::parse(text).unwrap()
+    }
+
+    fn press(logical_key: Key, modifiers: ModifiersState) -> KeyInput {
+        KeyInput { logical_key, physical_key: PhysicalKey::Unidentified(NativeKeyCode::Unidentified), is_repeat: false, modifiers }
+    }
+
+    #[test]
+    fn parses_modifiers_then_a_named_key() {
+        let parsed = chord("Ctrl+Shift+Tab");
+        assert_eq!(parsed.modifiers, ModifiersState::CONTROL | ModifiersState::SHIFT);
+        assert_eq!(parsed.key, ChordKey::Named(NamedKey::Tab));
+        assert_eq!(parsed.to_string(), "ctrl+shift+tab");
+    }
+
+    #[test]
+    fn the_plus_key_follows_the_last_plus() {
+        let parsed = chord("ctrl++");
+        assert_eq!(parsed.modifiers, ModifiersState::CONTROL);
+        assert_eq!(parsed.key, ChordKey::Character(String::from("+")));
+    }
+
+    #[test]
+    fn rejects_missing_and_unknown_keys() {
+        assert!(KeyChord::parse("ctrl+").is_err());
+        assert_eq!(KeyChord::parse("ctrl+escap").unwrap_err(), "unknown key 'escap'");
+        assert!(KeyChord::parse_sequence("  ").is_err());
+    }
+
+    #[test]
+    fn shift_only_counts_for_letters_and_named_keys() {
+        assert!(chord("ctrl++").matches(&chord("ctrl+shift++")));
+        assert!(!chord("ctrl+t").matches(&chord("ctrl+shift+t")));
+        assert!(!chord("ctrl+tab").matches(&chord("ctrl+shift+tab")));
+        assert!(!chord("ctrl++").matches(&chord("ctrl+alt++")));
+    }
+
+    #[test]
+    fn key_input_is_lowercased_and_modifier_keys_are_skipped() {
+        let pressed = KeyChord::from_key_input(&press(Key::Character("T".into()), ModifiersState::CONTROL | ModifiersState::SHIFT));
+        assert_eq!(pressed, Some(chord("ctrl+shift+t")));
+        assert_eq!(KeyChord::from_key_input(&press(Key::Named(NamedKey::Shift), ModifiersState::SHIFT)), None);
+    }
+}
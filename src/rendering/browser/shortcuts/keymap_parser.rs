@@ -0,0 +1,124 @@
+use std::{fmt, fs, path::Path};
+
+use crate::rendering::browser::styling::stylesheet_parser::StylesheetParser;
+
+use super::{command::Command, key_chord::KeyChord, shortcut_registry::ShortcutScope};
+
+// One line of a keymap; a command of None removes the binding instead
+pub struct KeymapEntry {
+    pub keys: Vec<KeyChord>,
+    pub command: Option<Command>,
+    pub scope: ShortcutScope,
+    pub line: usize,
+}
+
+#[derive(Debug)]
+pub struct KeymapError {
+    pub line: usize, // 1-based; 0 when the error isn't tied to a line
+    pub message: String,
+}
+
+impl KeymapError {
+    pub fn new(line: usize, message: &str) -> Self {
+        Self { line, message: message.to_string() }
+    }
+}
+
+impl fmt::Display for KeymapError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        if self.line == 0 {
+            return write!(formatter, "{}", self.message);
+        }
+        write!(formatter, "{}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for KeymapError {}
+
+pub struct KeymapParser {
+
+}
+
+/*
+ * Parses keymap files, one binding per line:
+ *   A. `keys = command`, where keys are chords separated by whitespace, pressed one after the
+ *      other, e.g. `ctrl+k ctrl+t = new-tab`
+ *   B. `keys = command when selector` only applies while the focused element matches one of the
+ *      stylesheet selectors, e.g. `ctrl+w = close-tab when tab-strip`
+ *   C. `keys = none` removes the binding of the keys, e.g. one of the defaults
+ * Blank lines and lines starting with `//` are skipped.
+ */
+impl KeymapParser {
+    pub fn parse(source: &str) -> Result<Vec<KeymapEntry>, KeymapError> {
+        source.lines().enumerate()
+            .map(|(index, text)| (index + 1, text.trim()))
+            .filter(|(_, text)| !text.is_empty() && !text.starts_with("//"))
+            .map(|(line, text)| KeymapParser::parse_line(line, text))
+            .collect()
+    }
+
+    pub fn load(path: &Path) -> Result<Vec<KeymapEntry>, KeymapError> {
+        let source = fs::read_to_string(path)
+            .map_err(|error| KeymapError::new(0, &format!("cannot read {}: {}", path.display(), error)))?;
+        KeymapParser::parse(&source)
+    }
+
+    fn parse_line(line: usize, text: &str) -> Result<KeymapEntry, KeymapError> {
+        // The last `=`, as keys like `ctrl+=` contain one too
+        let Some((keys, binding)) = text.rsplit_once('=') else {
+            return Err(KeymapError::new(line, "expected 'keys = command'"));
+        };
+        let keys = KeyChord::parse_sequence(keys).map_err(|message| KeymapError::new(line, &message))?;
+
+        let (command_name, scope) = match binding.split_once(" when ") {
+            Some((command_name, selectors)) => {
+                let selectors = StylesheetParser::parse_selectors(selectors)
+                    .map_err(|error| KeymapError::new(line, &format!("invalid scope: {}", error.message)))?;
+                (command_name.trim(), ShortcutScope::Focused(selectors))
+            }
+            None => (binding.trim(), ShortcutScope::Global),
+        };
+
+        let command = match command_name {
+            "none" => None,
+            _ => Some(Command::from_name(command_name).ok_or_else(|| KeymapError::new(line, &format!("unknown command '{}'", command_name)))?),
+        };
+        Ok(KeymapEntry { keys, command, scope, line })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_comments_and_blank_lines_and_counts_them() {
+        let entries = KeymapParser::parse("// Tabs\n\nctrl+k ctrl+t = new-tab\n").unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].line, 3);
+        assert_eq!(entries[0].keys, KeyChord::parse_sequence("ctrl+k ctrl+t").unwrap());
+        assert_eq!(entries[0].command, Some(Command::NewTab));
+        assert_eq!(entries[0].scope, ShortcutScope::Global);
+    }
+
+    #[test]
+    fn none_unbinds_the_keys() {
+        let entries = KeymapParser::parse("ctrl+w = none when tab-strip").unwrap();
+        assert_eq!(entries[0].command, None);
+        assert!(matches!(entries[0].scope, ShortcutScope::Focused(ref selectors) if selectors.len() == 1));
+    }
+
+    #[test]
+    fn splits_on_the_last_equals_sign() {
+        let entries = KeymapParser::parse("ctrl+= = reload").unwrap();
+        assert_eq!(entries[0].keys, vec![KeyChord::parse("ctrl+=").unwrap()]);
+        assert_eq!(entries[0].command, Some(Command::Reload));
+    }
+
+    #[test]
+    fn errors_give_the_line() {
+        let error = KeymapParser::parse("f5 = reload\nctrl+t = open-everything").err().unwrap();
+        assert_eq!(error.to_string(), "2: unknown command 'open-everything'");
+        assert_eq!(KeymapParser::parse("ctrl+t new-tab").err().unwrap().line, 1);
+    }
+}
@@ -0,0 +1,4 @@
+pub mod command;
+pub mod key_chord;
+pub mod keymap_parser;
+pub mod shortcut_registry;
\ No newline at end of file
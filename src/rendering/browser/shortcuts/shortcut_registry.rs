@@ -0,0 +1,294 @@
+use std::{cmp::Reverse, fmt, time::{Duration, Instant}};
+
+use crate::rendering::browser::elements::element::KeyInput;
+use crate::rendering::browser::styling::stylesheet::{Selector, SelectorSubject};
+
+use super::{command::Command, key_chord::KeyChord, keymap_parser::{KeymapEntry, KeymapParser}};
+
+const SEQUENCE_TIMEOUT: Duration = Duration::from_millis(1500); // Between two chords of a sequence
+
+// Bindings every keymap starts from, in the keymap file format; `primary` is Cmd on macOS, Ctrl elsewhere
+const DEFAULT_KEYMAP: &str = "
+    primary+l = focus-address-bar
+    primary+t = new-tab
+    primary+w = close-tab
+    ctrl+tab = next-tab
+    ctrl+shift+tab = previous-tab
+    primary+r = reload
+    f5 = reload
+    primary+q = quit
+";
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum ShortcutScope {
+    Global,
+    Focused(Vec<Selector>), // While the focused element matches one of the selectors
+}
+
+impl ShortcutScope {
+    // Higher for more specific scopes; None when the scope doesn't apply to the focused element
+    fn get_precedence(&self, focused: &[SelectorSubject]) -> Option<(bool, (usize, usize, usize))> {
+        match self {
+            ShortcutScope::Global => Some((false, (0, 0, 0))),
+            ShortcutScope::Focused(selectors) => {
+                let (subject, ancestors) = focused.split_last()?;
+                selectors.iter()
+                    .filter(|selector| selector.matches(subject, ancestors))
+                    .map(|selector| selector.specificity())
+                    .max()
+                    .map(|specificity| (true, specificity))
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Binding {
+    pub keys: Vec<KeyChord>,
+    pub command: Command,
+    pub scope: ShortcutScope,
+    pub line: Option<usize>, // Where a keymap file bound it; None for the defaults
+}
+
+impl Binding {
+    // Whether the keys pressed so far are the binding's keys or the start of them
+    fn starts_with(&self, pressed: &[KeyChord]) -> bool {
+        self.keys.len() >= pressed.len() && self.keys.iter().zip(pressed).all(|(bound, pressed)| bound.matches(pressed))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ShortcutMatch {
+    None,
+    Pending, // The keys so far start a sequence; the next key completes or breaks it
+    Command(Command),
+}
+
+pub enum ShortcutConflict {
+    // A keymap bound the same keys twice in one scope; the later binding wins
+    Rebound { keys: Vec<KeyChord>, line: usize, previous_line: usize },
+    // A binding's keys start a longer sequence in the same scope, which can then never be completed
+    Unreachable { keys: Vec<KeyChord>, command: Command, prefix: Vec<KeyChord>, prefix_command: Command },
+}
+
+impl fmt::Display for ShortcutConflict {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ShortcutConflict::Rebound { keys, line, previous_line } => {
+                write!(formatter, "{}: {} was already bound on line {}", line, format_keys(keys), previous_line)
+            }
+            ShortcutConflict::Unreachable { keys, command, prefix, prefix_command } => write!(
+                formatter,
+                "{} ({}) can't be pressed, since {} runs {} first",
+                format_keys(keys), command.get_name(), format_keys(prefix), prefix_command.get_name()
+            ),
+        }
+    }
+}
+
+fn format_keys(keys: &[KeyChord]) -> String {
+    keys.iter().map(|chord| chord.to_string()).collect::<Vec<_>>().join(" ")
+}
+
+pub struct ShortcutRegistry {
+    bindings: Vec<Binding>,
+    pending: Vec<KeyChord>,
+    pending_since: Option<Instant>,
+}
+
+/*
+ * Maps key presses to commands:
+ *   A. Each key down extends the sequence pressed so far. A binding whose keys are the whole
+ *      sequence runs its command; if bindings only start with it, the registry waits for the
+ *      next key. A sequence no binding starts with is dropped, and the last key tried on its own
+ *   B. Bindings scoped to the focused element take precedence over global ones, and among them
+ *      those with the more specific selector, so a focused element can reuse global keys
+ *   C. Keymap files are applied over the defaults: they replace the bindings of the same keys
+ *      in the same scope, and `none` removes them
+ */
+impl ShortcutRegistry {
+    pub fn new() -> Self {
+        Self { bindings: vec![], pending: vec![], pending_since: None }
+    }
+
+    pub fn with_defaults() -> Self {
+        let mut registry = ShortcutRegistry::new();
+        let defaults = KeymapParser::parse(DEFAULT_KEYMAP).expect("the default keymap is valid");
+        for entry in defaults {
+            if let Some(command) = entry.command {
+                registry.bind(entry.keys, command, entry.scope, None);
+            }
+        }
+        registry
+    }
+
+    // Replaces any binding of the same keys in the same scope, which is returned
+    pub fn bind(&mut self, keys: Vec<KeyChord>, command: Command, scope: ShortcutScope, line: Option<usize>) -> Option<Binding> {
+        let previous = self.unbind(&keys, &scope);
+        self.bindings.push(Binding { keys, command, scope, line });
+        previous
+    }
+
+    pub fn unbind(&mut self, keys: &[KeyChord], scope: &ShortcutScope) -> Option<Binding> {
+        let index = self.bindings.iter().position(|binding| binding.keys == keys && binding.scope == *scope)?;
+        Some(self.bindings.remove(index))
+    }
+
+    // Applies a keymap file's entries in order; returns the conflicts left in the bindings
+    pub fn apply_keymap(&mut self, entries: Vec<KeymapEntry>) -> Vec<ShortcutConflict> {
+        let mut conflicts = vec![];
+        for entry in entries {
+            let previous = match entry.command {
+                Some(command) => self.bind(entry.keys.clone(), command, entry.scope, Some(entry.line)),
+                None => self.unbind(&entry.keys, &entry.scope),
+            };
+            if let Some(previous_line) = previous.and_then(|previous| previous.line) {
+                conflicts.push(ShortcutConflict::Rebound { keys: entry.keys, line: entry.line, previous_line });
+            }
+        }
+        conflicts.extend(self.find_conflicts());
+        conflicts
+    }
+
+    pub fn find_conflicts(&self) -> Vec<ShortcutConflict> {
+        let mut conflicts = vec![];
+        for binding in &self.bindings {
+            let shorter_bindings = self.bindings.iter()
+                .filter(|other| other.scope == binding.scope && other.keys.len() < binding.keys.len());
+            for prefix in shorter_bindings.filter(|prefix| binding.keys.starts_with(&prefix.keys)) {
+                conflicts.push(ShortcutConflict::Unreachable {
+                    keys: binding.keys.clone(),
+                    command: binding.command,
+                    prefix: prefix.keys.clone(),
+                    prefix_command: prefix.command,
+                });
+            }
+        }
+        conflicts
+    }
+
+    // `focused` runs from the root down to the focused element; empty when nothing is focused
+    pub fn handle_key(&mut self, key_input: &KeyInput, focused: &[SelectorSubject], now: Instant) -> ShortcutMatch {
+        let Some(chord) = KeyChord::from_key_input(key_input) else {
+            return ShortcutMatch::None; // Pressing a modifier doesn't break a sequence
+        };
+
+        let is_expired = self.pending_since.is_some_and(|since| now.duration_since(since) > SEQUENCE_TIMEOUT);
+        let mut pressed = std::mem::take(&mut self.pending);
+        if is_expired {
+            pressed.clear();
+        }
+        pressed.push(chord.clone());
+
+        let mut shortcut_match = self.find_match(&pressed, focused);
+        if shortcut_match == ShortcutMatch::None && pressed.len() > 1 {
+            pressed = vec![chord];
+            shortcut_match = self.find_match(&pressed, focused);
+        }
+
+        if shortcut_match == ShortcutMatch::Pending {
+            self.pending = pressed;
+            self.pending_since = Some(now);
+        }
+        shortcut_match
+    }
+
+    fn find_match(&self, pressed: &[KeyChord], focused: &[SelectorSubject]) -> ShortcutMatch {
+        // The binding in the most specific scope wins, and within a scope a complete one
+        let best_candidate = self.bindings.iter()
+            .filter(|binding| binding.starts_with(pressed))
+            .filter_map(|binding| {
+                let precedence = binding.scope.get_precedence(focused)?;
+                Some((precedence, binding.keys.len() == pressed.len(), binding))
+            })
+            .min_by_key(|(precedence, is_complete, _)| Reverse((*precedence, *is_complete)));
+
+        match best_candidate {
+            Some((_, true, binding)) => ShortcutMatch::Command(binding.command),
+            Some((_, false, _)) => ShortcutMatch::Pending,
+            None => ShortcutMatch::None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::keyboard::{Key, ModifiersState, NativeKeyCode, PhysicalKey};
+
+    use super::{super::key_chord::PRIMARY_MODIFIER, *};
+
+    fn registry(keymap: &str) -> ShortcutRegistry {
+        let mut registry = ShortcutRegistry::new();
+        registry.apply_keymap(KeymapParser::parse(keymap).unwrap());
+        registry
+    }
+
+    fn press(registry: &mut ShortcutRegistry, character: &str, focused: &[SelectorSubject], now: Instant) -> ShortcutMatch {
+        let key_input = KeyInput {
+            logical_key: Key::Character(character.into()),
+            physical_key: PhysicalKey::Unidentified(NativeKeyCode::Unidentified),
+            is_repeat: false,
+            modifiers: ModifiersState::CONTROL,
+        };
+        registry.handle_key(&key_input, focused, now)
+    }
+
+    fn subject(type_name: &'static str, id: &str) -> SelectorSubject {
+        SelectorSubject { type_name, id: Some(id.to_string()), class_names: vec![] }
+    }
+
+    #[test]
+    fn a_sequence_waits_for_its_next_chord() {
+        let mut registry = registry("ctrl+k ctrl+t = new-tab");
+        let now = Instant::now();
+        assert_eq!(press(&mut registry, "k", &[], now), ShortcutMatch::Pending);
+        assert_eq!(press(&mut registry, "t", &[], now + Duration::from_millis(500)), ShortcutMatch::Command(Command::NewTab));
+    }
+
+    #[test]
+    fn a_timed_out_or_broken_sequence_falls_back_to_the_last_chord() {
+        let mut registry = registry("ctrl+k ctrl+t = new-tab\nctrl+t = reload\nctrl+q = quit");
+        let now = Instant::now();
+        assert_eq!(press(&mut registry, "k", &[], now), ShortcutMatch::Pending);
+        assert_eq!(press(&mut registry, "t", &[], now + SEQUENCE_TIMEOUT * 2), ShortcutMatch::Command(Command::Reload));
+
+        assert_eq!(press(&mut registry, "k", &[], now), ShortcutMatch::Pending);
+        assert_eq!(press(&mut registry, "q", &[], now), ShortcutMatch::Command(Command::Quit));
+        assert_eq!(press(&mut registry, "x", &[], now), ShortcutMatch::None);
+    }
+
+    #[test]
+    fn scoped_bindings_win_over_global_ones_while_focused() {
+        let mut registry = registry("ctrl+w = close-tab\nctrl+w = quit when row\nctrl+w = reload when #address-bar");
+        let focused = [subject("row", "chrome"), subject("text-input", "address-bar")];
+        let now = Instant::now();
+        assert_eq!(press(&mut registry, "w", &focused, now), ShortcutMatch::Command(Command::Reload));
+        assert_eq!(press(&mut registry, "w", &focused[..1], now), ShortcutMatch::Command(Command::Quit));
+        assert_eq!(press(&mut registry, "w", &[], now), ShortcutMatch::Command(Command::CloseTab));
+    }
+
+    #[test]
+    fn finds_rebound_and_unreachable_keys() {
+        let mut registry = ShortcutRegistry::with_defaults();
+        let conflicts = registry.apply_keymap(KeymapParser::parse("ctrl+j = reload\nctrl+j = quit\nctrl+j ctrl+t = new-tab").unwrap());
+        assert_eq!(conflicts.len(), 2);
+        assert!(matches!(&conflicts[0], ShortcutConflict::Rebound { line: 2, previous_line: 1, .. }));
+        assert!(matches!(&conflicts[1], ShortcutConflict::Unreachable { command: Command::NewTab, prefix_command: Command::Quit, .. }));
+    }
+
+    #[test]
+    fn rebinding_a_default_is_no_conflict_and_none_removes_it() {
+        let mut registry = ShortcutRegistry::with_defaults();
+        assert!(registry.apply_keymap(KeymapParser::parse("f5 = quit\nprimary+t = none").unwrap()).is_empty());
+        assert!(!registry.bindings.iter().any(|binding| binding.command == Command::NewTab));
+        assert!(registry.bindings.iter().any(|binding| binding.command == Command::Quit && binding.line == Some(1)));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn the_defaults_use_ctrl_on_linux() {
+        assert_eq!(PRIMARY_MODIFIER, ModifiersState::CONTROL);
+        let mut registry = ShortcutRegistry::with_defaults();
+        assert_eq!(press(&mut registry, "t", &[], Instant::now()), ShortcutMatch::Command(Command::NewTab));
+    }
+}
@@ -0,0 +1,35 @@
+use crate::rendering::browser::reactive::{runtime::ReactiveRuntime, signal::Signal};
+
+use super::app_state::AppState;
+
+// Parts of the application state as signals, for element bindings and effects; Renderer sets them after each change
+#[derive(Clone)]
+pub struct AppSignals {
+    pub active_url: Signal<Option<String>>,
+    pub is_loading: Signal<bool>,
+    pub search_engine: Signal<String>,
+    pub bookmarks: Signal<Vec<String>>,
+}
+
+impl AppSignals {
+    pub fn new(state: &AppState) -> Self {
+        let active_tab = state.get_active_tab();
+        Self {
+            active_url: Signal::new(active_tab.and_then(|tab| tab.url.clone())),
+            is_loading: Signal::new(active_tab.is_some_and(|tab| tab.is_loading)),
+            search_engine: Signal::new(state.search_engine.clone()),
+            bookmarks: Signal::new(state.bookmarks.clone()),
+        }
+    }
+
+    // Signals whose value is the same stay quiet, so only what depends on the parts that changed updates
+    pub fn update(&self, state: &AppState) {
+        let active_tab = state.get_active_tab();
+        ReactiveRuntime::batch(|| {
+            self.active_url.set(active_tab.and_then(|tab| tab.url.clone()));
+            self.is_loading.set(active_tab.is_some_and(|tab| tab.is_loading));
+            self.search_engine.set(state.search_engine.clone());
+            self.bookmarks.set(state.bookmarks.clone());
+        });
+    }
+}
@@ -0,0 +1,84 @@
+pub const NEW_TAB_TITLE: &str = "New Tab";
+
+#[derive(Clone, Debug)]
+pub struct TabState {
+    pub title: String,
+    pub url: Option<String>,
+    pub is_loading: bool,
+    back_stack: Vec<String>,    // URLs visited before the current one, most recent last
+    forward_stack: Vec<String>, // URLs gone back from, most recent last
+}
+
+impl TabState {
+    pub fn new(title: &str, url: Option<&str>) -> Self {
+        Self { title: title.to_string(), url: url.map(str::to_string), is_loading: false, back_stack: vec![], forward_stack: vec![] }
+    }
+
+    pub fn can_go_back(&self) -> bool {
+        !self.back_stack.is_empty()
+    }
+
+    pub fn can_go_forward(&self) -> bool {
+        !self.forward_stack.is_empty()
+    }
+
+    // Loads a new URL; the current one can be gone back to, and nothing forward is left
+    pub fn visit(&mut self, url: &str) {
+        if let Some(previous_url) = self.url.replace(url.to_string()) {
+            self.back_stack.push(previous_url);
+        }
+        self.forward_stack.clear();
+        self.start_loading();
+    }
+
+    // Returns the URL gone back to, if there was one
+    pub fn go_back(&mut self) -> Option<String> {
+        let url = self.back_stack.pop()?;
+        if let Some(current_url) = self.url.replace(url.clone()) {
+            self.forward_stack.push(current_url);
+        }
+        self.start_loading();
+        Some(url)
+    }
+
+    pub fn go_forward(&mut self) -> Option<String> {
+        let url = self.forward_stack.pop()?;
+        if let Some(current_url) = self.url.replace(url.clone()) {
+            self.back_stack.push(current_url);
+        }
+        self.start_loading();
+        Some(url)
+    }
+
+    // Until the page reports its title, the tab shows the URL
+    pub fn start_loading(&mut self) {
+        self.is_loading = true;
+        if let Some(url) = &self.url {
+            self.title = url.clone();
+        }
+    }
+}
+
+// Everything about the browser that outlives a single element: its tabs, their pages and the user's settings
+#[derive(Clone, Debug)]
+pub struct AppState {
+    pub tabs: Vec<TabState>,
+    pub active_index: Option<usize>,
+    pub search_engine: String,
+    pub bookmarks: Vec<String>,
+}
+
+impl AppState {
+    pub fn new(tabs: Vec<TabState>, search_engine: &str) -> Self {
+        let active_index = if tabs.is_empty() { None } else { Some(0) };
+        Self { tabs, active_index, search_engine: search_engine.to_string(), bookmarks: vec![] }
+    }
+
+    pub fn get_active_tab(&self) -> Option<&TabState> {
+        self.tabs.get(self.active_index?)
+    }
+
+    pub fn get_active_tab_mut(&mut self) -> Option<&mut TabState> {
+        self.tabs.get_mut(self.active_index?)
+    }
+}
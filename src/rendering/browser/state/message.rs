@@ -0,0 +1,26 @@
+// What element callbacks ask of the application; see Store::update
+#[derive(Clone, PartialEq, Debug)]
+pub enum Message {
+    Navigate(String), // A URL, or text to search for, to load in the active tab
+    GoBack,
+    GoForward,
+    Reload,
+    PageLoaded { title: String }, // The page of the active tab finished loading
+    OpenTab { url: Option<String> },
+    SelectTab(usize),
+    CloseTab(usize),
+    DuplicateTab(usize),
+    CloseOtherTabs(usize),
+    MoveTab { from: usize, to: usize },
+    SetSearchEngine(String),
+    AddBookmark(String),
+    Quit,
+}
+
+// What has to happen beyond the state changing, carried out by Renderer
+#[derive(Clone, PartialEq, Debug)]
+pub enum Effect {
+    LoadPage(String),
+    ReloadPage,
+    Quit,
+}
@@ -0,0 +1,26 @@
+use std::cell::RefCell;
+
+use super::message::Message;
+
+thread_local! {
+    static PENDING_MESSAGES: RefCell<Vec<Message>> = RefCell::new(vec![]);
+}
+
+pub struct MessageQueue {
+
+}
+
+/*
+ * Where element callbacks send their messages, so that they don't need a handle on the
+ * application. Nothing happens on sending; Renderer takes the messages after each event.
+ */
+impl MessageQueue {
+    pub fn send(message: Message) {
+        PENDING_MESSAGES.with(|messages| messages.borrow_mut().push(message));
+    }
+
+    // The messages sent since the last call, oldest first
+    pub fn take() -> Vec<Message> {
+        PENDING_MESSAGES.with(|messages| std::mem::take(&mut *messages.borrow_mut()))
+    }
+}
@@ -0,0 +1,5 @@
+pub mod app_signals;
+pub mod app_state;
+pub mod message;
+pub mod message_queue;
+pub mod store;
\ No newline at end of file
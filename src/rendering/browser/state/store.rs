@@ -0,0 +1,287 @@
+use super::{app_state::{AppState, TabState, NEW_TAB_TITLE}, message::{Effect, Message}};
+
+pub struct Store {
+    state: AppState,
+}
+
+/*
+ * Owns the application state, and is the only place it changes:
+ *   A. Element callbacks send messages to the MessageQueue rather than acting themselves
+ *   B. After each event, Renderer takes the queued messages and passes them to `update` in
+ *      order. It changes the state and returns what else has to happen, like loading a page
+ *   C. Elements bound to the state then follow it (see Element::on_state_changed), restyling
+ *      or asking for a new layout only where what they show changed
+ */
+impl Store {
+    pub fn new(state: AppState) -> Self {
+        Self { state }
+    }
+
+    pub fn get_state(&self) -> &AppState {
+        &self.state
+    }
+
+    pub fn update(&mut self, message: Message) -> Option<Effect> {
+        match message {
+            Message::Navigate(text) => {
+                let url = Store::get_url(&text, &self.state.search_engine);
+                match self.state.get_active_tab_mut() {
+                    Some(tab) => tab.visit(&url),
+                    None => self.open_tab(Some(&url)),
+                }
+                Some(Effect::LoadPage(url))
+            }
+            Message::GoBack => self.state.get_active_tab_mut()?.go_back().map(Effect::LoadPage),
+            Message::GoForward => self.state.get_active_tab_mut()?.go_forward().map(Effect::LoadPage),
+            Message::Reload => {
+                let tab = self.state.get_active_tab_mut().filter(|tab| tab.url.is_some())?;
+                tab.start_loading();
+                Some(Effect::ReloadPage)
+            }
+            Message::PageLoaded { title } => {
+                let tab = self.state.get_active_tab_mut()?;
+                tab.is_loading = false;
+                tab.title = title;
+                None
+            }
+            Message::OpenTab { url } => {
+                self.open_tab(url.as_deref());
+                url.map(Effect::LoadPage)
+            }
+            Message::SelectTab(index) if index < self.state.tabs.len() => {
+                self.state.active_index = Some(index);
+                self.state.tabs[index].url.clone().map(Effect::LoadPage)
+            }
+            Message::CloseTab(index) if index < self.state.tabs.len() => {
+                self.state.tabs.remove(index);
+                let tab_count = self.state.tabs.len();
+                self.state.active_index = match self.state.active_index {
+                    _ if tab_count == 0 => None,
+                    Some(active) if active > index => Some(active - 1),
+                    Some(active) => Some(active.min(tab_count - 1)),
+                    None => None,
+                };
+                None
+            }
+            // The copy keeps the history, opens next to the original and loads its page again
+            Message::DuplicateTab(index) if index < self.state.tabs.len() => {
+                let mut tab = self.state.tabs[index].clone();
+                let url = tab.url.clone();
+                if url.is_some() {
+                    tab.start_loading();
+                }
+                self.state.tabs.insert(index + 1, tab);
+                self.state.active_index = Some(index + 1);
+                url.map(Effect::LoadPage)
+            }
+            Message::CloseOtherTabs(index) if index < self.state.tabs.len() => {
+                let was_active = self.state.active_index == Some(index);
+                let tab = self.state.tabs.swap_remove(index);
+                let url = tab.url.clone();
+                self.state.tabs = vec![tab];
+                self.state.active_index = Some(0);
+                // Its page is only shown if it wasn't already
+                if was_active { None } else { url.map(Effect::LoadPage) }
+            }
+            Message::MoveTab { from, to } if from < self.state.tabs.len() && to < self.state.tabs.len() => {
+                let tab = self.state.tabs.remove(from);
+                self.state.tabs.insert(to, tab);
+                self.state.active_index = self.state.active_index.map(|active| match active {
+                    _ if active == from => to,
+                    _ if from < active && active <= to => active - 1,
+                    _ if to <= active && active < from => active + 1,
+                    _ => active,
+                });
+                None
+            }
+            Message::SetSearchEngine(search_engine) => {
+                self.state.search_engine = search_engine;
+                None
+            }
+            Message::AddBookmark(url) => {
+                if !self.state.bookmarks.contains(&url) {
+                    self.state.bookmarks.push(url);
+                }
+                None
+            }
+            Message::Quit => Some(Effect::Quit),
+            // Tabs already gone, e.g. closed twice before the state caught up
+            Message::SelectTab(_) | Message::CloseTab(_) | Message::DuplicateTab(_) | Message::CloseOtherTabs(_) | Message::MoveTab { .. } => None,
+        }
+    }
+
+    fn open_tab(&mut self, url: Option<&str>) {
+        let mut tab = TabState::new(NEW_TAB_TITLE, None);
+        if let Some(url) = url {
+            tab.visit(url);
+        }
+        self.state.tabs.push(tab);
+        self.state.active_index = Some(self.state.tabs.len() - 1);
+    }
+
+    // Text that isn't a URL, or a host to open over HTTPS, is searched for with the search engine
+    fn get_url(text: &str, search_engine: &str) -> String {
+        let text = text.trim();
+        if text.contains("://") {
+            return text.to_string();
+        }
+        if Store::is_host(text) {
+            return format!("https://{}", text);
+        }
+        let search_url = match search_engine {
+            "Google" => "https://www.google.com/search?q=",
+            "Bing" => "https://www.bing.com/search?q=",
+            "Startpage" => "https://www.startpage.com/search?q=",
+            _ => "https://duckduckgo.com/?q=",
+        };
+        format!("{}{}", search_url, Store::encode_query(text))
+    }
+
+    // Whether text, up to any port, path, query or fragment, is a host name such as `example.com`,
+    // `localhost` or an IPv4 address
+    fn is_host(text: &str) -> bool {
+        if text.is_empty() || text.contains(char::is_whitespace) {
+            return false;
+        }
+        let host = text.split(['/', '?', '#']).next().unwrap_or_default();
+        let host = host.split(':').next().unwrap_or_default();
+        let labels: Vec<&str> = host.split('.').collect();
+        let is_valid_label = |label: &str| !label.is_empty() && label.chars().all(|c| c.is_alphanumeric() || c == '-');
+
+        if host.eq_ignore_ascii_case("localhost") {
+            return true;
+        }
+        if labels.len() < 2 || !labels.iter().all(|label| is_valid_label(label)) {
+            return false;
+        }
+        let is_ipv4 = labels.len() == 4 && labels.iter().all(|label| label.parse::<u8>().is_ok());
+        let top_level = labels[labels.len() - 1];
+        is_ipv4 || (top_level.len() >= 2 && top_level.chars().all(char::is_alphabetic))
+    }
+
+    // Encodes text as a form value: spaces become `+`, and bytes other than unreserved ones `%XX`
+    fn encode_query(text: &str) -> String {
+        text.bytes().map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (byte as char).to_string(),
+            b' ' => String::from("+"),
+            _ => format!("%{:02X}", byte),
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store_with_tabs(titles: &[&str], active_index: usize) -> Store {
+        let mut state = AppState::new(titles.iter().map(|title| TabState::new(title, None)).collect(), "DuckDuckGo");
+        state.active_index = Some(active_index);
+        Store::new(state)
+    }
+
+    fn tab_titles(store: &Store) -> Vec<&str> {
+        store.get_state().tabs.iter().map(|tab| tab.title.as_str()).collect()
+    }
+
+    #[test]
+    fn closing_a_tab_before_the_active_one_keeps_it_active() {
+        let mut store = store_with_tabs(&["A", "B", "C"], 2);
+        store.update(Message::CloseTab(0));
+        assert_eq!(tab_titles(&store), ["B", "C"]);
+        assert_eq!(store.get_state().active_index, Some(1));
+    }
+
+    #[test]
+    fn closing_the_active_tab_activates_the_next_or_the_last() {
+        let mut store = store_with_tabs(&["A", "B", "C"], 1);
+        store.update(Message::CloseTab(1));
+        assert_eq!(store.get_state().active_index, Some(1));
+        assert_eq!(store.get_state().get_active_tab().map(|tab| tab.title.as_str()), Some("C"));
+
+        store.update(Message::CloseTab(1));
+        assert_eq!(store.get_state().active_index, Some(0));
+    }
+
+    #[test]
+    fn closing_after_the_active_tab_or_a_missing_one_changes_nothing_else() {
+        let mut store = store_with_tabs(&["A", "B", "C"], 0);
+        store.update(Message::CloseTab(2));
+        store.update(Message::CloseTab(5));
+        assert_eq!(tab_titles(&store), ["A", "B"]);
+        assert_eq!(store.get_state().active_index, Some(0));
+    }
+
+    #[test]
+    fn closing_the_last_tab_leaves_none_active() {
+        let mut store = store_with_tabs(&["A"], 0);
+        store.update(Message::CloseTab(0));
+        assert!(store.get_state().tabs.is_empty());
+        assert_eq!(store.get_state().active_index, None);
+    }
+
+    #[test]
+    fn moving_the_active_tab_keeps_it_active() {
+        let mut store = store_with_tabs(&["A", "B", "C", "D"], 1);
+        store.update(Message::MoveTab { from: 1, to: 3 });
+        assert_eq!(tab_titles(&store), ["A", "C", "D", "B"]);
+        assert_eq!(store.get_state().active_index, Some(3));
+    }
+
+    #[test]
+    fn moving_a_tab_across_the_active_one_shifts_it() {
+        let mut store = store_with_tabs(&["A", "B", "C", "D"], 2);
+        store.update(Message::MoveTab { from: 0, to: 3 });
+        assert_eq!(store.get_state().active_index, Some(1));
+        store.update(Message::MoveTab { from: 3, to: 0 });
+        assert_eq!(store.get_state().active_index, Some(2));
+        assert_eq!(tab_titles(&store), ["A", "B", "C", "D"]);
+
+        // Tabs moved on one side of it leave it where it is
+        store.update(Message::MoveTab { from: 0, to: 1 });
+        assert_eq!(store.get_state().active_index, Some(2));
+    }
+
+    #[test]
+    fn duplicating_a_tab_opens_and_loads_the_copy_next_to_it() {
+        let mut store = store_with_tabs(&["A", "B"], 0);
+        store.update(Message::SelectTab(1));
+        store.update(Message::Navigate(String::from("https://example.com/")));
+
+        let effect = store.update(Message::DuplicateTab(1));
+        assert_eq!(effect, Some(Effect::LoadPage(String::from("https://example.com/"))));
+        assert_eq!(store.get_state().tabs.len(), 3);
+        assert_eq!(store.get_state().active_index, Some(2));
+        assert_eq!(store.get_state().tabs[2].url.as_deref(), Some("https://example.com/"));
+    }
+
+    #[test]
+    fn closing_other_tabs_keeps_only_that_one_active() {
+        let mut store = store_with_tabs(&["A", "B", "C"], 0);
+        assert_eq!(store.update(Message::CloseOtherTabs(1)), None);
+        assert_eq!(tab_titles(&store), ["B"]);
+        assert_eq!(store.get_state().active_index, Some(0));
+    }
+
+    #[test]
+    fn urls_are_kept() {
+        assert_eq!(Store::get_url("  https://example.com/a?b=c  ", "Google"), "https://example.com/a?b=c");
+        assert_eq!(Store::get_url("file:///tmp/page.html", "Google"), "file:///tmp/page.html");
+    }
+
+    #[test]
+    fn hosts_open_over_https() {
+        assert_eq!(Store::get_url("example.com", "Google"), "https://example.com");
+        assert_eq!(Store::get_url("docs.rs/skia-safe?search=canvas#top", "Google"), "https://docs.rs/skia-safe?search=canvas#top");
+        assert_eq!(Store::get_url("localhost:8080/index.html", "Google"), "https://localhost:8080/index.html");
+        assert_eq!(Store::get_url("192.168.0.1", "Google"), "https://192.168.0.1");
+    }
+
+    #[test]
+    fn other_text_is_searched_for_encoded() {
+        assert_eq!(Store::get_url("c++ & rust", "Google"), "https://www.google.com/search?q=c%2B%2B+%26+rust");
+        assert_eq!(Store::get_url("what is #1?", "Bing"), "https://www.bing.com/search?q=what+is+%231%3F");
+        assert_eq!(Store::get_url("3.14", "DuckDuckGo"), "https://duckduckgo.com/?q=3.14");
+        assert_eq!(Store::get_url("example.com is down", "DuckDuckGo"), "https://duckduckgo.com/?q=example.com+is+down");
+        assert_eq!(Store::get_url("café", "Startpage"), "https://www.startpage.com/search?q=caf%C3%A9");
+    }
+}
@@ -0,0 +1,139 @@
+use std::time::Instant;
+
+use winit::event::MouseButton;
+
+use crate::rendering::browser::{animation::style_transition::StyleTransition, elements::{element::EventType, styles::Styles}};
+
+use super::style_resolver::StyleContext;
+
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct InteractionState {
+    pub is_hovered: bool,
+    pub is_pressed: bool,
+    pub is_focused: bool,
+    pub is_focus_visible: bool, // Focused from the keyboard, so a focus ring is shown
+    pub is_disabled: bool,
+}
+
+// Overrides merged over an element's styles while the matching state is active
+#[derive(Clone, Default)]
+pub struct StateStyles {
+    pub hover: Option<Styles>,
+    pub active: Option<Styles>, // While pressed
+    pub focus: Option<Styles>,
+    pub disabled: Option<Styles>,
+}
+
+impl StateStyles {
+    // Later states win where several set the same property: hover < focus < active < disabled
+    pub fn apply(&self, base: &Styles, state: InteractionState) -> Styles {
+        let overrides = [
+            (state.is_hovered, &self.hover),
+            (state.is_focused, &self.focus),
+            (state.is_pressed, &self.active),
+            (state.is_disabled, &self.disabled),
+        ];
+
+        overrides.iter()
+            .filter_map(|(is_active, styles)| if *is_active { styles.as_ref() } else { None })
+            .fold(base.clone(), |merged, styles| styles.merged_over(&merged))
+    }
+}
+
+pub struct InteractionStyles {
+    pub state_styles: StateStyles,
+    pub state: InteractionState,
+    context: StyleContext, // What the element was last resolved against, to resolve it again on its own
+    needs_relayout: bool,
+    transition: Option<StyleTransition>,
+}
+
+/*
+ * Tracks an element's interaction state and the styles that go with it:
+ *   A. Enter, leave, press, release, focus and blur events update the hovered, pressed and focused states
+ *   B. On a change the element resolves its styles again against the context it was last resolved with
+ *   C. If the new styles have a transition, the computed styles animate towards them frame by frame
+ *   D. If a property affecting layout changed, a relayout is requested from UIManager;
+ *      otherwise the redraw that follows every input event or animation frame is enough
+ * Which element is focused is decided by UIManager, see FocusManager.
+ */
+impl InteractionStyles {
+    pub fn new() -> Self {
+        Self { state_styles: StateStyles::default(), state: InteractionState::default(), context: StyleContext::default(), needs_relayout: false, transition: None }
+    }
+
+    pub fn get_context(&self) -> StyleContext {
+        self.context.clone()
+    }
+
+    // Remembers the context and returns the element's styles with its active state overrides
+    pub fn apply(&mut self, base: &Styles, context: &StyleContext) -> Styles {
+        // Restyles other than interaction ones, e.g. stylesheet changes, apply at once; resolving
+        // against the same context again, as when an ancestor restyles, leaves a transition running
+        if !context.resolves_like(&self.context) {
+            self.transition = None;
+        }
+        self.context = context.clone();
+        self.state_styles.apply(base, self.state)
+    }
+
+    // Whether the event changed the state
+    pub fn on_event(&mut self, event_type: &EventType) -> bool {
+        let previous_state = self.state;
+        match event_type {
+            EventType::MouseEnter => self.state.is_hovered = true,
+            EventType::MouseLeave => self.state.is_hovered = false, // Still pressed until released, as in CSS
+            EventType::MouseDown { button: MouseButton::Left, .. } => self.state.is_pressed = !self.state.is_disabled,
+            // The release may happen in another window once focus is lost, so the press ends there too
+            EventType::MouseUp { button: MouseButton::Left, .. } | EventType::FocusLost => self.state.is_pressed = false,
+            EventType::Focus { is_visible } => {
+                self.state.is_focused = !self.state.is_disabled;
+                self.state.is_focus_visible = self.state.is_focused && *is_visible;
+            }
+            EventType::Blur => {
+                self.state.is_focused = false;
+                self.state.is_focus_visible = false;
+            }
+            _ => (),
+        }
+        self.state != previous_state
+    }
+
+    pub fn set_disabled(&mut self, is_disabled: bool) {
+        self.state.is_disabled = is_disabled;
+        if is_disabled {
+            self.state.is_pressed = false;
+            self.state.is_focused = false;
+            self.state.is_focus_visible = false;
+        }
+    }
+
+    // Called with the styles before and after an interaction restyle; starts the transition to them if any,
+    // replacing the one running
+    pub fn on_restyled(&mut self, previous_styles: &Styles, computed_styles: &mut Styles) {
+        let now = Instant::now();
+        self.transition = computed_styles.transition
+            .filter(|transition| !transition.duration.is_zero())
+            .map(|transition| StyleTransition::new(previous_styles.clone(), computed_styles.clone(), transition, now));
+        if let Some(transition) = &self.transition {
+            *computed_styles = transition.sample(now);
+        }
+        self.needs_relayout |= !computed_styles.has_same_layout(previous_styles);
+    }
+
+    // Steps the running transition; returns whether there was one, so a frame is needed
+    pub fn advance_transition(&mut self, now: Instant, computed_styles: &mut Styles) -> bool {
+        let Some(transition) = &self.transition else { return false; };
+
+        let previous_styles = std::mem::replace(computed_styles, transition.sample(now));
+        self.needs_relayout |= !computed_styles.has_same_layout(&previous_styles);
+        if transition.is_finished_at(now) {
+            self.transition = None;
+        }
+        true
+    }
+
+    pub fn take_needs_relayout(&mut self) -> bool {
+        std::mem::take(&mut self.needs_relayout)
+    }
+}
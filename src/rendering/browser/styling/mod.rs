@@ -0,0 +1,6 @@
+pub mod interaction_styles;
+pub mod style_resolver;
+pub mod stylesheet;
+pub mod stylesheet_parser;
+pub mod theme;
+pub mod theme_manager;
\ No newline at end of file
@@ -0,0 +1,139 @@
+use std::rc::Rc;
+
+use crate::rendering::browser::elements::styles::Styles;
+
+use super::{stylesheet::{SelectorSubject, Stylesheet}, theme::Theme, theme_manager::ThemeManager};
+
+// What an element resolves its styles against: its parent's computed styles, the stylesheet and the theme
+#[derive(Clone)]
+pub struct StyleContext {
+    pub parent_styles: Styles,
+    pub ancestors: Vec<SelectorSubject>, // From the root down to the parent
+    pub stylesheet: Rc<Stylesheet>,
+    pub theme: Theme, // The active one when the root was restyled, which length tokens resolve against
+}
+
+impl StyleContext {
+    pub fn root(stylesheet: Rc<Stylesheet>) -> Self {
+        Self { parent_styles: Styles::initial(), ancestors: vec![], stylesheet, theme: ThemeManager::current() }
+    }
+
+    // Context for the children of an element with the given subject and computed styles
+    pub fn for_children(&self, subject: SelectorSubject, computed_styles: &Styles) -> Self {
+        let mut ancestors = self.ancestors.clone();
+        ancestors.push(subject);
+        Self { parent_styles: computed_styles.clone(), ancestors, stylesheet: self.stylesheet.clone(), theme: self.theme }
+    }
+
+    // Whether an element resolves the same against either context
+    pub fn resolves_like(&self, other: &StyleContext) -> bool {
+        Rc::ptr_eq(&self.stylesheet, &other.stylesheet)
+            && self.theme == other.theme
+            && self.ancestors == other.ancestors
+            && self.parent_styles.has_same_inherited(&other.parent_styles)
+    }
+}
+
+// A root context without a stylesheet, for elements that haven't been restyled yet
+impl Default for StyleContext {
+    fn default() -> Self {
+        StyleContext::root(Rc::new(Stylesheet::empty()))
+    }
+}
+
+pub struct StyleResolver;
+
+/*
+ * Derives an element's computed styles, top-down from the root:
+ *   A. Properties the element sets itself are kept as they are
+ *   B. Unset inherited properties take the parent's computed value
+ *   C. Unset non-inherited properties take their initial value
+ * The root resolves against `Styles::initial()`, so every inherited property of every computed
+ * style is set. Before that, resolve_element merges what the element sets in Rust over what
+ * the stylesheet declares for it, and resolves lengths given as theme tokens against the theme.
+ */
+impl StyleResolver {
+    pub fn resolve_element(subject: &SelectorSubject, specified: &Styles, context: &StyleContext) -> Styles {
+        let declared = context.stylesheet.get_declared_styles(subject, &context.ancestors);
+        let mut specified = specified.merged_over(&declared);
+        specified.resolve_length_tokens(&context.theme);
+        StyleResolver::resolve(&specified, &context.parent_styles)
+    }
+
+    pub fn resolve(specified: &Styles, parent: &Styles) -> Styles {
+        let initial = Styles::initial();
+
+        Styles {
+            size: specified.size.or(initial.size),
+            margin: specified.margin.or(initial.margin),
+            padding: specified.padding.or(initial.padding),
+            alignment: specified.alignment.or(initial.alignment),
+            spacing: specified.spacing.or(initial.spacing),
+            color: specified.color.or(initial.color),
+            border: specified.border.or(initial.border),
+            overflow: specified.overflow.or(initial.overflow),
+            gradient: specified.gradient.clone().or(initial.gradient),
+            shadows: specified.shadows.clone().or(initial.shadows),
+            opacity: specified.opacity.or(initial.opacity),
+            blend_mode: specified.blend_mode.or(initial.blend_mode),
+            text_decoration: specified.text_decoration.or(initial.text_decoration),
+            transition: specified.transition.or(initial.transition),
+            transform: specified.transform.or(initial.transform),
+            length_tokens: None,
+
+            text_color: specified.text_color.or(parent.text_color),
+            font_families: specified.font_families.clone().or_else(|| parent.font_families.clone()),
+            font_size: specified.font_size.or(parent.font_size),
+            font_weight: specified.font_weight.or(parent.font_weight),
+            font_slant: specified.font_slant.or(parent.font_slant),
+            line_height: specified.line_height.or(parent.line_height),
+            letter_spacing: specified.letter_spacing.or(parent.letter_spacing),
+            text_align: specified.text_align.or(parent.text_align),
+            direction: specified.direction.or(parent.direction),
+            cursor: specified.cursor.or(parent.cursor),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rendering::browser::{elements::styles::LengthTokens, styling::theme::RadiusToken};
+
+    use super::*;
+
+    fn button() -> SelectorSubject {
+        SelectorSubject { type_name: "button", id: None, class_names: vec![] }
+    }
+
+    #[test]
+    fn styles_set_in_rust_win_over_the_stylesheet() {
+        let stylesheet = Stylesheet::parse("button { font-size: 20px; padding: 4px }").unwrap();
+        let context = StyleContext::root(Rc::new(stylesheet));
+
+        let specified = Styles { font_size: Some(12.0), ..Default::default() };
+        let computed = StyleResolver::resolve_element(&button(), &specified, &context);
+        assert_eq!(computed.font_size, Some(12.0));
+        assert_eq!(computed.padding.map(|padding| padding.left), Some(4.0));
+    }
+
+    #[test]
+    fn inherited_properties_come_from_the_parent() {
+        let stylesheet = Stylesheet::parse("row { font-weight: 700; opacity: 0.5 }").unwrap();
+        let root = StyleContext::root(Rc::new(stylesheet));
+        let row = SelectorSubject { type_name: "row", id: None, class_names: vec![] };
+        let row_styles = StyleResolver::resolve_element(&row, &Styles::default(), &root);
+
+        let computed = StyleResolver::resolve_element(&button(), &Styles::default(), &root.for_children(row, &row_styles));
+        assert_eq!(computed.font_weight, Some(700));
+        assert_eq!(computed.opacity, Styles::initial().opacity);
+    }
+
+    #[test]
+    fn length_tokens_resolve_against_the_theme() {
+        let context = StyleContext::default();
+        let specified = Styles { length_tokens: Some(LengthTokens::uniform_radius(RadiusToken::Large)), ..Default::default() };
+        let computed = StyleResolver::resolve_element(&button(), &specified, &context);
+        assert_eq!(computed.border.map(|border| border.radius.bottom_left), Some(context.theme.radius.large));
+        assert!(computed.length_tokens.is_none());
+    }
+}
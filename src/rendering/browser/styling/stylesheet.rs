@@ -0,0 +1,166 @@
+use std::{fs, path::Path};
+
+use crate::rendering::browser::elements::{element::Element, styles::Styles};
+
+use super::stylesheet_parser::{StylesheetError, StylesheetParser};
+
+// What selectors are matched against: an element's type name, id and classes
+#[derive(Clone, PartialEq, Debug)]
+pub struct SelectorSubject {
+    pub type_name: &'static str,
+    pub id: Option<String>,
+    pub class_names: Vec<String>,
+}
+
+impl SelectorSubject {
+    pub fn of(element: &dyn Element) -> Self {
+        Self {
+            type_name: element.get_element_type().get_type_name(),
+            id: element.get_style_id(),
+            class_names: element.get_class_names(),
+        }
+    }
+}
+
+// A type, id and classes that must all match one element, e.g. `button#menu.primary`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CompoundSelector {
+    pub type_name: Option<String>, // None for `*` or when only id/classes are given
+    pub id: Option<String>,
+    pub class_names: Vec<String>,
+}
+
+impl CompoundSelector {
+    fn matches(&self, subject: &SelectorSubject) -> bool {
+        self.type_name.as_ref().is_none_or(|type_name| type_name == subject.type_name)
+            && self.id.as_ref().is_none_or(|id| subject.id.as_ref() == Some(id))
+            && self.class_names.iter().all(|class_name| subject.class_names.contains(class_name))
+    }
+}
+
+// Compounds separated by the descendant combinator, outermost ancestor first
+#[derive(Clone, Debug, PartialEq)]
+pub struct Selector {
+    pub compounds: Vec<CompoundSelector>,
+}
+
+impl Selector {
+    // (ids, classes, types), compared lexicographically like CSS
+    pub fn specificity(&self) -> (usize, usize, usize) {
+        self.compounds.iter().fold((0, 0, 0), |(ids, classes, types), compound| (
+            ids + compound.id.is_some() as usize,
+            classes + compound.class_names.len(),
+            types + compound.type_name.is_some() as usize,
+        ))
+    }
+
+    // `ancestors` runs from the root down to the subject's parent
+    pub fn matches(&self, subject: &SelectorSubject, ancestors: &[SelectorSubject]) -> bool {
+        let Some((last, rest)) = self.compounds.split_last() else { return false; };
+        if !last.matches(subject) {
+            return false;
+        }
+
+        // Matching each remaining compound against the nearest ancestor possible is enough for
+        // descendant-only selectors
+        let mut remaining_ancestors = ancestors.iter().rev();
+        rest.iter().rev().all(|compound| remaining_ancestors.any(|ancestor| compound.matches(ancestor)))
+    }
+}
+
+pub struct Rule {
+    pub selectors: Vec<Selector>,
+    pub declarations: Styles,
+}
+
+pub struct Stylesheet {
+    pub rules: Vec<Rule>,
+}
+
+/*
+ * Rules from a stylesheet file, applied while styles are resolved:
+ *   A. Every rule with a selector matching the element contributes its declarations
+ *   B. More specific rules win over less specific ones, later rules over earlier ones
+ *   C. Styles set on the element in Rust win over the whole stylesheet
+ */
+impl Stylesheet {
+    pub fn empty() -> Self {
+        Self { rules: vec![] }
+    }
+
+    pub fn parse(source: &str) -> Result<Self, StylesheetError> {
+        StylesheetParser::parse(source)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, StylesheetError> {
+        let source = fs::read_to_string(path)
+            .map_err(|error| StylesheetError::new(0, 0, &format!("cannot read {}: {}", path.display(), error)))?;
+        Stylesheet::parse(&source)
+    }
+
+    pub fn get_declared_styles(&self, subject: &SelectorSubject, ancestors: &[SelectorSubject]) -> Styles {
+        let mut matching_rules: Vec<((usize, usize, usize), usize, &Rule)> = self.rules.iter().enumerate()
+            .filter_map(|(source_order, rule)| {
+                rule.selectors.iter()
+                    .filter(|selector| selector.matches(subject, ancestors))
+                    .map(|selector| selector.specificity())
+                    .max()
+                    .map(|specificity| (specificity, source_order, rule))
+            })
+            .collect();
+        matching_rules.sort_by_key(|(specificity, source_order, _)| (*specificity, *source_order));
+
+        matching_rules.iter()
+            .fold(Styles::default(), |declared, (_, _, rule)| rule.declarations.merged_over(&declared))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn subject(type_name: &'static str, id: Option<&str>, class_names: &[&str]) -> SelectorSubject {
+        SelectorSubject { type_name, id: id.map(String::from), class_names: class_names.iter().map(|class_name| class_name.to_string()).collect() }
+    }
+
+    fn selector(source: &str) -> Selector {
+        StylesheetParser::parse_selectors(source).unwrap().remove(0)
+    }
+
+    #[test]
+    fn descendant_compounds_match_ancestors_in_order() {
+        let button = subject("button", None, &["primary"]);
+        let ancestors = [subject("row", Some("chrome"), &[]), subject("row", Some("toolbar"), &["bar"])];
+
+        assert!(selector("#chrome button.primary").matches(&button, &ancestors));
+        assert!(selector("#chrome .bar button").matches(&button, &ancestors));
+        assert!(!selector(".bar #chrome button").matches(&button, &ancestors));
+        assert!(!selector("#chrome button.secondary").matches(&button, &ancestors[..1]));
+        assert!(!selector("row button").matches(&button, &[]));
+        assert!(selector("*").matches(&ancestors[0], &[]));
+    }
+
+    #[test]
+    fn specificity_counts_ids_classes_then_types() {
+        assert_eq!(selector("#chrome .bar button.primary").specificity(), (1, 2, 1));
+        assert!(selector("#chrome").specificity() > selector("row.bar.primary button").specificity());
+    }
+
+    #[test]
+    fn more_specific_then_later_rules_win() {
+        let stylesheet = Stylesheet::parse("
+            #menu { font-size: 20px }
+            button { font-size: 12px; font-weight: 700 }
+            .primary { font-size: 14px; letter-spacing: 1px }
+            .wide { letter-spacing: 2px }
+        ").unwrap();
+
+        let declared = stylesheet.get_declared_styles(&subject("button", Some("menu"), &["primary", "wide"]), &[]);
+        assert_eq!(declared.font_size, Some(20.0));
+        assert_eq!(declared.font_weight, Some(700));
+        assert_eq!(declared.letter_spacing, Some(2.0));
+
+        let declared = stylesheet.get_declared_styles(&subject("button", None, &["primary"]), &[]);
+        assert_eq!(declared.font_size, Some(14.0));
+    }
+}
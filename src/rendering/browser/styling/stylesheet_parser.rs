@@ -0,0 +1,818 @@
+use std::{fmt, time::Duration};
+
+use skia_safe::Color;
+
+use crate::rendering::browser::elements::styles::{
+    Border, BorderRadius, BorderSide, BorderStyle, ColorValue, Cursor, Direction, Directions, FontSlant, Length, LengthToken, LengthTokens,
+    Margin, Overflow, Padding, RowItemsAlignment, Size, SizeMode, Spacing, Styles, TextAlign, TextDecoration, Transform, Transition,
+};
+
+use crate::rendering::browser::animation::easing::Easing;
+
+use super::{stylesheet::{CompoundSelector, Rule, Selector, Stylesheet}, theme::{ColorToken, RadiusToken}};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StylesheetError {
+    pub line: usize,   // 1-based; 0 when the error isn't tied to a position
+    pub column: usize, // 1-based
+    pub message: String,
+}
+
+impl StylesheetError {
+    pub fn new(line: usize, column: usize, message: &str) -> Self {
+        Self { line, column, message: message.to_string() }
+    }
+}
+
+impl fmt::Display for StylesheetError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        if self.line == 0 {
+            return write!(formatter, "{}", self.message);
+        }
+        write!(formatter, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for StylesheetError {}
+
+pub struct StylesheetParser {
+    chars: Vec<char>,
+    index: usize,
+    line: usize,
+    column: usize,
+}
+
+/*
+ * Parses the CSS subset used for the browser chrome:
+ *   A. Rules are a comma separated selector list followed by a `{ property: value; }` block
+ *   B. Selectors are type, `.class` and `#id` compounds joined by the descendant combinator
+ *   C. Each declaration is mapped onto the matching `Styles` field as soon as it is read,
+ *      so an invalid value is reported at its own line and column
+ * Comments (`/* */`) may appear between any two tokens.
+ */
+impl StylesheetParser {
+    pub fn parse(source: &str) -> Result<Stylesheet, StylesheetError> {
+        let mut parser = StylesheetParser { chars: source.chars().collect(), index: 0, line: 1, column: 1 };
+        let mut rules = vec![];
+
+        loop {
+            parser.skip_whitespace_and_comments()?;
+            if parser.peek().is_none() {
+                break;
+            }
+            rules.push(parser.parse_rule()?);
+        }
+
+        Ok(Stylesheet { rules })
+    }
+
+    // A selector list on its own, e.g. the scope of a keyboard shortcut
+    pub fn parse_selectors(source: &str) -> Result<Vec<Selector>, StylesheetError> {
+        let mut parser = StylesheetParser { chars: source.chars().collect(), index: 0, line: 1, column: 1 };
+        parser.skip_whitespace_and_comments()?;
+        let selectors = parser.parse_selector_list()?;
+
+        match parser.peek() {
+            Some(next) => Err(parser.error(&format!("unexpected '{}' after the selectors", next))),
+            None => Ok(selectors),
+        }
+    }
+
+    // Cursor
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.index).copied()
+    }
+
+    fn advance(&mut self) -> Option<char> {
+        let next = self.peek()?;
+        self.index += 1;
+        if next == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(next)
+    }
+
+    fn error(&self, message: &str) -> StylesheetError {
+        StylesheetError::new(self.line, self.column, message)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), StylesheetError> {
+        match self.peek() {
+            Some(next) if next == expected => {
+                self.advance();
+                Ok(())
+            }
+            Some(next) => Err(self.error(&format!("expected '{}' but found '{}'", expected, next))),
+            None => Err(self.error(&format!("expected '{}' but reached the end of the file", expected))),
+        }
+    }
+
+    fn skip_whitespace_and_comments(&mut self) -> Result<(), StylesheetError> {
+        loop {
+            match self.peek() {
+                Some(next) if next.is_whitespace() => {
+                    self.advance();
+                }
+                Some('/') if self.chars.get(self.index + 1) == Some(&'*') => {
+                    let (line, column) = (self.line, self.column);
+                    self.advance();
+                    self.advance();
+                    loop {
+                        match self.advance() {
+                            Some('*') if self.peek() == Some('/') => {
+                                self.advance();
+                                break;
+                            }
+                            Some(_) => (),
+                            None => return Err(StylesheetError::new(line, column, "unterminated comment")),
+                        }
+                    }
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn is_ident_char(next: char) -> bool {
+        next.is_ascii_alphanumeric() || next == '-' || next == '_'
+    }
+
+    fn parse_ident(&mut self) -> Result<String, StylesheetError> {
+        let mut ident = String::new();
+        while let Some(next) = self.peek().filter(|next| StylesheetParser::is_ident_char(*next)) {
+            ident.push(next);
+            self.advance();
+        }
+
+        if ident.is_empty() {
+            return Err(match self.peek() {
+                Some(next) => self.error(&format!("expected a name but found '{}'", next)),
+                None => self.error("expected a name but reached the end of the file"),
+            });
+        }
+        Ok(ident)
+    }
+
+    // Rules
+    fn parse_rule(&mut self) -> Result<Rule, StylesheetError> {
+        let selectors = self.parse_selector_list()?;
+        self.expect('{')?;
+        let declarations = self.parse_declarations()?;
+        Ok(Rule { selectors, declarations })
+    }
+
+    fn parse_selector_list(&mut self) -> Result<Vec<Selector>, StylesheetError> {
+        let mut selectors = vec![self.parse_selector()?];
+        loop {
+            self.skip_whitespace_and_comments()?;
+            match self.peek() {
+                Some(',') => {
+                    self.advance();
+                    self.skip_whitespace_and_comments()?;
+                    selectors.push(self.parse_selector()?);
+                }
+                _ => return Ok(selectors),
+            }
+        }
+    }
+
+    fn parse_selector(&mut self) -> Result<Selector, StylesheetError> {
+        let mut compounds = vec![];
+        loop {
+            match self.peek() {
+                Some(next) if next == '.' || next == '#' || next == '*' || StylesheetParser::is_ident_char(next) => {
+                    compounds.push(self.parse_compound_selector()?);
+                    // Whitespace between two compounds is the descendant combinator
+                    self.skip_whitespace_and_comments()?;
+                }
+                _ => break,
+            }
+        }
+
+        if compounds.is_empty() {
+            return Err(match self.peek() {
+                Some(next) => self.error(&format!("expected a selector but found '{}'", next)),
+                None => self.error("expected a selector but reached the end of the file"),
+            });
+        }
+        Ok(Selector { compounds })
+    }
+
+    fn parse_compound_selector(&mut self) -> Result<CompoundSelector, StylesheetError> {
+        let mut compound = CompoundSelector::default();
+
+        match self.peek() {
+            Some('*') => {
+                self.advance();
+            }
+            Some(next) if StylesheetParser::is_ident_char(next) => compound.type_name = Some(self.parse_ident()?),
+            _ => (),
+        }
+
+        loop {
+            match self.peek() {
+                Some('.') => {
+                    self.advance();
+                    compound.class_names.push(self.parse_ident()?);
+                }
+                Some('#') => {
+                    self.advance();
+                    if compound.id.is_some() {
+                        return Err(self.error("a selector can only have one id"));
+                    }
+                    compound.id = Some(self.parse_ident()?);
+                }
+                _ => return Ok(compound),
+            }
+        }
+    }
+
+    fn parse_declarations(&mut self) -> Result<Styles, StylesheetError> {
+        let mut styles = Styles::default();
+
+        loop {
+            self.skip_whitespace_and_comments()?;
+            match self.peek() {
+                Some('}') => {
+                    self.advance();
+                    return Ok(styles);
+                }
+                Some(';') => { // Stray separators are allowed, as in CSS
+                    self.advance();
+                    continue;
+                }
+                None => return Err(self.error("expected '}' but reached the end of the file")),
+                _ => (),
+            }
+
+            let property = self.parse_ident()?;
+            self.skip_whitespace_and_comments()?;
+            self.expect(':')?;
+            self.skip_whitespace_and_comments()?;
+
+            let (line, column) = (self.line, self.column);
+            let mut value = String::new();
+            while let Some(next) = self.peek().filter(|next| *next != ';' && *next != '}') {
+                value.push(next);
+                self.advance();
+            }
+            if self.peek() == Some(';') {
+                self.advance();
+            }
+
+            StylesheetParser::apply_declaration(&mut styles, &property, value.trim())
+                .map_err(|message| StylesheetError::new(line, column, &message))?;
+        }
+    }
+
+    // Declarations
+    fn apply_declaration(styles: &mut Styles, property: &str, value: &str) -> Result<(), String> {
+        if value.is_empty() {
+            return Err(format!("missing value for '{}'", property));
+        }
+        let values = StylesheetParser::split_values(value);
+
+        match property {
+            "width" | "height" => {
+                let length = StylesheetParser::parse_length(value)?;
+                let mut size = styles.size.unwrap_or_default();
+                if property == "width" { size.width = Some(length); } else { size.height = Some(length); }
+                styles.size = Some(size);
+            }
+            "size-mode" => {
+                let mode = match value {
+                    "fit-content" => SizeMode::FitContent,
+                    "exact" => SizeMode::Exact(Directions { horizontal: true, vertical: true }),
+                    "exact-width" => SizeMode::Exact(Directions { horizontal: true, vertical: false }),
+                    "exact-height" => SizeMode::Exact(Directions { horizontal: false, vertical: true }),
+                    "fill-parent" => SizeMode::FillParent,
+                    "fit-parent-width" => SizeMode::FitParentWidth,
+                    "fit-parent-height" => SizeMode::FitParentHeight,
+                    _ => return Err(format!("unknown size mode '{}'", value)),
+                };
+                styles.size = Some(Size { mode: Some(mode), ..styles.size.unwrap_or_default() });
+            }
+            "margin" => {
+                let ([top, right, bottom, left], tokens) = StylesheetParser::parse_box_lengths(&values)?;
+                styles.margin = Some(Margin { top, right, bottom, left });
+                StylesheetParser::set_length_tokens(styles, |length_tokens| length_tokens.margin = tokens);
+            }
+            "margin-top" | "margin-right" | "margin-bottom" | "margin-left" => {
+                let (length, token) = StylesheetParser::parse_length_or_token(value)?.split();
+                let mut margin = styles.margin.unwrap_or_default();
+                let side = match property {
+                    "margin-top" => { margin.top = length; 0 }
+                    "margin-right" => { margin.right = length; 1 }
+                    "margin-bottom" => { margin.bottom = length; 2 }
+                    _ => { margin.left = length; 3 }
+                };
+                styles.margin = Some(margin);
+                StylesheetParser::set_length_tokens(styles, |length_tokens| length_tokens.margin[side] = token);
+            }
+            "padding" => {
+                let ([top, right, bottom, left], tokens) = StylesheetParser::parse_box_lengths(&values)?;
+                styles.padding = Some(Padding { top, right, bottom, left });
+                StylesheetParser::set_length_tokens(styles, |length_tokens| length_tokens.padding = tokens);
+            }
+            "padding-top" | "padding-right" | "padding-bottom" | "padding-left" => {
+                let (length, token) = StylesheetParser::parse_length_or_token(value)?.split();
+                let mut padding = styles.padding.unwrap_or_default();
+                let side = match property {
+                    "padding-top" => { padding.top = length; 0 }
+                    "padding-right" => { padding.right = length; 1 }
+                    "padding-bottom" => { padding.bottom = length; 2 }
+                    _ => { padding.left = length; 3 }
+                };
+                styles.padding = Some(padding);
+                StylesheetParser::set_length_tokens(styles, |length_tokens| length_tokens.padding[side] = token);
+            }
+            "spacing" | "gap" => {
+                let (x, y) = match values.as_slice() {
+                    [both] => (StylesheetParser::parse_length_or_token(both)?, StylesheetParser::parse_length_or_token(both)?),
+                    [x, y] => (StylesheetParser::parse_length_or_token(x)?, StylesheetParser::parse_length_or_token(y)?),
+                    _ => return Err(format!("'{}' takes one or two lengths", property)),
+                };
+                let ((spacing_x, token_x), (spacing_y, token_y)) = (x.split(), y.split());
+                styles.spacing = Some(Spacing { spacing_x, spacing_y });
+                StylesheetParser::set_length_tokens(styles, |length_tokens| length_tokens.spacing = [token_x, token_y]);
+            }
+            "align-items" => {
+                styles.alignment = Some(match value {
+                    "start" => RowItemsAlignment::Start,
+                    "center" => RowItemsAlignment::Center,
+                    "end" => RowItemsAlignment::End,
+                    _ => return Err(format!("unknown alignment '{}'", value)),
+                });
+            }
+            "background" | "background-color" => styles.color = Some(StylesheetParser::parse_color(value)?),
+            "color" => styles.text_color = Some(StylesheetParser::parse_color(value)?),
+            "border" => {
+                let side = StylesheetParser::parse_border_side(&values)?;
+                let border = styles.border.unwrap_or_default();
+                styles.border = Some(Border { width: side.width, color: side.color, style: side.style, ..border });
+            }
+            "border-top" | "border-right" | "border-bottom" | "border-left" => {
+                let side = Some(StylesheetParser::parse_border_side(&values)?);
+                let mut border = styles.border.unwrap_or_default();
+                match property {
+                    "border-top" => border.top = side,
+                    "border-right" => border.right = side,
+                    "border-bottom" => border.bottom = side,
+                    _ => border.left = side,
+                }
+                styles.border = Some(border);
+            }
+            "border-width" => {
+                styles.border = Some(Border { width: StylesheetParser::parse_length(value)?, ..styles.border.unwrap_or_default() });
+            }
+            "border-color" => {
+                styles.border = Some(Border { color: StylesheetParser::parse_color(value)?, ..styles.border.unwrap_or_default() });
+            }
+            "border-style" => {
+                let style = StylesheetParser::parse_border_style(value).ok_or(format!("unknown border style '{}'", value))?;
+                styles.border = Some(Border { style, ..styles.border.unwrap_or_default() });
+            }
+            "border-radius" => {
+                let ([top_left, top_right, bottom_right, bottom_left], tokens) = StylesheetParser::parse_box_lengths(&values)?;
+                let radius = BorderRadius { top_left, top_right, bottom_right, bottom_left };
+                styles.border = Some(Border { radius, ..styles.border.unwrap_or_default() });
+                StylesheetParser::set_length_tokens(styles, |length_tokens| length_tokens.radius = tokens);
+            }
+            "overflow" => {
+                styles.overflow = Some(match value {
+                    "visible" => Overflow::Visible,
+                    "hidden" | "clip" => Overflow::Clip,
+                    _ => return Err(format!("unknown overflow '{}'", value)),
+                });
+            }
+            "transition" => {
+                styles.transition = match value {
+                    "none" => None,
+                    _ => Some(StylesheetParser::parse_transition(&values)?),
+                };
+            }
+            "transform" => {
+                let origin = styles.transform.unwrap_or_default();
+                styles.transform = match value {
+                    "none" => None,
+                    _ => Some(Transform { origin_x: origin.origin_x, origin_y: origin.origin_y, ..StylesheetParser::parse_transform(&values)? }),
+                };
+            }
+            "transform-origin" => {
+                let (origin_x, origin_y) = StylesheetParser::parse_transform_origin(&values)?;
+                styles.transform = Some(Transform { origin_x, origin_y, ..styles.transform.unwrap_or_default() });
+            }
+            "opacity" => {
+                let opacity = value.parse::<f32>().map_err(|_| format!("invalid number '{}'", value))?;
+                styles.opacity = Some(opacity.clamp(0.0, 1.0));
+            }
+            "font-family" => {
+                let families: Vec<String> = value.split(',')
+                    .map(|family| family.trim().trim_matches(|quote| quote == '"' || quote == '\'').to_string())
+                    .collect();
+                if families.iter().any(|family| family.is_empty()) {
+                    return Err(format!("invalid font family list '{}'", value));
+                }
+                styles.font_families = Some(families);
+            }
+            "font-size" => styles.font_size = Some(StylesheetParser::parse_length(value)?),
+            "font-weight" => {
+                styles.font_weight = Some(match value {
+                    "normal" => 400,
+                    "bold" => 700,
+                    _ => value.parse::<u16>().ok()
+                        .filter(|weight| (1..=1000).contains(weight))
+                        .ok_or(format!("invalid font weight '{}'", value))?,
+                });
+            }
+            "font-style" => {
+                styles.font_slant = Some(match value {
+                    "normal" => FontSlant::Normal,
+                    "italic" => FontSlant::Italic,
+                    "oblique" => FontSlant::Oblique,
+                    _ => return Err(format!("unknown font style '{}'", value)),
+                });
+            }
+            "line-height" => {
+                styles.line_height = match value {
+                    "normal" => None,
+                    _ => Some(value.parse::<f32>().map_err(|_| format!("line-height takes a multiple of the font size, not '{}'", value))?),
+                };
+            }
+            "letter-spacing" => {
+                styles.letter_spacing = Some(if value == "normal" { 0.0 } else { StylesheetParser::parse_length(value)? });
+            }
+            "text-align" => {
+                styles.text_align = Some(match value {
+                    "start" => TextAlign::Start,
+                    "center" => TextAlign::Center,
+                    "end" => TextAlign::End,
+                    _ => return Err(format!("unknown text alignment '{}'", value)),
+                });
+            }
+            "text-decoration" => {
+                styles.text_decoration = Some(match value {
+                    "none" => TextDecoration::None,
+                    "underline" => TextDecoration::Underline,
+                    "overline" => TextDecoration::Overline,
+                    "line-through" => TextDecoration::LineThrough,
+                    _ => return Err(format!("unknown text decoration '{}'", value)),
+                });
+            }
+            "direction" => {
+                styles.direction = Some(match value {
+                    "ltr" => Direction::LeftToRight,
+                    "rtl" => Direction::RightToLeft,
+                    _ => return Err(format!("unknown direction '{}'", value)),
+                });
+            }
+            "cursor" => {
+                styles.cursor = Some(match value {
+                    "default" | "auto" => Cursor::Default,
+                    "pointer" => Cursor::Pointer,
+                    "text" => Cursor::Text,
+                    "wait" => Cursor::Wait,
+                    "progress" => Cursor::Progress,
+                    "not-allowed" => Cursor::NotAllowed,
+                    "crosshair" => Cursor::Crosshair,
+                    "move" => Cursor::Move,
+                    "grab" => Cursor::Grab,
+                    "grabbing" => Cursor::Grabbing,
+                    "copy" => Cursor::Copy,
+                    "no-drop" => Cursor::NoDrop,
+                    "col-resize" => Cursor::ColResize,
+                    "row-resize" => Cursor::RowResize,
+                    _ => return Err(format!("unknown cursor '{}'", value)),
+                });
+            }
+            _ => return Err(format!("unknown property '{}'", property)),
+        }
+
+        Ok(())
+    }
+
+    // Values
+    // Splits on whitespace outside parentheses, so `rgb(0, 0, 0)` stays one value
+    fn split_values(value: &str) -> Vec<&str> {
+        let mut values = vec![];
+        let (mut start, mut depth) = (None, 0);
+        for (index, next) in value.char_indices() {
+            match next {
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                _ if next.is_whitespace() && depth <= 0 => {
+                    if let Some(value_start) = start.take() {
+                        values.push(&value[value_start..index]);
+                    }
+                    continue;
+                }
+                _ => (),
+            }
+            start.get_or_insert(index);
+        }
+        if let Some(value_start) = start {
+            values.push(&value[value_start..]);
+        }
+        values
+    }
+
+    fn parse_length(value: &str) -> Result<f32, String> {
+        value.strip_suffix("px").unwrap_or(value)
+            .parse::<f32>()
+            .map_err(|_| format!("invalid length '{}'", value))
+    }
+
+    // A length, or `var(--radius-small|medium|large)` or `var(--space-<step>)` for one of the theme's
+    fn parse_length_or_token(value: &str) -> Result<Length, String> {
+        let Some(name) = value.strip_prefix("var(--").and_then(|rest| rest.strip_suffix(')')) else {
+            return StylesheetParser::parse_length(value).map(Length::Px);
+        };
+        let token = match name {
+            "radius-small" => LengthToken::Radius(RadiusToken::Small),
+            "radius-medium" => LengthToken::Radius(RadiusToken::Medium),
+            "radius-large" => LengthToken::Radius(RadiusToken::Large),
+            _ => match name.strip_prefix("space-").and_then(|step| step.parse::<usize>().ok()) {
+                Some(step) => LengthToken::Space(step),
+                None => return Err(format!("unknown theme length '--{}'", name)),
+            },
+        };
+        Ok(Length::Token(token))
+    }
+
+    // One to four lengths, expanded in CSS order: top, right, bottom, left. Tokens give 0 pixels,
+    // and are returned by side to be resolved over them
+    fn parse_box_lengths(values: &[&str]) -> Result<([f32; 4], [Option<LengthToken>; 4]), String> {
+        let lengths = values.iter()
+            .map(|value| StylesheetParser::parse_length_or_token(value))
+            .collect::<Result<Vec<Length>, String>>()?;
+
+        let lengths = match lengths.as_slice() {
+            [all] => [*all; 4],
+            [vertical, horizontal] => [*vertical, *horizontal, *vertical, *horizontal],
+            [top, horizontal, bottom] => [*top, *horizontal, *bottom, *horizontal],
+            [top, right, bottom, left] => [*top, *right, *bottom, *left],
+            _ => return Err(format!("expected one to four lengths but found {}", lengths.len())),
+        };
+        let split = lengths.map(Length::split);
+        Ok((split.map(|(length, _)| length), split.map(|(_, token)| token)))
+    }
+
+    // Lengths set in pixels clear the tokens set before them, as later declarations win
+    fn set_length_tokens(styles: &mut Styles, update: impl FnOnce(&mut LengthTokens)) {
+        let mut length_tokens = styles.length_tokens.unwrap_or_default();
+        update(&mut length_tokens);
+        styles.length_tokens = (length_tokens != LengthTokens::default()).then_some(length_tokens);
+    }
+
+    // `<duration> <easing>`, in either order and the easing optional
+    fn parse_transition(values: &[&str]) -> Result<Transition, String> {
+        let mut transition = Transition::default();
+        let mut has_duration = false;
+        for value in values {
+            if let Some(duration) = StylesheetParser::parse_duration(value) {
+                transition.duration = duration;
+                has_duration = true;
+            } else {
+                transition.easing = StylesheetParser::parse_easing(value)?;
+            }
+        }
+
+        if !has_duration {
+            return Err("transition needs a duration, e.g. '150ms'".to_string());
+        }
+        Ok(transition)
+    }
+
+    fn parse_duration(value: &str) -> Option<Duration> {
+        let (number, unit_in_seconds) = match value.strip_suffix("ms") {
+            Some(milliseconds) => (milliseconds, 0.001),
+            None => (value.strip_suffix('s')?, 1.0),
+        };
+        let seconds = number.parse::<f32>().ok().filter(|number| *number >= 0.0)? * unit_in_seconds;
+        Some(Duration::from_secs_f32(seconds))
+    }
+
+    fn parse_easing(value: &str) -> Result<Easing, String> {
+        match value {
+            "linear" => Ok(Easing::Linear),
+            "ease" => Ok(Easing::Ease),
+            "ease-in" => Ok(Easing::EaseIn),
+            "ease-out" => Ok(Easing::EaseOut),
+            "ease-in-out" => Ok(Easing::EaseInOut),
+            _ => {
+                let arguments = value.strip_prefix("cubic-bezier(").and_then(|rest| rest.strip_suffix(')'))
+                    .ok_or(format!("unknown easing '{}'", value))?;
+                let points = arguments.split(',')
+                    .map(|point| point.trim().parse::<f32>())
+                    .collect::<Result<Vec<f32>, _>>()
+                    .map_err(|_| format!("invalid easing '{}'", value))?;
+                match points.as_slice() {
+                    [x1, y1, x2, y2] if (0.0..=1.0).contains(x1) && (0.0..=1.0).contains(x2) => Ok(Easing::CubicBezier(*x1, *y1, *x2, *y2)),
+                    _ => Err(format!("invalid easing '{}'", value)),
+                }
+            }
+        }
+    }
+
+    // Transform functions, e.g. `translate(4px, 2px) scale(1.1) rotate(45deg)`. They combine into
+    // one Transform, which always scales, then rotates, then translates, whatever their order here
+    fn parse_transform(values: &[&str]) -> Result<Transform, String> {
+        let mut transform = Transform::default();
+        for value in values {
+            let (name, arguments) = value.strip_suffix(')').and_then(|rest| rest.split_once('('))
+                .ok_or(format!("expected a transform function but found '{}'", value))?;
+            let arguments = arguments.split(',').map(|argument| argument.trim()).collect::<Vec<&str>>();
+            let number = |argument: &str| argument.parse::<f32>().map_err(|_| format!("invalid number '{}'", argument));
+
+            match (name, arguments.as_slice()) {
+                ("translate", [x]) => transform.translate_x += StylesheetParser::parse_length(x)?,
+                ("translate", [x, y]) => {
+                    transform.translate_x += StylesheetParser::parse_length(x)?;
+                    transform.translate_y += StylesheetParser::parse_length(y)?;
+                }
+                ("translateX", [x]) => transform.translate_x += StylesheetParser::parse_length(x)?,
+                ("translateY", [y]) => transform.translate_y += StylesheetParser::parse_length(y)?,
+                ("scale", [both]) => {
+                    let scale = number(both)?;
+                    transform.scale_x *= scale;
+                    transform.scale_y *= scale;
+                }
+                ("scale", [x, y]) => {
+                    transform.scale_x *= number(x)?;
+                    transform.scale_y *= number(y)?;
+                }
+                ("scaleX", [x]) => transform.scale_x *= number(x)?,
+                ("scaleY", [y]) => transform.scale_y *= number(y)?,
+                ("rotate", [angle]) => {
+                    let degrees = angle.strip_suffix("deg").ok_or(format!("rotate takes an angle in degrees, not '{}'", angle))?;
+                    transform.rotate += number(degrees)?;
+                }
+                _ => return Err(format!("invalid transform function '{}'", value)),
+            }
+        }
+        Ok(transform)
+    }
+
+    // One or two of left, center, right, top, bottom or percentages; a single value leaves the other axis centred
+    fn parse_transform_origin(values: &[&str]) -> Result<(f32, f32), String> {
+        let position = |value: &str| match value {
+            "left" | "top" => Ok(0.0),
+            "center" => Ok(0.5),
+            "right" | "bottom" => Ok(1.0),
+            _ => value.strip_suffix('%').and_then(|percentage| percentage.parse::<f32>().ok())
+                .map(|percentage| percentage / 100.0)
+                .ok_or(format!("invalid transform origin '{}'", value)),
+        };
+
+        match values {
+            [vertical @ ("top" | "bottom")] => Ok((0.5, position(vertical)?)),
+            [horizontal] => Ok((position(horizontal)?, 0.5)),
+            [vertical @ ("top" | "bottom"), horizontal @ ("left" | "right" | "center")] => Ok((position(horizontal)?, position(vertical)?)),
+            [horizontal, vertical] => Ok((position(horizontal)?, position(vertical)?)),
+            _ => Err(format!("transform-origin takes one or two positions but found {}", values.len())),
+        }
+    }
+
+    fn parse_border_style(value: &str) -> Option<BorderStyle> {
+        match value {
+            "solid" => Some(BorderStyle::Solid),
+            "dashed" => Some(BorderStyle::Dashed),
+            "dotted" => Some(BorderStyle::Dotted),
+            _ => None,
+        }
+    }
+
+    // `<width> <style> <color>`, in any order and each optional
+    fn parse_border_side(values: &[&str]) -> Result<BorderSide, String> {
+        let mut side = BorderSide::default();
+        for value in values {
+            if let Ok(width) = StylesheetParser::parse_length(value) {
+                side.width = width;
+            } else if let Some(style) = StylesheetParser::parse_border_style(value) {
+                side.style = style;
+            } else {
+                side.color = StylesheetParser::parse_color(value)?;
+            }
+        }
+        Ok(side)
+    }
+
+    // `#rgb`, `#rgba`, `#rrggbb`, `#rrggbbaa`, `rgb()`, `rgba()`, a few names, or `var(--token)`
+    fn parse_color(value: &str) -> Result<ColorValue, String> {
+        let invalid = || format!("invalid color '{}'", value);
+
+        if let Some(hex) = value.strip_prefix('#') {
+            let digits = hex.chars()
+                .map(|digit| digit.to_digit(16).map(|digit| digit as u8))
+                .collect::<Option<Vec<u8>>>()
+                .ok_or_else(invalid)?;
+            let (r, g, b, a) = match digits.as_slice() {
+                [r, g, b] => (r * 17, g * 17, b * 17, 255),
+                [r, g, b, a] => (r * 17, g * 17, b * 17, a * 17),
+                [r1, r2, g1, g2, b1, b2] => (r1 * 16 + r2, g1 * 16 + g2, b1 * 16 + b2, 255),
+                [r1, r2, g1, g2, b1, b2, a1, a2] => (r1 * 16 + r2, g1 * 16 + g2, b1 * 16 + b2, a1 * 16 + a2),
+                _ => return Err(invalid()),
+            };
+            return Ok(Color::from_argb(a, r, g, b).into());
+        }
+
+        if let Some(arguments) = value.strip_prefix("rgba(").or_else(|| value.strip_prefix("rgb(")).and_then(|rest| rest.strip_suffix(')')) {
+            let components: Vec<&str> = arguments.split(',').map(|component| component.trim()).collect();
+            let channel = |component: &str| component.parse::<u8>().map_err(|_| invalid());
+            let (r, g, b, a) = match components.as_slice() {
+                [r, g, b] => (channel(r)?, channel(g)?, channel(b)?, 255),
+                [r, g, b, a] => {
+                    let alpha = a.parse::<f32>().map_err(|_| invalid())?;
+                    (channel(r)?, channel(g)?, channel(b)?, (alpha.clamp(0.0, 1.0) * 255.0).round() as u8)
+                }
+                _ => return Err(invalid()),
+            };
+            return Ok(Color::from_argb(a, r, g, b).into());
+        }
+
+        if let Some(name) = value.strip_prefix("var(--").and_then(|rest| rest.strip_suffix(')')) {
+            let token = match name {
+                "surface" => ColorToken::Surface,
+                "surface-raised" => ColorToken::SurfaceRaised,
+                "surface-sunken" => ColorToken::SurfaceSunken,
+                "accent" => ColorToken::Accent,
+                "on-accent" => ColorToken::OnAccent,
+                "text" => ColorToken::Text,
+                "text-muted" => ColorToken::TextMuted,
+                "border" => ColorToken::Border,
+                "shadow" => ColorToken::Shadow,
+                "focus-ring" => ColorToken::FocusRing,
+                _ => return Err(format!("unknown theme color '--{}'", name)),
+            };
+            return Ok(token.into());
+        }
+
+        let color = match value {
+            "transparent" => Color::TRANSPARENT,
+            "black" => Color::BLACK,
+            "white" => Color::WHITE,
+            "gray" | "grey" => Color::GRAY,
+            "red" => Color::RED,
+            "green" => Color::GREEN,
+            "blue" => Color::BLUE,
+            "yellow" => Color::YELLOW,
+            "cyan" => Color::CYAN,
+            "magenta" => Color::MAGENTA,
+            _ => return Err(invalid()),
+        };
+        Ok(color.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_error(source: &str) -> StylesheetError {
+        StylesheetParser::parse(source).err().unwrap()
+    }
+
+    #[test]
+    fn parses_selector_lists_and_declarations_around_comments() {
+        let stylesheet = StylesheetParser::parse("/* Bars */ row.bar, #chrome /* ids too */ {\n  padding: 4px 8px;; color: var(--accent)\n}").unwrap();
+        assert_eq!(stylesheet.rules.len(), 1);
+
+        let rule = &stylesheet.rules[0];
+        assert_eq!(rule.selectors.len(), 2);
+        assert_eq!(rule.selectors[0].compounds[0], CompoundSelector { type_name: Some(String::from("row")), id: None, class_names: vec![String::from("bar")] });
+        assert_eq!(rule.selectors[1].compounds[0].id.as_deref(), Some("chrome"));
+
+        let padding = rule.declarations.padding.unwrap();
+        assert_eq!((padding.top, padding.right, padding.bottom, padding.left), (4.0, 8.0, 4.0, 8.0));
+        assert_eq!(rule.declarations.text_color, Some(ColorValue::Token(ColorToken::Accent)));
+    }
+
+    #[test]
+    fn errors_point_at_the_line_and_column() {
+        let error = parse_error("row {\n  padding: 4px;\n  colour: red;\n}");
+        assert_eq!(error.to_string(), "3:11: unknown property 'colour'");
+
+        let error = parse_error("row {\n  padding: 4px 3em;\n}");
+        assert_eq!((error.line, error.column, error.message.as_str()), (2, 12, "invalid length '3em'"));
+
+        assert_eq!(parse_error("row { padding: 4px;").message, "expected '}' but reached the end of the file");
+        assert_eq!((parse_error("\n  /* never closed").line, parse_error("\n  /* never closed").column), (2, 3));
+        assert_eq!(parse_error("row#a#b {}").message, "a selector can only have one id");
+    }
+
+    #[test]
+    fn theme_lengths_are_kept_as_tokens() {
+        let stylesheet = StylesheetParser::parse("row { border-radius: var(--radius-medium); spacing: var(--space-2) 0 }").unwrap();
+        let length_tokens = stylesheet.rules[0].declarations.length_tokens.unwrap();
+        assert_eq!(length_tokens.radius, [Some(LengthToken::Radius(RadiusToken::Medium)); 4]);
+        assert_eq!(length_tokens.spacing, [Some(LengthToken::Space(2)), None]);
+
+        let stylesheet = StylesheetParser::parse("row { padding: var(--space-1); padding: 3px }").unwrap();
+        assert!(stylesheet.rules[0].declarations.length_tokens.is_none());
+
+        assert_eq!(parse_error("row { margin: var(--space-huge) }").message, "unknown theme length '--space-huge'");
+    }
+}
@@ -0,0 +1,144 @@
+use skia_safe::Color;
+
+// Named colours that styles reference instead of literals, so they follow the active theme
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ColorToken {
+    Surface,       // Toolbars and the window background
+    SurfaceRaised, // Buttons, fields, popups and the active tab
+    SurfaceSunken, // Wells such as the tab strip
+    Accent,        // Selection and highlights
+    OnAccent,      // Text and icons drawn on the accent
+    Text,
+    TextMuted,     // Secondary text, e.g. accelerators and disabled items
+    Border,
+    Shadow,
+    FocusRing,     // Outline around the element focused from the keyboard
+}
+
+// Named corner radii of the theme's radius scale
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RadiusToken {
+    Small,
+    Medium,
+    Large,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ThemeMode {
+    Light,
+    Dark,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ThemeColors {
+    pub surface: Color,
+    pub surface_raised: Color,
+    pub surface_sunken: Color,
+    pub accent: Color,
+    pub on_accent: Color,
+    pub text: Color,
+    pub text_muted: Color,
+    pub border: Color,
+    pub shadow: Color,
+    pub focus_ring: Color,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct RadiusScale {
+    pub small: f32,
+    pub medium: f32,
+    pub large: f32,
+}
+
+/*
+ * A set of design tokens: colours by role plus the radius and spacing scales. Themes of the
+ * same family share their geometry, so switching between them only changes colours.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Theme {
+    pub name: &'static str,
+    pub mode: ThemeMode,
+    pub colors: ThemeColors,
+    pub radius: RadiusScale,
+    pub spacing: [f32; 6], // Spacing steps, from hairline gaps to section gutters
+}
+
+impl Theme {
+    pub fn light() -> Self {
+        Self {
+            name: "Light",
+            mode: ThemeMode::Light,
+            colors: ThemeColors {
+                surface: Color::from_argb(255, 236, 238, 241),
+                surface_raised: Color::WHITE,
+                surface_sunken: Color::from_argb(255, 200, 204, 210),
+                accent: Color::from_argb(255, 30, 110, 220),
+                on_accent: Color::WHITE,
+                text: Color::from_argb(255, 32, 33, 36),
+                text_muted: Color::from_argb(255, 120, 124, 130),
+                border: Color::from_argb(255, 190, 192, 196),
+                shadow: Color::from_argb(60, 0, 0, 0),
+                focus_ring: Color::from_argb(200, 30, 110, 220),
+            },
+            radius: Theme::default_radius(),
+            spacing: Theme::default_spacing(),
+        }
+    }
+
+    pub fn dark() -> Self {
+        Self {
+            name: "Dark",
+            mode: ThemeMode::Dark,
+            colors: ThemeColors {
+                surface: Color::from_argb(255, 41, 42, 45),
+                surface_raised: Color::from_argb(255, 60, 62, 66),
+                surface_sunken: Color::from_argb(255, 28, 29, 31),
+                accent: Color::from_argb(255, 138, 180, 248),
+                on_accent: Color::from_argb(255, 20, 22, 26),
+                text: Color::from_argb(255, 232, 234, 237),
+                text_muted: Color::from_argb(255, 154, 160, 166),
+                border: Color::from_argb(255, 84, 86, 90),
+                shadow: Color::from_argb(140, 0, 0, 0),
+                focus_ring: Color::from_argb(220, 138, 180, 248),
+            },
+            radius: Theme::default_radius(),
+            spacing: Theme::default_spacing(),
+        }
+    }
+
+    fn default_radius() -> RadiusScale {
+        RadiusScale { small: 4.0, medium: 6.0, large: 12.0 }
+    }
+
+    fn default_spacing() -> [f32; 6] {
+        [2.0, 4.0, 8.0, 12.0, 20.0, 40.0]
+    }
+
+    pub fn color(&self, token: ColorToken) -> Color {
+        match token {
+            ColorToken::Surface => self.colors.surface,
+            ColorToken::SurfaceRaised => self.colors.surface_raised,
+            ColorToken::SurfaceSunken => self.colors.surface_sunken,
+            ColorToken::Accent => self.colors.accent,
+            ColorToken::OnAccent => self.colors.on_accent,
+            ColorToken::Text => self.colors.text,
+            ColorToken::TextMuted => self.colors.text_muted,
+            ColorToken::Border => self.colors.border,
+            ColorToken::Shadow => self.colors.shadow,
+            ColorToken::FocusRing => self.colors.focus_ring,
+        }
+    }
+
+    pub fn radius(&self, token: RadiusToken) -> f32 {
+        match token {
+            RadiusToken::Small => self.radius.small,
+            RadiusToken::Medium => self.radius.medium,
+            RadiusToken::Large => self.radius.large,
+        }
+    }
+
+    // Steps past the end of the scale clamp to its largest value
+    pub fn spacing(&self, step: usize) -> f32 {
+        self.spacing[step.min(self.spacing.len() - 1)]
+    }
+}
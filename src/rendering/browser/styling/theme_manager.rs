@@ -0,0 +1,33 @@
+use std::cell::Cell;
+
+use super::theme::Theme;
+
+thread_local! {
+    static CURRENT_THEME: Cell<Theme> = Cell::new(Theme::light());
+}
+
+pub struct ThemeManager {
+
+}
+
+/*
+ * Holds the active theme that colour tokens resolve against. Changing it doesn't repaint
+ * anything by itself; UIManager::set_theme restyles and lays out the tree afterwards.
+ */
+impl ThemeManager {
+    pub fn current() -> Theme {
+        CURRENT_THEME.with(|theme| theme.get())
+    }
+
+    pub fn set_current(theme: Theme) {
+        CURRENT_THEME.with(|current| current.set(theme));
+    }
+
+    // Theme to use for the OS light/dark preference reported by winit
+    pub fn for_system_theme(system_theme: winit::window::Theme) -> Theme {
+        match system_theme {
+            winit::window::Theme::Light => Theme::light(),
+            winit::window::Theme::Dark => Theme::dark(),
+        }
+    }
+}
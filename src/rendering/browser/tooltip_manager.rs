@@ -0,0 +1,116 @@
+use std::time::Instant;
+
+use skia_safe::{Canvas, Color, Rect};
+
+use super::{elements::{element::ElementSize, styles::{Border, BorderRadius, BoxShadow, Styles}, tooltip::Tooltip}, painting::{box_painter::BoxPainter, text_painter::TextPainter}};
+
+const TEXT_SIZE: f32 = 12.0;
+const BUBBLE_PADDING: f32 = 6.0;
+const ANCHOR_GAP: f32 = 6.0;
+const CORNER_RADIUS: f32 = 4.0;
+
+struct HoveredTooltip {
+    tooltip: Tooltip,
+    anchor: Rect,
+    hover_start: Instant,
+    is_visible: bool,
+    is_suppressed: bool,
+}
+
+pub struct TooltipManager {
+    hovered: Option<HoveredTooltip>,
+}
+
+/*
+ * Tracks the tooltip of the element under the cursor:
+ *   A. Hovering a new element restarts the delay
+ *   B. Once the delay elapses the bubble is shown, anchored to the element
+ *   C. Clicks and key presses hide it until the cursor moves onto another element
+ */
+impl TooltipManager {
+    pub fn new() -> Self {
+        Self { hovered: None }
+    }
+
+    pub fn on_cursor_moved(&mut self, hovered_tooltip: Option<(Tooltip, Rect)>) {
+        match hovered_tooltip {
+            Some((tooltip, anchor)) => {
+                let is_same_target = self.hovered.as_ref()
+                    .is_some_and(|hovered| hovered.tooltip == tooltip && hovered.anchor == anchor);
+                if !is_same_target {
+                    self.hovered = Some(HoveredTooltip { tooltip, anchor, hover_start: Instant::now(), is_visible: false, is_suppressed: false });
+                }
+            }
+            None => self.hovered = None,
+        }
+    }
+
+    pub fn dismiss(&mut self) {
+        if let Some(hovered) = &mut self.hovered {
+            hovered.is_visible = false;
+            hovered.is_suppressed = true;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.hovered = None;
+    }
+
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.hovered.as_ref()
+            .filter(|hovered| !hovered.is_visible && !hovered.is_suppressed)
+            .map(|hovered| hovered.hover_start + hovered.tooltip.delay)
+    }
+
+    // Returns whether the tooltip became visible and needs a redraw
+    pub fn on_timer(&mut self, now: Instant) -> bool {
+        let Some(hovered) = &mut self.hovered else { return false; };
+        if hovered.is_visible || hovered.is_suppressed || now < hovered.hover_start + hovered.tooltip.delay {
+            return false;
+        }
+        hovered.is_visible = true;
+        true
+    }
+
+    /*
+     * The bubble goes below the anchor, centred on it, and flips above it when it would
+     * leave the viewport; horizontally it is pushed back inside the viewport edges
+     */
+    fn bubble_rect(anchor: &Rect, text_width: f32, viewport: &ElementSize) -> Rect {
+        let width = text_width + 2.0 * BUBBLE_PADDING;
+        let height = TEXT_SIZE + 2.0 * BUBBLE_PADDING;
+
+        let below_top = anchor.bottom + ANCHOR_GAP;
+        let top = if below_top + height > viewport.height && anchor.top - ANCHOR_GAP - height >= 0.0 {
+            anchor.top - ANCHOR_GAP - height
+        } else {
+            below_top
+        };
+        let left = (anchor.center_x() - width / 2.0).min(viewport.width - width).max(0.0);
+
+        Rect::from_xywh(left, top, width, height)
+    }
+
+    pub fn render(&self, canvas: &Canvas, viewport: &ElementSize) {
+        let Some(hovered) = self.hovered.as_ref().filter(|hovered| hovered.is_visible) else { return; };
+
+        let text_styles = Styles { font_size: Some(TEXT_SIZE), ..Styles::initial() };
+        let text_width = TextPainter::measure_width(&hovered.tooltip.text, &text_styles);
+        let bubble_rect = TooltipManager::bubble_rect(&hovered.anchor, text_width, viewport);
+        let bubble_styles = Styles {
+            color: Some(Color::from_argb(240, 40, 40, 40).into()),
+            border: Some(Border {
+                width: 1.0,
+                color: Color::from_argb(255, 20, 20, 20).into(),
+                radius: BorderRadius::uniform(CORNER_RADIUS),
+                ..Default::default()
+            }),
+            shadows: Some(vec![BoxShadow { offset_y: 2.0, blur_radius: 6.0, ..Default::default() }]),
+            ..Default::default()
+        };
+        BoxPainter::paint_box(canvas, bubble_rect, &bubble_styles, Color::TRANSPARENT.into());
+
+        let text_rect = bubble_rect.with_inset((BUBBLE_PADDING, BUBBLE_PADDING));
+        TextPainter::paint_line(canvas, &hovered.tooltip.text, text_rect, &text_styles, Color::WHITE);
+    }
+}
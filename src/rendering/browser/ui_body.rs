@@ -1,130 +1,309 @@
 
-use super::elements::{button::Button, element::{Element, ElementSize}, row::Row, styles::{Border, Margin, Padding, RowItemsAlignment, Size, SizeMode, Spacing, Styles}};
-
-
-pub fn get_ui_body() -> Box<dyn Element> {
-    let first_row_children: Vec<Box<dyn Element>> = vec![
-        Box::new(
-            Button::new(None, Box::new(|| println!("Button 1 clicked")))
-                .set_styles(Styles {
-                    size: Some(Size { width: Some(50.0), height: Some(100.0), mode: Some(SizeMode::FitContent) }),
-                    color: Some(skia_safe::Color::from_argb(255, 255, 0, 0)),
-                    ..Default::default()
-                })
-        ),
-        Box::new(
-            Button::new(None, Box::new(|| println!("Button 2 clicked")))
-                .set_styles(Styles {
-                    size: Some(Size { width: Some(75.0), height: Some(75.0), mode: Some(SizeMode::FitContent) }),
-                    color: Some(skia_safe::Color::from_argb(255, 0, 255, 0)),
-                    ..Default::default()
-                })
-        ),
-        Box::new(
-            Button::new(None, Box::new(|| println!("Button 3 clicked")))
-                .set_styles(Styles {
-                    size: Some(Size { width: Some(100.0), height: Some(50.0), mode: Some(SizeMode::FitContent) }),
-                    color: Some(skia_safe::Color::from_argb(255, 0, 0, 255)),
-                    ..Default::default()
-                })
-        ),
-    ];
-    let first_row = Box::new(Row::new()
-        .add_children(first_row_children)
-        .set_styles(Styles {
-            spacing: Some(Spacing { spacing_x: 5.0, spacing_y: 0.0 }),
-            alignment: Some(RowItemsAlignment::Start),
-            // padding: Some(Padding {
-            //     top: 20.0,
-            //     right: 10.0,
-            //     bottom: 30.0,
-            //     left: 10.0,
-            // }),
-            border: Some(Border {
-                width: 2.0,
-                color: skia_safe::Color::from_argb(255, 0, 0, 0),
-                ..Default::default()
-            }),
-            color: Some(skia_safe::Color::from_argb(255, 180, 180, 180)),
-            ..Default::default()
-        })
-    );
-
-    let second_row_children: Vec<Box<dyn Element>> = vec![
-        Box::new(
-            Button::new(None, Box::new(|| println!("Button 4 clicked")))
-                .set_styles(Styles {
-                    size: Some(Size { width: Some(40.0), height: Some(60.0), mode: Some(SizeMode::FitContent) }),
-                    color: Some(skia_safe::Color::from_argb(255, 255, 255, 0)),
-                    ..Default::default()
-                })
-        ),
-        Box::new(
-            Button::new(None, Box::new(|| println!("Button 5 clicked")))
-                .set_styles(Styles {
-                    size: Some(Size { width: Some(20.0), height: Some(40.0), mode: Some(SizeMode::FitContent) }),
-                    color: Some(skia_safe::Color::from_argb(255, 0, 255, 255)),
-                    ..Default::default()
-                })
-        ),
-        Box::new(
-            Button::new(None, Box::new(|| println!("Button 6 clicked")))
-                .set_styles(Styles {
-                    size: Some(Size { width: Some(60.0), height: Some(60.0), mode: Some(SizeMode::FitContent) }),
-                    color: Some(skia_safe::Color::from_argb(255, 130, 70, 170)),
-                    ..Default::default()
-                })
-        ),
-    ];
-
-    let second_row = Box::new(Row::new()
-        .add_children(second_row_children)
-        .set_styles(Styles {
-            spacing: Some(Spacing { spacing_x: 20.0, spacing_y: 0.0 }),
-            alignment: Some(RowItemsAlignment::Center),
-            // padding: Some(Padding {
-            //     top: 10.0,
-            //     right: 10.0,
-            //     bottom: 10.0,
-            //     left: 10.0,
-            // }),
-            border: Some(Border {
-                width: 2.0,
-                color: skia_safe::Color::from_argb(255, 0, 0, 0),
-                ..Default::default()
-            }),
-            color: Some(skia_safe::Color::from_argb(255, 60, 60, 60)),
-            ..Default::default()
-        })
-    );
-
-    let total_row: Box<dyn Element> = Box::new(Row::new()
-        .add_child(first_row)
-        .add_child(second_row)
-        .set_styles(Styles {
-            spacing: Some(Spacing { spacing_x: 40.0, spacing_y: 0.0 }),
-            // alignment: Some(RowItemsAlignment::End),
-            margin: Some(Margin {
-                top: 20.0,
-                right: 20.0,
-                bottom: 20.0,
-                left: 20.0,
-            }),
-            // padding: Some(Padding {
-            //     top: 20.0,
-            //     right: 20.0,
-            //     bottom: 20.0,
-            //     left: 20.0,
-            // }),
-            border: Some(Border {
-                width: 2.0,
-                color: skia_safe::Color::from_argb(255, 0, 0, 0),
-                ..Default::default()
-            }),
-            // color: Some(skia_safe::Color::from_argb(255, 120, 120, 120)),
-            ..Default::default()
-        })
-    );
-    
-    total_row
+use super::elements::{button::Button, canvas_element::CanvasElement, element::{DragData, Element}, label::Label, menu::{Menu, MenuItem}, row::Row, select::Select, tab::TabInfo, tab_strip::{TabStrip, TabStripEvent}, virtual_list::{ItemHeight, VirtualList}, styles::{BoxShadow, ColorStop, ColorValue, Cursor, Gradient, LengthTokens, Size, SizeMode, Styles, Transform}};
+use super::reactive::derived::Derived;
+use super::state::{app_signals::AppSignals, app_state::{AppState, TabState}, message::Message, message_queue::MessageQueue};
+use super::styling::{interaction_styles::StateStyles, theme::{ColorToken, RadiusToken}, theme_manager::ThemeManager};
+use super::ui_macro::ui;
+
+const SEARCH_ENGINES: [&str; 4] = ["DuckDuckGo", "Google", "Bing", "Startpage"];
+
+
+pub fn get_initial_state() -> AppState {
+    AppState::new(
+        vec![TabState::new("Cartan", None), TabState::new("Skia", None), TabState::new("Rust", None)],
+        SEARCH_ENGINES[0],
+    )
+}
+
+// Callbacks send messages rather than acting themselves, and elements showing the state bind to it,
+// or to its signals; see Store and ReactiveRuntime
+pub fn get_ui_body(state: &AppState, signals: &AppSignals) -> Box<dyn Element> {
+    let (active_url, bookmarks) = (signals.active_url.clone(), signals.bookmarks.clone());
+
+    // Spacing, borders and colours of the rows come from styles/chrome.css
+    ui! {
+        Row() {
+            style_id: "chrome",
+            context_menu: get_page_context_menu,
+            children: [
+                // The strip changes its own tabs as they're used, and reports it so the state follows;
+                // changes coming from anywhere else reach it through the state binding
+                TabStrip(
+                    get_tab_infos(state),
+                    Box::new(|event| MessageQueue::send(match event {
+                        TabStripEvent::Select(index) => Message::SelectTab(index),
+                        TabStripEvent::Close(index) => Message::CloseTab(index),
+                        TabStripEvent::Reorder { from, to } => Message::MoveTab { from, to },
+                        TabStripEvent::New(_) => Message::OpenTab { url: None },
+                    }))
+                ) {
+                    active_index: state.active_index.unwrap_or(0),
+                    state_binding: |tab_strip, state| tab_strip.sync_tabs(get_tab_infos(state), state.active_index),
+                    styles: { size: Size { width: Some(480.0), height: Some(36.0), mode: Some(SizeMode::FitContent) } },
+                    tab_context_menu: get_tab_context_menu,
+                },
+                Row() {
+                    style_id: "toolbar",
+                    class: "bar",
+                    children: [
+                        Button(None, Box::new(|| MessageQueue::send(Message::GoBack))) {
+                            styles: {
+                                size: Size { width: Some(50.0), height: Some(100.0), mode: Some(SizeMode::FitContent) },
+                                color: skia_safe::Color::from_argb(255, 255, 0, 0).into(),
+                            },
+                            state_styles: get_navigation_button_state_styles(),
+                            disabled: true,
+                            state_binding: |button, state| {
+                                button.update_disabled(!state.get_active_tab().is_some_and(|tab| tab.can_go_back()));
+                            },
+                            tooltip: "Back",
+                        },
+                        Button(None, Box::new(|| MessageQueue::send(Message::GoForward))) {
+                            styles: {
+                                size: Size { width: Some(75.0), height: Some(75.0), mode: Some(SizeMode::FitContent) },
+                                color: skia_safe::Color::from_argb(255, 0, 255, 0).into(),
+                                opacity: 0.6,
+                            },
+                            state_styles: get_navigation_button_state_styles(),
+                            disabled: true,
+                            state_binding: |button, state| {
+                                button.update_disabled(!state.get_active_tab().is_some_and(|tab| tab.can_go_forward()));
+                            },
+                            tooltip: "Forward",
+                        },
+                        // Clicking opens its menu, so it needs nothing else
+                        Button(None, Box::new(|| {})) {
+                            styles: {
+                                size: Size { width: Some(100.0), height: Some(50.0), mode: Some(SizeMode::FitContent) },
+                                color: ColorToken::Accent.into(),
+                                gradient: Gradient::Linear {
+                                    angle: 0.0,
+                                    stops: vec![
+                                        ColorStop { offset: 0.0, color: ColorValue::TokenWithAlpha(ColorToken::Accent, 160) },
+                                        ColorStop { offset: 1.0, color: ColorToken::Accent.into() },
+                                    ],
+                                },
+                                length_tokens: LengthTokens::uniform_radius(RadiusToken::Medium),
+                                shadows: vec![BoxShadow { offset_y: 3.0, blur_radius: 8.0, ..Default::default() }],
+                            },
+                            menu: get_app_menu(),
+                            tooltip: "Menu",
+                        },
+                        { get_network_activity_sparkline() },
+                    ],
+                },
+                Row() {
+                    style_id: "navigation-bar",
+                    class: "bar",
+                    children: [
+                        Button(None, Box::new(move || {
+                            if let Some(url) = active_url.get_untracked() {
+                                MessageQueue::send(Message::AddBookmark(url));
+                            }
+                        })) {
+                            styles: { size: Size { width: Some(40.0), height: Some(60.0), mode: Some(SizeMode::FitContent) } },
+                            // Turns accent once something is bookmarked
+                            color_binding: move || -> ColorValue {
+                                if bookmarks.get().is_empty() { skia_safe::Color::from_argb(255, 255, 255, 0).into() } else { ColorToken::Accent.into() }
+                            },
+                            state_styles: StateStyles {
+                                hover: Some(Styles { color: Some(skia_safe::Color::from_argb(255, 255, 220, 0).into()), ..Default::default() }),
+                                // Growing while pressed changes the size, so the row is laid out again
+                                active: Some(Styles {
+                                    size: Some(Size { width: Some(44.0), height: Some(64.0), mode: Some(SizeMode::FitContent) }),
+                                    ..Default::default()
+                                }),
+                                ..Default::default()
+                            },
+                            // History entries dragged onto it are bookmarked
+                            on_drop: "text/uri-list" => |data| MessageQueue::send(Message::AddBookmark(data.value.clone())),
+                            tooltip: "Bookmark this page",
+                        },
+                        Button(None, Box::new(|| MessageQueue::send(Message::Reload))) {
+                            styles: {
+                                size: Size { width: Some(60.0), height: Some(60.0), mode: Some(SizeMode::FitContent) },
+                                color: skia_safe::Color::from_argb(255, 130, 70, 170).into(),
+                            },
+                            state_styles: StateStyles {
+                                // Only painted and hit-tested transformed; its neighbours stay where they are
+                                hover: Some(Styles { transform: Some(Transform { scale_x: 1.1, scale_y: 1.1, rotate: 8.0, ..Default::default() }), ..Default::default() }),
+                                ..Default::default()
+                            },
+                            tooltip: "Reload",
+                        },
+                        Select(
+                            SEARCH_ENGINES.iter().map(|search_engine| search_engine.to_string()).collect(),
+                            Box::new(|_, search_engine| MessageQueue::send(Message::SetSearchEngine(search_engine.to_string())))
+                        ) {
+                            selected_index: SEARCH_ENGINES.iter().position(|search_engine| *search_engine == state.search_engine).unwrap_or(0),
+                            styles: { size: Size { width: Some(160.0), height: Some(32.0), mode: Some(SizeMode::FitContent) } },
+                        },
+                        { get_address_label(signals) },
+                    ],
+                },
+                { get_bookmarks_bar(signals) },
+                VirtualList(
+                    50_000,
+                    ItemHeight::Fixed(24.0),
+                    Box::new(|index| {
+                        Button::new(Some(get_history_entry_styles(index)), get_history_entry_on_click(index))
+                            .set_drag_data(get_history_entry_link(index))
+                    }),
+                    Box::new(|index, button: &mut Button| {
+                        button.restyle(get_history_entry_styles(index));
+                        button.on_click = get_history_entry_on_click(index);
+                        button.drag_data = Some(get_history_entry_link(index));
+                    })
+                ) {
+                    styles: { size: Size { width: Some(200.0), height: Some(300.0), mode: Some(SizeMode::FitContent) } },
+                    style_id: "history",
+                },
+            ],
+        }
+    }
+}
+
+fn get_address_label(signals: &AppSignals) -> Box<dyn Element> {
+    let (active_url, is_loading) = (signals.active_url.clone(), signals.is_loading.clone());
+    let address = Derived::new(move || match (active_url.get(), is_loading.get()) {
+        (Some(url), true) => format!("Loading {}\u{2026}", url),
+        (Some(url), false) => url,
+        (None, _) => String::from("Search or enter an address"),
+    });
+
+    ui! {
+        Label("") {
+            text_binding: move || address.get(),
+            styles: { size: Size { width: Some(240.0), height: Some(32.0), mode: Some(SizeMode::FitContent) } },
+            style_id: "address",
+        }
+    }
+}
+
+// A button per bookmark, rebuilt as they change, with a hint while there are none
+fn get_bookmarks_bar(signals: &AppSignals) -> Box<dyn Element> {
+    let (bookmarks, hint_bookmarks) = (signals.bookmarks.clone(), signals.bookmarks.clone());
+
+    ui! {
+        Row() {
+            style_id: "bookmarks-bar",
+            class: "bar",
+            children: [
+                Label("Drop history entries on the yellow button to bookmark them") {
+                    visibility_binding: move || hint_bookmarks.get().is_empty(),
+                },
+                Row() {
+                    children_binding: move || bookmarks.get() => |url: &String| -> Box<dyn Element> {
+                        let link = url.clone();
+                        ui! {
+                            Button(None, Box::new(move || MessageQueue::send(Message::Navigate(link.clone())))) {
+                                styles: {
+                                    size: Size { width: Some(24.0), height: Some(24.0), mode: Some(SizeMode::FitContent) },
+                                    color: ColorToken::Accent.into(),
+                                },
+                                tooltip: url,
+                            }
+                        }
+                    },
+                },
+            ],
+        }
+    }
+}
+
+fn get_network_activity_sparkline() -> Box<dyn Element> {
+    let samples = [3.0, 7.0, 4.0, 9.0, 12.0, 6.0, 8.0, 15.0, 11.0, 5.0, 9.0, 13.0];
+    ui! {
+        CanvasElement(Box::new(move |canvas, size| {
+            let max_sample = samples.iter().cloned().fold(f32::MIN, f32::max);
+            let step = size.width / (samples.len() - 1) as f32;
+
+            let mut path = skia_safe::Path::new();
+            for (index, sample) in samples.iter().enumerate() {
+                let point = (index as f32 * step, size.height - sample / max_sample * size.height);
+                if index == 0 { path.move_to(point); } else { path.line_to(point); }
+            }
+
+            let mut paint = skia_safe::Paint::default();
+            paint.set_anti_alias(true);
+            paint.set_style(skia_safe::PaintStyle::Stroke);
+            paint.set_stroke_width(1.5);
+            paint.set_color(ThemeManager::current().color(ColorToken::Accent));
+            canvas.draw_path(&path, &paint);
+        })) {
+            styles: { size: Size { width: Some(80.0), height: Some(30.0), mode: Some(SizeMode::FitContent) } },
+        }
+    }
+}
+
+fn get_history_entry_styles(index: usize) -> Styles {
+    let shade = if index % 2 == 0 { ColorToken::Surface } else { ColorToken::SurfaceRaised };
+    Styles {
+        size: Some(Size { width: Some(194.0), height: Some(24.0), mode: Some(SizeMode::FitContent) }),
+        color: Some(shade.into()),
+        ..Default::default()
+    }
+}
+
+fn get_history_entry_url(index: usize) -> String {
+    format!("https://example.com/history/{}", index)
+}
+
+fn get_history_entry_link(index: usize) -> DragData {
+    DragData::new("text/uri-list", &get_history_entry_url(index))
+}
+
+fn get_history_entry_on_click(index: usize) -> Box<dyn FnMut()> {
+    Box::new(move || MessageQueue::send(Message::Navigate(get_history_entry_url(index))))
+}
+
+fn get_tab_infos(state: &AppState) -> Vec<TabInfo> {
+    state.tabs.iter().map(|tab| TabInfo::new(&tab.title)).collect()
+}
+
+fn get_navigation_button_state_styles() -> StateStyles {
+    StateStyles {
+        disabled: Some(Styles { opacity: Some(0.4), cursor: Some(Cursor::NotAllowed), ..Default::default() }),
+        ..Default::default()
+    }
+}
+
+fn get_app_menu() -> Menu {
+    Menu::new(vec![
+        MenuItem::action("New Tab", Box::new(|| MessageQueue::send(Message::OpenTab { url: None }))).set_accelerator("Ctrl+T"),
+        MenuItem::action("New Window", Box::new(|| {})).set_accelerator("Ctrl+N").set_enabled(false),
+        MenuItem::separator(),
+        MenuItem::submenu("Zoom", Menu::new(vec![
+            MenuItem::action("Zoom In", Box::new(|| {})).set_accelerator("Ctrl++").set_enabled(false),
+            MenuItem::action("Zoom Out", Box::new(|| {})).set_accelerator("Ctrl+-").set_enabled(false),
+            MenuItem::action("Reset Zoom", Box::new(|| {})).set_accelerator("Ctrl+0").set_enabled(false),
+        ])),
+        MenuItem::action("History", Box::new(|| {})).set_accelerator("Ctrl+H").set_enabled(false),
+        MenuItem::action("Downloads", Box::new(|| {})).set_accelerator("Ctrl+J").set_enabled(false),
+        MenuItem::separator(),
+        MenuItem::action("Quit", Box::new(|| MessageQueue::send(Message::Quit))).set_accelerator("Ctrl+Q"),
+    ])
+}
+
+fn get_tab_context_menu(tab_index: usize) -> Menu {
+    Menu::new(vec![
+        MenuItem::action("Reload", Box::new(move || {
+            MessageQueue::send(Message::SelectTab(tab_index));
+            MessageQueue::send(Message::Reload);
+        })).set_accelerator("Ctrl+R"),
+        MenuItem::action("Duplicate", Box::new(move || MessageQueue::send(Message::DuplicateTab(tab_index)))),
+        MenuItem::separator(),
+        MenuItem::action("Close Tab", Box::new(move || MessageQueue::send(Message::CloseTab(tab_index)))).set_accelerator("Ctrl+W"),
+        MenuItem::action("Close Other Tabs", Box::new(move || MessageQueue::send(Message::CloseOtherTabs(tab_index)))),
+    ])
+}
+
+fn get_page_context_menu() -> Menu {
+    Menu::new(vec![
+        MenuItem::action("Back", Box::new(|| MessageQueue::send(Message::GoBack))).set_accelerator("Alt+Left"),
+        MenuItem::action("Forward", Box::new(|| MessageQueue::send(Message::GoForward))).set_accelerator("Alt+Right"),
+        MenuItem::action("Reload", Box::new(|| MessageQueue::send(Message::Reload))).set_accelerator("Ctrl+R"),
+        MenuItem::separator(),
+        MenuItem::action("View Page Source", Box::new(|| {})).set_accelerator("Ctrl+U").set_enabled(false),
+    ])
 }
\ No newline at end of file
@@ -0,0 +1,113 @@
+/*
+ * Declares a tree of elements rather than chaining their builders by hand:
+ *
+ *     ui! {
+ *         Row() {
+ *             style_id: "toolbar",
+ *             class: "bar",
+ *             children: [
+ *                 Button(None, Box::new(|| MessageQueue::send(Message::GoBack))) {
+ *                     styles: { size: Size { width: Some(50.0), height: Some(100.0), mode: Some(SizeMode::FitContent) } },
+ *                     tooltip: "Back",
+ *                 },
+ *                 { get_network_activity_sparkline() },
+ *             ],
+ *         }
+ *     }
+ *
+ * It expands to the same builder calls, and gives the element as a Box<dyn Element>:
+ *   A. `Type(arguments)` constructs the element with `Type::new(arguments)`
+ *   B. Each property calls its builder, in the order written; see `@property` below for which.
+ *      Handlers and bindings are boxed, and `styles: { ... }` fills in the fields given of Styles
+ *   C. `children: [...]` adds the elements listed, in order. `{ expression }` adds one built
+ *      elsewhere, which must be a Box<dyn Element> too
+ * A property the macro doesn't know is a compile error, and so is one the element has no
+ * builder for, as its builder method is then missing.
+ */
+macro_rules! ui {
+    ($element:ident ( $($argument:expr),* $(,)? ) { $($properties:tt)* }) => {{
+        let element = $crate::rendering::browser::ui_macro::ui!(@properties $element::new($($argument),*), $($properties)*);
+        Box::new(element) as Box<dyn $crate::rendering::browser::elements::element::Element>
+    }};
+    ($element:ident ( $($argument:expr),* $(,)? )) => {
+        Box::new($element::new($($argument),*)) as Box<dyn $crate::rendering::browser::elements::element::Element>
+    };
+
+    // Properties taking more than a single value, then the ones that do
+    (@properties $target:expr, $(,)?) => { $target };
+    (@properties $target:expr, children: [ $($children:tt)* ] $(, $($rest:tt)*)?) => {
+        $crate::rendering::browser::ui_macro::ui!(
+            @properties $target.add_children($crate::rendering::browser::ui_macro::ui!(@children [] $($children)*)),
+            $($($rest)*)?
+        )
+    };
+    (@properties $target:expr, styles: { $($field:ident : $value:expr),* $(,)? } $(, $($rest:tt)*)?) => {
+        $crate::rendering::browser::ui_macro::ui!(
+            @properties $target.set_styles($crate::rendering::browser::elements::styles::Styles {
+                $($field: Some($value),)*
+                ..Default::default()
+            }),
+            $($($rest)*)?
+        )
+    };
+    (@properties $target:expr, on_drop: $format:expr => $on_drop:expr $(, $($rest:tt)*)?) => {
+        $crate::rendering::browser::ui_macro::ui!(@properties $target.set_on_drop($format, Box::new($on_drop)), $($($rest)*)?)
+    };
+    (@properties $target:expr, children_binding: $items:expr => $build_child:expr $(, $($rest:tt)*)?) => {
+        $crate::rendering::browser::ui_macro::ui!(
+            @properties $target.set_children_binding(Box::new($items), Box::new($build_child)),
+            $($($rest)*)?
+        )
+    };
+    (@properties $target:expr, $property:ident : $value:expr $(, $($rest:tt)*)?) => {
+        $crate::rendering::browser::ui_macro::ui!(
+            @properties $crate::rendering::browser::ui_macro::ui!(@property $target, $property, $value),
+            $($($rest)*)?
+        )
+    };
+
+    (@property $target:expr, style_id, $value:expr) => { $target.set_style_id($value) };
+    (@property $target:expr, class, $value:expr) => { $target.add_class($value) };
+    (@property $target:expr, styles, $value:expr) => { $target.set_styles($value) };
+    (@property $target:expr, state_styles, $value:expr) => { $target.set_state_styles($value) };
+    (@property $target:expr, size, $value:expr) => { $target.set_size($value) };
+    (@property $target:expr, color, $value:expr) => { $target.set_color($value) };
+    (@property $target:expr, margin, $value:expr) => { $target.set_margin($value) };
+    (@property $target:expr, padding, $value:expr) => { $target.set_padding($value) };
+    (@property $target:expr, spacing, $value:expr) => { $target.set_spacing($value) };
+    (@property $target:expr, alignment, $value:expr) => { $target.set_alignment($value) };
+    (@property $target:expr, disabled, $value:expr) => { $target.set_disabled($value) };
+    (@property $target:expr, tab_index, $value:expr) => { $target.set_tab_index($value) };
+    (@property $target:expr, tooltip, $value:expr) => {
+        $target.set_tooltip($crate::rendering::browser::elements::tooltip::Tooltip::new($value))
+    };
+    (@property $target:expr, menu, $value:expr) => { $target.set_menu($value) };
+    (@property $target:expr, context_menu, $value:expr) => { $target.set_context_menu(Box::new($value)) };
+    (@property $target:expr, tab_context_menu, $value:expr) => { $target.set_tab_context_menu(Box::new($value)) };
+    (@property $target:expr, drag_data, $value:expr) => { $target.set_drag_data($value) };
+    (@property $target:expr, selected_index, $value:expr) => { $target.set_selected_index($value) };
+    (@property $target:expr, active_index, $value:expr) => { $target.set_active_index($value) };
+    (@property $target:expr, on_click, $value:expr) => { $target.set_on_click(Box::new($value)) };
+    (@property $target:expr, on_mouse_event, $value:expr) => { $target.set_on_mouse_event(Box::new($value)) };
+    (@property $target:expr, state_binding, $value:expr) => { $target.set_state_binding(Box::new($value)) };
+    (@property $target:expr, text_binding, $value:expr) => { $target.set_text_binding(Box::new($value)) };
+    (@property $target:expr, color_binding, $value:expr) => { $target.set_color_binding(Box::new($value)) };
+    (@property $target:expr, visibility_binding, $value:expr) => { $target.set_visibility_binding(Box::new($value)) };
+    (@property $target:expr, $property:ident, $value:expr) => {
+        compile_error!(concat!("ui!: unknown property `", stringify!($property), "`"))
+    };
+
+    // Children, gathered into `[...]` until none are left
+    (@children [ $($built:expr),* ] $(,)?) => { vec![$($built),*] };
+    (@children [ $($built:expr),* ] { $child:expr } $(, $($rest:tt)*)?) => {
+        $crate::rendering::browser::ui_macro::ui!(@children [ $($built,)* $child ] $($($rest)*)?)
+    };
+    (@children [ $($built:expr),* ] $element:ident ( $($argument:tt)* ) $({ $($properties:tt)* })? $(, $($rest:tt)*)?) => {
+        $crate::rendering::browser::ui_macro::ui!(
+            @children [ $($built,)* $crate::rendering::browser::ui_macro::ui!($element ( $($argument)* ) $({ $($properties)* })?) ]
+            $($($rest)*)?
+        )
+    };
+}
+
+pub(crate) use ui;
\ No newline at end of file
@@ -1,24 +1,492 @@
-use super::elements::element::{Element, EventType};
+use std::{rc::Rc, time::Instant};
+
+use skia_safe::Point;
+use winit::{event::{MouseButton, TouchPhase}, keyboard::{Key, ModifiersState, NamedKey}};
+
+use super::{elements::{element::{Element, ElementSize, Event, EventType, Gesture, GesturePhase, KeyInput}, menu::{Menu, MenuResponse}, styles::Cursor}, drag_manager::DragManager, event_dispatcher::{EventDispatcher, HitPath}, focus_manager::FocusManager, gesture_recognizer::GestureRecognizer, layout::types::Position, reactive::runtime::ReactiveRuntime, shortcuts::{command::Command, shortcut_registry::{ShortcutMatch, ShortcutRegistry}}, state::app_state::AppState, styling::{style_resolver::StyleContext, stylesheet::{SelectorSubject, Stylesheet}, theme::Theme, theme_manager::ThemeManager}, tooltip_manager::TooltipManager};
 
 pub struct UIManager {
     root_element: Box<dyn Element>,
+    viewport: ElementSize,
+    context_menu: Option<Menu>,
+    tooltip_manager: TooltipManager,
+    stylesheet: Rc<Stylesheet>,
+    cursor: Option<Cursor>, // None while the pointer is over the page rather than the chrome
+    hovered_path: Option<HitPath>,
+    focused_path: Option<HitPath>,
+    captured_path: Option<HitPath>, // Where the pointer was pressed; moves and the release go there until it's released
+    drag_manager: DragManager,
+    shortcut_registry: ShortcutRegistry,
+    is_text_input_suppressed: bool, // The last key down ran or continued a shortcut, so the text it typed is dropped
+    unhandled_commands: Vec<Command>,
+    gesture_recognizer: GestureRecognizer,
+    primary_touch: Option<u64>, // The finger acting as the mouse until a gesture takes the touches over
+    gesture_path: Option<HitPath>, // Where the current gesture started; its events go there
+    unhandled_gestures: Vec<Gesture>,
 }
 
+/*
+ * Rendering and event dispatch happen in two layers:
+ *   A. The element tree, rendered first
+ *   B. The overlay layer (context menu, popups, option lists, tooltips), rendered after the whole tree
+ * so that it sits on top of it, and offered every event before the tree gets to see it
+ *
+ * Events the overlay layer leaves go to one element of the tree and its ancestors (see EventDispatcher):
+ *   - Pointer events to the element under the cursor, or to the one the pointer was pressed on
+ *     until it's released, followed by the clicks and drags they make, see DragManager
+ *   - Key and text events to the focused element, see FocusManager, unless they're shortcuts:
+ *     those run a command instead, see ShortcutRegistry
+ *   - Window focus changes to every element
+ *   - Gestures to the element they started on, see GestureRecognizer. The first finger of a
+ *     touch sequence acts as the mouse until a gesture takes the touches over
+ * The hovered and focused paths are kept here to send enter/leave and focus/blur events as they change.
+ */
 impl UIManager {
-    pub fn new(root_element: Box<dyn Element>) -> Self {
-        Self { root_element }
+    pub fn new(root_element: Box<dyn Element>, viewport: ElementSize) -> Self {
+        Self { root_element, viewport, context_menu: None, tooltip_manager: TooltipManager::new(), stylesheet: Rc::new(Stylesheet::empty()), cursor: None, hovered_path: None, focused_path: None, captured_path: None, drag_manager: DragManager::new(), shortcut_registry: ShortcutRegistry::with_defaults(), is_text_input_suppressed: false, unhandled_commands: vec![], gesture_recognizer: GestureRecognizer::new(), primary_touch: None, gesture_path: None, unhandled_gestures: vec![] }
     }
 
     pub fn render(&mut self, canvas: &skia_safe::Canvas) {
         self.root_element.render(canvas);
+        self.root_element.render_overlay(canvas, &self.viewport);
+
+        if let Some(context_menu) = &self.context_menu {
+            context_menu.render(canvas);
+        }
+
+        self.tooltip_manager.render(canvas, &self.viewport);
+    }
+
+    // Resolves styles and lays the whole tree out again, e.g. after a theme change
+    pub fn restyle(&mut self) {
+        self.root_element.resolve_styles(&StyleContext::root(self.stylesheet.clone())); // Cascade styles down before layout reads them
+        self.relayout();
+    }
+
+    // Lays the tree out again with the styles already resolved
+    fn relayout(&mut self) {
+        self.root_element.compute_allocation_plan(); // Start backwards recursion to plan space allocations
+        self.root_element.enact_allocation_plan( // Start forwards recursion to allocate space
+            Position { x: 0.0, y: 0.0 },
+            self.viewport.clone()
+        );
+    }
+
+    pub fn set_stylesheet(&mut self, stylesheet: Stylesheet) {
+        self.stylesheet = Rc::new(stylesheet);
+        self.restyle();
+    }
+
+    pub fn set_shortcut_registry(&mut self, shortcut_registry: ShortcutRegistry) {
+        self.shortcut_registry = shortcut_registry;
+    }
+
+    // Commands of shortcuts no element handled, left to the application, e.g. quitting
+    pub fn take_unhandled_commands(&mut self) -> Vec<Command> {
+        std::mem::take(&mut self.unhandled_commands)
+    }
+
+    // Gestures no element handled, left to the page, e.g. to scroll or zoom it
+    pub fn take_unhandled_gestures(&mut self) -> Vec<Gesture> {
+        std::mem::take(&mut self.unhandled_gestures)
+    }
+
+    pub fn set_theme(&mut self, theme: Theme) {
+        ThemeManager::set_current(theme);
+        self.restyle();
+    }
+
+    // Lets the elements bound to the application state follow it, then lays out again if they changed size
+    pub fn apply_state(&mut self, state: &AppState) {
+        self.change_tree(|root| UIManager::notify_state_changed(root, state));
+        self.relayout_if_needed();
     }
 
-    #[allow(dead_code)]
-    pub fn update(&mut self) {
-        self.root_element.update();
+    fn notify_state_changed(element: &mut dyn Element, state: &AppState) {
+        element.on_state_changed(state);
+        for index in element.get_event_child_indices() {
+            if let Some(child) = element.get_event_child_mut(index) {
+                UIManager::notify_state_changed(child, state);
+            }
+        }
+    }
+
+    // Brings elements bound to signals up to date if a signal they read changed; returns whether one did, so a redraw is needed
+    pub fn update(&mut self) -> bool {
+        if !ReactiveRuntime::take_has_stale_bindings() {
+            return false;
+        }
+        self.change_tree(|root| root.update());
+        self.relayout_if_needed();
+        true
+    }
+
+    /*
+     * Makes a change that may replace elements, like a bound Row rebuilding its children or a virtual
+     * list recycling the items scrolled out. The kept paths are child indices, so one going through
+     * replaced elements would lead to others that never got its enter or focus events; such paths
+     * are forgotten rather than followed. The elements they led to are gone, or were reset by their
+     * owner, so there's nothing to send them, and the next pointer event finds the hovered path again.
+     */
+    fn change_tree(&mut self, change: impl FnOnce(&mut dyn Element)) {
+        let root = self.root_element.as_ref();
+        let ids_before = [&self.hovered_path, &self.focused_path, &self.captured_path, &self.gesture_path]
+            .map(|path| path.as_deref().and_then(|path| UIManager::get_path_ids(root, path)));
+
+        change(self.root_element.as_mut());
+
+        let root = self.root_element.as_ref();
+        let paths = [&mut self.hovered_path, &mut self.focused_path, &mut self.captured_path, &mut self.gesture_path];
+        for (path, ids_before) in paths.into_iter().zip(ids_before) {
+            if path.as_deref().and_then(|path| UIManager::get_path_ids(root, path)) != ids_before {
+                *path = None;
+            }
+        }
+    }
+
+    // Ids of the elements along a path, or None if it no longer leads anywhere
+    fn get_path_ids(root: &dyn Element, path: &[usize]) -> Option<Vec<String>> {
+        let mut element = root;
+        let mut ids = vec![];
+        for index in path {
+            element = element.get_event_child(*index)?;
+            ids.push(element.get_id());
+        }
+        Some(ids)
     }
 
     pub fn handle_event(&mut self, cursor_position: skia_safe::Point, event_type: &EventType) {
-        self.root_element.handle_event(cursor_position, event_type);
+        if let EventType::Touch { id, phase } = event_type {
+            self.handle_touch(cursor_position, *id, *phase);
+            return;
+        }
+
+        match event_type {
+            EventType::MouseMove { .. } => self.tooltip_manager.on_cursor_moved(self.root_element.find_tooltip(cursor_position)),
+            EventType::MouseDown { .. } | EventType::KeyDown(_) | EventType::FocusLost => self.tooltip_manager.dismiss(),
+            _ => (),
+        }
+        if let EventType::KeyDown(_) = event_type {
+            self.is_text_input_suppressed = false;
+        }
+        if event_type.is_pointer_event() {
+            self.cursor = Some(Cursor::Default); // Overlays keep the default cursor; the tree picks its own below
+        }
+
+        if let Some(context_menu) = &mut self.context_menu {
+            let response = context_menu.handle_event(cursor_position, event_type, &self.viewport);
+            if !context_menu.is_open() {
+                self.context_menu = None;
+            }
+            if response != MenuResponse::Ignored {
+                self.on_overlay_event(cursor_position, event_type);
+                return;
+            }
+        }
+
+        if self.root_element.handle_overlay_event(cursor_position, event_type, &self.viewport) {
+            self.on_overlay_event(cursor_position, event_type);
+            return;
+        }
+
+        if event_type.is_press_of(MouseButton::Right) && self.open_context_menu(cursor_position) {
+            return;
+        }
+
+        if self.handle_shortcut(cursor_position, event_type) {
+            return;
+        }
+
+        self.dispatch_to_tree(cursor_position, event_type);
+        if event_type.is_pointer_event() {
+            // After dispatch, so that state styles and drags the event started are reflected
+            self.cursor = self.drag_manager.get_cursor()
+                .or_else(|| self.root_element.find_cursor(cursor_position));
+        }
+    }
+
+    // Cursor icon for the window, as of the last pointer event; None when it was over none of the chrome
+    pub fn get_cursor(&self) -> Option<Cursor> {
+        self.cursor
+    }
+
+    pub fn handle_cursor_left(&mut self) {
+        self.tooltip_manager.clear();
+        self.set_hovered_path(None, Point::new(f32::NAN, f32::NAN));
+        self.relayout_if_needed();
+    }
+
+    // Returns whether the element under the cursor had a context menu to open
+    fn open_context_menu(&mut self, cursor_position: Point) -> bool {
+        let Some(mut context_menu) = self.root_element.build_context_menu(cursor_position) else { return false; };
+        context_menu.open_at(cursor_position, &self.viewport);
+        self.context_menu = Some(context_menu);
+        true
+    }
+
+    /*
+     * Touches drive the gesture recognizer and, while no gesture has taken them over, the pointer:
+     *   A. The first finger down presses the left button where it lands, moves the pointer as it
+     *      moves and releases the button as it lifts, so taps click and drags drag
+     *   B. Once a pan, pinch or long press takes the touches over, the press is cancelled
+     *      without a click or a drop, and further fingers never touch the pointer
+     * The gestures go to the element under the first finger down, see `dispatch_gesture`.
+     */
+    fn handle_touch(&mut self, position: Point, id: u64, phase: TouchPhase) {
+        if phase == TouchPhase::Started && !self.gesture_recognizer.is_tracking() {
+            self.gesture_path = EventDispatcher::hit_test(self.root_element.as_ref(), position);
+            self.primary_touch = Some(id);
+        }
+
+        let was_taken_over = self.gesture_recognizer.has_taken_over();
+        let gestures = self.gesture_recognizer.on_touch(id, phase, position, Instant::now());
+        let is_taken_over = was_taken_over || self.gesture_recognizer.has_taken_over();
+
+        if self.primary_touch == Some(id) && !is_taken_over {
+            let modifiers = ModifiersState::empty();
+            match phase {
+                TouchPhase::Started => {
+                    self.handle_event(position, &EventType::MouseMove { modifiers });
+                    self.handle_event(position, &EventType::MouseDown { button: MouseButton::Left, modifiers });
+                }
+                TouchPhase::Moved => self.handle_event(position, &EventType::MouseMove { modifiers }),
+                TouchPhase::Ended => self.handle_event(position, &EventType::MouseUp { button: MouseButton::Left, modifiers }),
+                TouchPhase::Cancelled => self.cancel_touch_press(),
+            }
+        } else if !was_taken_over && is_taken_over && self.primary_touch.is_some() {
+            self.cancel_touch_press();
+        }
+        if matches!(phase, TouchPhase::Ended | TouchPhase::Cancelled) && self.primary_touch == Some(id) {
+            self.primary_touch = None;
+        }
+
+        let gesture_position = self.gesture_recognizer.get_position();
+        for gesture in gestures {
+            self.dispatch_gesture(gesture_position, gesture);
+        }
+    }
+
+    // Ends the press of the finger acting as the mouse without clicking or dropping anything
+    fn cancel_touch_press(&mut self) {
+        let no_position = Point::new(f32::NAN, f32::NAN);
+        self.drag_manager.on_focus_lost(self.root_element.as_mut(), no_position);
+        self.handle_event(no_position, &EventType::MouseUp { button: MouseButton::Left, modifiers: ModifiersState::empty() });
+        self.handle_cursor_left();
+    }
+
+    // A long press nobody handles opens the context menu like a right click; other gestures are left to the page
+    fn dispatch_gesture(&mut self, cursor_position: Point, gesture: Gesture) {
+        let mut event = Event::new(EventType::Gesture(gesture));
+        if let Some(path) = self.gesture_path.clone() {
+            // Pans and flings scroll virtual lists, which recycle the items scrolled out
+            self.change_tree(|root| EventDispatcher::dispatch(root, &path, cursor_position, &mut event));
+        }
+
+        if !event.is_handled() {
+            match gesture {
+                Gesture::LongPress => {
+                    self.open_context_menu(cursor_position);
+                }
+                _ => self.unhandled_gestures.push(gesture),
+            }
+        }
+        self.relayout_if_needed();
+    }
+
+    fn dispatch_to_tree(&mut self, cursor_position: Point, event_type: &EventType) {
+        let mut event = Event::new(event_type.clone());
+        match event_type {
+            EventType::MouseDown { .. } | EventType::MouseUp { .. } | EventType::MouseMove { .. } | EventType::MouseWheel { .. } => {
+                let hit_path = EventDispatcher::hit_test(self.root_element.as_ref(), cursor_position);
+                self.set_hovered_path(hit_path.clone(), cursor_position);
+
+                if let EventType::MouseDown { .. } = event_type {
+                    let focus_target = hit_path.as_deref().and_then(|path| FocusManager::find_click_target(self.root_element.as_ref(), path));
+                    self.set_focused_path(focus_target, false);
+                    self.captured_path = self.captured_path.take().or(hit_path.clone());
+                }
+                let target_path = match event_type {
+                    EventType::MouseWheel { .. } => hit_path,
+                    _ => self.captured_path.clone().or(hit_path),
+                };
+                if let Some(path) = &target_path {
+                    // The wheel scrolls virtual lists, which recycle the items scrolled out
+                    self.change_tree(|root| EventDispatcher::dispatch(root, path, cursor_position, &mut event));
+                }
+
+                match event_type {
+                    EventType::MouseDown { button, .. } => {
+                        if let Some(path) = target_path {
+                            self.drag_manager.on_press(*button, cursor_position, path, Instant::now());
+                        }
+                    }
+                    EventType::MouseMove { modifiers } => self.drag_manager.on_move(self.root_element.as_mut(), cursor_position, *modifiers),
+                    EventType::MouseUp { button, modifiers } => {
+                        let hit_path = EventDispatcher::hit_test(self.root_element.as_ref(), cursor_position);
+                        self.drag_manager.on_release(self.root_element.as_mut(), *button, cursor_position, hit_path.as_deref(), *modifiers);
+                        self.captured_path = None;
+                    }
+                    EventType::MouseWheel { .. } => {
+                        // Scrolling moves other elements under the cursor
+                        let hit_path = EventDispatcher::hit_test(self.root_element.as_ref(), cursor_position);
+                        self.set_hovered_path(hit_path, cursor_position);
+                    }
+                    _ => (),
+                }
+            }
+            EventType::KeyDown(KeyInput { logical_key: Key::Named(NamedKey::Escape), .. })
+                if self.drag_manager.cancel(self.root_element.as_mut(), cursor_position) => (),
+            EventType::KeyDown(_) | EventType::KeyUp(_) | EventType::TextInput(_) => {
+                let path = self.focused_path.clone().unwrap_or_default();
+                EventDispatcher::dispatch(self.root_element.as_mut(), &path, cursor_position, &mut event);
+
+                // Tab moves the focus unless the focused element used the key itself
+                if let EventType::KeyDown(KeyInput { logical_key: Key::Named(NamedKey::Tab), modifiers, .. }) = event_type {
+                    if !event.is_handled() {
+                        let next_path = FocusManager::find_next(self.root_element.as_ref(), self.focused_path.as_deref(), modifiers.shift_key());
+                        self.set_focused_path(next_path, true);
+                    }
+                }
+            }
+            // From the touchpad, which has no touches to start from, so it starts where the pointer is
+            EventType::Gesture(gesture) => {
+                if let Gesture::Pan { phase: GesturePhase::Started, .. } | Gesture::Pinch { phase: GesturePhase::Started, .. } = gesture {
+                    self.gesture_path = EventDispatcher::hit_test(self.root_element.as_ref(), cursor_position);
+                }
+                self.dispatch_gesture(cursor_position, *gesture);
+            }
+            EventType::FocusGained | EventType::FocusLost => {
+                if let EventType::FocusLost = event_type {
+                    self.captured_path = None; // The release may happen in another window
+                    self.drag_manager.on_focus_lost(self.root_element.as_mut(), cursor_position);
+                }
+                EventDispatcher::broadcast(self.root_element.as_mut(), &mut event);
+            }
+            // Handled before anything else, see `handle_touch`
+            EventType::Touch { .. } => (),
+            // Sent per element as the paths change, never dispatched as they come
+            EventType::MouseEnter | EventType::MouseLeave | EventType::Focus { .. } | EventType::Blur | EventType::Command(_) => (),
+            // Made by DragManager out of the pointer events
+            EventType::Click { .. } | EventType::DragStart { .. } | EventType::DragMove { .. } | EventType::DragEnd { .. }
+            | EventType::DragEnter | EventType::DragLeave | EventType::DragOver { .. } | EventType::Drop { .. } => (),
+        }
+        self.relayout_if_needed();
+    }
+
+    // Returns whether the event was a shortcut's key down, or the text it typed, and so is used up
+    fn handle_shortcut(&mut self, cursor_position: Point, event_type: &EventType) -> bool {
+        match event_type {
+            EventType::KeyDown(key_input) => {
+                let focused_subjects = self.get_focused_subjects();
+                let shortcut_match = self.shortcut_registry.handle_key(key_input, &focused_subjects, Instant::now());
+                if let ShortcutMatch::Command(command) = shortcut_match {
+                    self.dispatch_command(cursor_position, command);
+                }
+                self.is_text_input_suppressed = shortcut_match != ShortcutMatch::None;
+                self.is_text_input_suppressed
+            }
+            EventType::TextInput(_) => std::mem::take(&mut self.is_text_input_suppressed),
+            _ => false,
+        }
+    }
+
+    fn dispatch_command(&mut self, cursor_position: Point, command: Command) {
+        let mut event = Event::new(EventType::Command(command));
+        let path = self.focused_path.clone().unwrap_or_default();
+        EventDispatcher::dispatch(self.root_element.as_mut(), &path, cursor_position, &mut event);
+        if !event.is_handled() {
+            EventDispatcher::broadcast(self.root_element.as_mut(), &mut event);
+        }
+
+        if !event.is_handled() {
+            self.unhandled_commands.push(command);
+        }
+        self.relayout_if_needed();
+    }
+
+    // From the root down to the focused element, for the scopes of shortcuts
+    fn get_focused_subjects(&self) -> Vec<SelectorSubject> {
+        let Some(focused_path) = &self.focused_path else { return vec![]; };
+        let mut element: &dyn Element = self.root_element.as_ref();
+        let mut subjects = vec![SelectorSubject::of(element)];
+
+        for index in focused_path {
+            let Some(child) = element.get_event_child(*index) else { break; };
+            element = child;
+            subjects.push(SelectorSubject::of(element));
+        }
+        subjects
+    }
+
+    // A pointer event the overlay layer took was over it rather than the tree, so nothing there stays hovered
+    fn on_overlay_event(&mut self, cursor_position: Point, event_type: &EventType) {
+        if event_type.is_pointer_event() {
+            self.set_hovered_path(None, cursor_position);
+            self.relayout_if_needed();
+        }
+    }
+
+    fn set_hovered_path(&mut self, hovered_path: Option<HitPath>, cursor_position: Point) {
+        let previous_path = std::mem::replace(&mut self.hovered_path, hovered_path);
+        EventDispatcher::update_path(
+            self.root_element.as_mut(),
+            previous_path.as_deref(),
+            self.hovered_path.as_deref(),
+            cursor_position,
+            EventType::MouseLeave,
+            EventType::MouseEnter,
+        );
+    }
+
+    // Focusing the focused element again only updates whether its focus ring shows
+    fn set_focused_path(&mut self, focused_path: Option<HitPath>, is_visible: bool) {
+        let no_position = Point::new(f32::NAN, f32::NAN);
+        if let Some(previous_path) = self.focused_path.take().filter(|previous_path| Some(previous_path) != focused_path.as_ref()) {
+            EventDispatcher::dispatch_to_target(self.root_element.as_mut(), &previous_path, no_position, &mut Event::new(EventType::Blur));
+        }
+        if let Some(path) = &focused_path {
+            EventDispatcher::dispatch_to_target(self.root_element.as_mut(), path, no_position, &mut Event::new(EventType::Focus { is_visible }));
+        }
+        self.focused_path = focused_path;
+    }
+
+    // Elements restyle themselves on interaction state changes; only size changes need a new layout
+    fn relayout_if_needed(&mut self) {
+        if self.root_element.take_needs_relayout() {
+            self.relayout();
+        }
+    }
+
+    // Timers
+    pub fn next_timer_deadline(&self) -> Option<Instant> {
+        [self.tooltip_manager.next_deadline(), self.gesture_recognizer.next_deadline()].into_iter().flatten().min()
+    }
+
+    // Returns whether anything changed and a redraw is needed
+    pub fn on_timer(&mut self, now: Instant) -> bool {
+        let gestures = self.gesture_recognizer.on_timer(now);
+        let has_gestures = !gestures.is_empty();
+        if has_gestures && self.primary_touch.is_some() {
+            self.cancel_touch_press(); // A long press took the touches over
+        }
+        let gesture_position = self.gesture_recognizer.get_position();
+        for gesture in gestures {
+            self.dispatch_gesture(gesture_position, gesture);
+        }
+
+        self.tooltip_manager.on_timer(now) || has_gestures
+    }
+
+    // Animations
+    // Steps running animations; returns whether any ran, so another frame is needed
+    pub fn advance_animations(&mut self, now: Instant) -> bool {
+        let mut is_animating = false;
+        self.change_tree(|root| is_animating = root.advance_animations(now)); // Momentum scrolls virtual lists
+        self.relayout_if_needed();
+        is_animating
+    }
+
+    pub fn set_viewport(&mut self, viewport: ElementSize) {
+        self.viewport = viewport;
     }
 }
@@ -1,17 +1,28 @@
-use crate::rendering::browser::{elements::element::EventType, ui_manager::UIManager};
+use crate::rendering::browser::{elements::{element::EventType, styles::Cursor}, reactive::effect, shortcuts::{command::Command, keymap_parser::{KeymapEntry, KeymapParser}, shortcut_registry::ShortcutRegistry}, state::{app_signals::AppSignals, message::{Effect, Message}, message_queue::MessageQueue, store::Store}, ui_manager::UIManager};
 use skia_safe::{
     gpu::{self, gl::FramebufferInfo, SurfaceOrigin},
     ColorType, Surface,
 };
+use std::{cell::RefCell, env, path::PathBuf, rc::Rc, time::Instant};
 use winit::window::Window;
 use skia_safe::gpu::DirectContext;
 
-use super::{browser::{elements::element::{Element, ElementSize}, layout::types::Position, ui_body::get_ui_body}, webpage_renderer::WebPageRenderer};
+use super::{browser::{elements::element::{Element, ElementSize}, internal::font_manager::FontManager, styling::{stylesheet::Stylesheet, theme::{ColorToken, Theme}, theme_manager::ThemeManager}, ui_body::{get_initial_state, get_ui_body}}, webpage_renderer::WebPageRenderer};
+
+const CHROME_STYLESHEET_PATH: &str = "styles/chrome.css";
+const BUNDLED_FONTS_PATH: &str = "assets/fonts";
+const KEYMAP_PATH: &str = "config/keymap.conf";
+const WINDOW_TITLE: &str = "Cartan";
 
 pub struct Renderer {
     pub surface: Surface,
     ui_manager: UIManager,
-    web_page_renderer: WebPageRenderer
+    web_page_renderer: WebPageRenderer,
+    store: Store,
+    app_signals: AppSignals,
+    commands: Vec<Command>, // Commands messages asked of the application, e.g. quitting
+    window_title: Rc<RefCell<Option<String>>>, // Set when it changes, until the application takes it
+    _window_title_effect: effect::Effect,
 }
 
 impl Renderer {
@@ -23,25 +34,84 @@ impl Renderer {
             sample_count,
             stencil_bits,
         );
-        let mut ui_body: Box<dyn Element> = get_ui_body();
+        let store = Store::new(get_initial_state());
+        let app_signals = AppSignals::new(store.get_state());
+        let ui_body: Box<dyn Element> = get_ui_body(store.get_state(), &app_signals);
         let screen_size = window.inner_size();
-        ui_body.compute_allocation_plan(); // Start backwards recursion to plan space allocations
-        ui_body.enact_allocation_plan( // Start forwards recursion to allocate space
-            Position { x: 0.0, y: 0.0 },
-            ElementSize { width: screen_size.width as f32, height: screen_size.height as f32 
-        });
-
+        let viewport = ElementSize { width: screen_size.width as f32, height: screen_size.height as f32 };
+        FontManager::load_bundled_fonts(&PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(BUNDLED_FONTS_PATH));
+        let mut ui_manager = UIManager::new(ui_body, viewport);
+        ui_manager.set_stylesheet(Self::load_chrome_stylesheet());
+        ui_manager.set_shortcut_registry(Self::load_shortcut_registry());
+        let (window_title, window_title_effect) = Self::follow_window_title(&app_signals);
 
         Self { 
             surface,
-            ui_manager: UIManager::new(ui_body),
+            ui_manager,
             web_page_renderer: WebPageRenderer::new(),
+            store,
+            app_signals,
+            commands: vec![],
+            window_title,
+            _window_title_effect: window_title_effect,
+        }
+    }
+
+    // The window title names the active page, and follows it as tabs switch and pages load
+    fn follow_window_title(app_signals: &AppSignals) -> (Rc<RefCell<Option<String>>>, effect::Effect) {
+        let window_title = Rc::new(RefCell::new(None));
+        let (active_url, pending_title) = (app_signals.active_url.clone(), window_title.clone());
+        let window_title_effect = effect::Effect::new(move || {
+            let title = match active_url.get() {
+                Some(url) => format!("{} - {}", url, WINDOW_TITLE),
+                None => WINDOW_TITLE.to_string(),
+            };
+            *pending_title.borrow_mut() = Some(title);
+        });
+        (window_title, window_title_effect)
+    }
+
+    // The chrome stylesheet, from CARTAN_STYLESHEET if set; a broken one is reported and ignored
+    fn load_chrome_stylesheet() -> Stylesheet {
+        let path = env::var_os("CARTAN_STYLESHEET")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(CHROME_STYLESHEET_PATH));
+
+        Stylesheet::load(&path).unwrap_or_else(|error| {
+            eprintln!("Ignoring stylesheet {}: {}", path.display(), error);
+            Stylesheet::empty()
+        })
+    }
+
+    // The default shortcuts with the keymap from CARTAN_KEYMAP, or config/keymap.conf, applied over them
+    fn load_shortcut_registry() -> ShortcutRegistry {
+        let mut shortcut_registry = ShortcutRegistry::with_defaults();
+        let conflicts = shortcut_registry.apply_keymap(Self::load_keymap());
+        for conflict in conflicts {
+            eprintln!("Keymap conflict: {}", conflict);
         }
+        shortcut_registry
+    }
+
+    // A broken keymap is reported and ignored; the default one may be missing
+    fn load_keymap() -> Vec<KeymapEntry> {
+        let (path, is_required) = match env::var_os("CARTAN_KEYMAP") {
+            Some(path) => (PathBuf::from(path), true),
+            None => (PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(KEYMAP_PATH), false),
+        };
+        if !is_required && !path.exists() {
+            return vec![];
+        }
+
+        KeymapParser::load(&path).unwrap_or_else(|error| {
+            eprintln!("Ignoring keymap {}: {}", path.display(), error);
+            vec![]
+        })
     }
 
     pub fn render_frame(&mut self, _gr_context: &mut DirectContext) {
         let canvas = self.surface.canvas();
-        canvas.clear(skia_safe::Color::WHITE);
+        canvas.clear(ThemeManager::current().color(ColorToken::Surface));
 
         self.ui_manager.render(canvas);
 
@@ -50,7 +120,103 @@ impl Renderer {
 
     pub fn handle_event(&mut self, cursor_position: skia_safe::Point, event_type: EventType) {
         self.ui_manager.handle_event(cursor_position, &event_type);
+        self.forward_unhandled_gestures();
         self.web_page_renderer.handle_event(cursor_position, event_type);
+        self.process_messages();
+        self.ui_manager.update();
+    }
+
+    /*
+     * Applies the messages element callbacks sent, see Store. Carrying out their effects can send
+     * more, like a page reporting it loaded, so it goes on until none are left. Returns whether
+     * any were applied, so a redraw is needed.
+     */
+    fn process_messages(&mut self) -> bool {
+        let mut has_messages = false;
+        loop {
+            let messages = MessageQueue::take();
+            if messages.is_empty() {
+                break;
+            }
+            has_messages = true;
+
+            for message in messages {
+                match self.store.update(message) {
+                    Some(Effect::LoadPage(url)) => self.web_page_renderer.load(&url),
+                    Some(Effect::ReloadPage) => self.web_page_renderer.reload(),
+                    Some(Effect::Quit) => self.commands.push(Command::Quit),
+                    None => (),
+                }
+            }
+        }
+
+        if has_messages {
+            self.ui_manager.apply_state(self.store.get_state());
+            self.app_signals.update(self.store.get_state());
+        }
+        has_messages
+    }
+
+    // Gestures the browser chrome didn't handle scroll and zoom the page
+    fn forward_unhandled_gestures(&mut self) {
+        for gesture in self.ui_manager.take_unhandled_gestures() {
+            self.web_page_renderer.handle_gesture(gesture);
+        }
+    }
+
+    // Commands of shortcuts the browser chrome didn't handle, and those messages asked for
+    pub fn take_unhandled_commands(&mut self) -> Vec<Command> {
+        let mut commands = self.ui_manager.take_unhandled_commands();
+        commands.append(&mut self.commands);
+        commands
+    }
+
+    pub fn reload_page(&mut self) {
+        MessageQueue::send(Message::Reload);
+        self.process_messages();
+        self.ui_manager.update();
+    }
+
+    // Restyles and lays out the browser chrome with the theme; the caller requests the redraw
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.ui_manager.set_theme(theme);
+    }
+
+    pub fn handle_cursor_left(&mut self) {
+        self.ui_manager.handle_cursor_left();
+    }
+
+    // The chrome's cursor, or the page's past it. While the active tab loads, Progress stands in
+    // for the default cursor over both, and cursors elements chose for themselves are kept
+    pub fn get_cursor(&self) -> Cursor {
+        let cursor = self.ui_manager.get_cursor().unwrap_or_else(|| self.web_page_renderer.get_cursor());
+        let is_loading = self.store.get_state().get_active_tab().is_some_and(|tab| tab.is_loading);
+        match cursor {
+            Cursor::Default if is_loading => Cursor::Progress,
+            cursor => cursor,
+        }
+    }
+
+    // The window title, if it changed since the last call
+    pub fn take_window_title(&mut self) -> Option<String> {
+        self.window_title.borrow_mut().take()
+    }
+
+    pub fn next_timer_deadline(&self) -> Option<Instant> {
+        self.ui_manager.next_timer_deadline()
+    }
+
+    pub fn on_timer(&mut self, now: Instant) -> bool {
+        let needs_redraw = self.ui_manager.on_timer(now);
+        self.forward_unhandled_gestures();
+        let has_messages = self.process_messages();
+        self.ui_manager.update() || has_messages || needs_redraw
+    }
+
+    pub fn advance_animations(&mut self, now: Instant) -> bool {
+        let is_chrome_animating = self.ui_manager.advance_animations(now);
+        let is_page_animating = self.web_page_renderer.advance_animations(now);
+        is_chrome_animating || is_page_animating
     }
     
     fn create_surface(
@@ -65,6 +231,9 @@ impl Renderer {
 
     pub fn resize_surface(&mut self, window: &Window, gr_context: &mut DirectContext, fb_info: FramebufferInfo, sample_count: usize, stencil_bits: usize) {
         self.surface = Renderer::create_or_resize_surface(window, gr_context, fb_info, sample_count, stencil_bits);
+
+        let screen_size = window.inner_size();
+        self.ui_manager.set_viewport(ElementSize { width: screen_size.width as f32, height: screen_size.height as f32 });
     }
 
     fn create_or_resize_surface(
@@ -1,13 +1,22 @@
-use super::browser::elements::element::EventType;
+use std::time::Instant;
 
+use super::browser::{animation::momentum::Momentum, elements::{element::{EventType, Gesture}, styles::Cursor}, state::{message::Message, message_queue::MessageQueue}};
 
-pub struct WebPageRenderer {
+const MIN_ZOOM: f32 = 0.25;
+const MAX_ZOOM: f32 = 5.0;
 
+#[allow(dead_code)] // The viewport is only read once pages render
+pub struct WebPageRenderer {
+    url: Option<String>,
+    scroll_x: f32,
+    scroll_y: f32,
+    zoom: f32,
+    momentum: Option<Momentum>, // Scrolling on after a fling
 }
 
 impl WebPageRenderer {
     pub fn new() -> Self {
-        Self {}
+        Self { url: None, scroll_x: 0.0, scroll_y: 0.0, zoom: 1.0, momentum: None }
     }
 
     pub fn render(&self, canvas: &skia_safe::Canvas) {
@@ -18,7 +27,56 @@ impl WebPageRenderer {
         // Update the webpage
     }
 
+    // Nothing is fetched yet, so pages finish loading straight away, titled with their URL
+    pub fn load(&mut self, url: &str) {
+        self.url = Some(url.to_string());
+        self.scroll_x = 0.0;
+        self.scroll_y = 0.0;
+        self.momentum = None;
+        MessageQueue::send(Message::PageLoaded { title: url.to_string() });
+    }
+
+    pub fn reload(&self) {
+        // Reload the webpage
+        if let Some(url) = &self.url {
+            MessageQueue::send(Message::PageLoaded { title: url.clone() });
+        }
+    }
+
     pub fn handle_event(&self, cursor_position: skia_safe::Point, event_type: EventType) {
         // Handle the event
     }
+
+    // Cursor over the page; pages have no text or links to show Text or Pointer over until they render
+    pub fn get_cursor(&self) -> Cursor {
+        Cursor::Default
+    }
+
+    // Gestures the browser chrome left: pans and flings scroll the page, pinches zoom it
+    pub fn handle_gesture(&mut self, gesture: Gesture) {
+        match gesture {
+            Gesture::Pan { delta_x, delta_y, .. } => {
+                self.momentum = None;
+                self.scroll_by(-delta_x, -delta_y);
+            }
+            Gesture::Pinch { scale, .. } => self.zoom = (self.zoom * scale).clamp(MIN_ZOOM, MAX_ZOOM),
+            Gesture::Fling { velocity_x, velocity_y } => self.momentum = Some(Momentum::new(-velocity_x, -velocity_y, Instant::now())),
+            Gesture::LongPress => (),
+        }
+    }
+
+    // Returns whether the page is still scrolling, so another frame is needed
+    pub fn advance_animations(&mut self, now: Instant) -> bool {
+        let Some(momentum) = &mut self.momentum else { return false; };
+        match momentum.step(now) {
+            Some((delta_x, delta_y)) => self.scroll_by(delta_x, delta_y),
+            None => self.momentum = None,
+        }
+        self.momentum.is_some()
+    }
+
+    fn scroll_by(&mut self, delta_x: f32, delta_y: f32) {
+        self.scroll_x = (self.scroll_x + delta_x).max(0.0);
+        self.scroll_y = (self.scroll_y + delta_y).max(0.0);
+    }
 }
\ No newline at end of file